@@ -0,0 +1,204 @@
+//! Consistency between the decomposed acceleration sensors and the total acceleration.
+//!
+//! The sensor fusion splits the measured acceleration into [`Gravity`](SensorId::Gravity) and
+//! [`LinearAcceleration`](SensorId::LinearAcceleration), so the accelerometer reading is their sum.
+//! [`total_acceleration`], [`linear_acceleration`] and [`gravity`] convert between both views.
+//! [`AccelerationJoiner`] pairs the samples of both sensors by their timestamps, so analytics can use the decomposed
+//! and the total acceleration without enabling the accelerometer separately.
+//!
+//! ```ignore
+//! let mut joiner = AccelerationJoiner::new(bhi.scaler()?);
+//! bhi.drain_fifo(&mut buf, usize::MAX, |event| {
+//!     if let Some(sample) = joiner.observe(&event) {
+//!         println!("{} ticks: {:?}", sample.ticks, sample.total());
+//!     }
+//! })?;
+//! ```
+
+use crate::{
+    fifo::Event,
+    math::Vector,
+    parameters::sensors::SensorId,
+    scaled::{Reading, Scaler},
+    timestamp::TimestampTracker,
+};
+
+/// The acceleration measured by the accelerometer, i.e. the sum of gravity and the linear acceleration.
+pub fn total_acceleration(gravity: Vector<f32>, linear: Vector<f32>) -> Vector<f32> {
+    gravity.elem_add(linear)
+}
+
+/// The linear acceleration contained in the `total` acceleration.
+pub fn linear_acceleration(total: Vector<f32>, gravity: Vector<f32>) -> Vector<f32> {
+    total.elem_sub(gravity)
+}
+
+/// The gravity contained in the `total` acceleration.
+pub fn gravity(total: Vector<f32>, linear: Vector<f32>) -> Vector<f32> {
+    total.elem_sub(linear)
+}
+
+/// A gravity and a linear acceleration sample paired by [`AccelerationJoiner`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccelerationSample {
+    /// The timestamp of the newer of both samples in ticks.
+    pub ticks: u32,
+    /// Whether the samples were read from the wakeup FIFO.
+    pub wakeup: bool,
+    pub gravity: Vector<f32>,
+    pub linear: Vector<f32>,
+}
+
+impl AccelerationSample {
+    /// The acceleration an accelerometer would have measured, see [`total_acceleration`].
+    pub fn total(&self) -> Vector<f32> {
+        total_acceleration(self.gravity, self.linear)
+    }
+}
+
+/// The index of the gravity and the linear acceleration in the pending samples.
+const GRAVITY: usize = 0;
+const LINEAR: usize = 1;
+
+/// An unpaired sample and its timestamp.
+type Pending = Option<(Vector<f32>, u32)>;
+
+/// Pairs the samples of [`Gravity`](SensorId::Gravity) and [`LinearAcceleration`](SensorId::LinearAcceleration).
+///
+/// Both sensors are scaled with the given [`Scaler`]. A sample is paired with the latest sample of the other sensor
+/// from the same FIFO if their timestamps differ by at most the configured skew (0 by default, i.e. both sensors have
+/// to be sampled at the same time). Samples which can not be paired are dropped once a newer sample of the same sensor
+/// arrives.
+///
+/// The timestamps are reconstructed from the timestamp events, so all events have to be passed to
+/// [`observe`](Self::observe).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccelerationJoiner {
+    scaler: Scaler,
+    timestamps: TimestampTracker,
+    max_skew: u32,
+    /// The latest unpaired samples and their timestamps, by FIFO (non-wakeup, wakeup) and sensor.
+    pending: [[Pending; 2]; 2],
+}
+
+impl AccelerationJoiner {
+    pub fn new(scaler: Scaler) -> Self {
+        Self {
+            scaler,
+            timestamps: TimestampTracker::new(),
+            max_skew: 0,
+            pending: [[None; 2]; 2],
+        }
+    }
+
+    /// Sets the largest difference (in ticks) between the timestamps of two samples which are paired.
+    pub fn with_max_skew(mut self, ticks: u32) -> Self {
+        self.max_skew = ticks;
+        self
+    }
+
+    /// Processes an event and returns a pair once both sensors reported a sample.
+    pub fn observe(&mut self, event: &Event) -> Option<AccelerationSample> {
+        let ticks = self.timestamps.update(event);
+        let sensor = match event.id().non_wakeup_variant() {
+            SensorId::Gravity => GRAVITY,
+            SensorId::LinearAcceleration => LINEAR,
+            _ => return None,
+        };
+        let Some(Reading::Vector(vector, _)) = self.scaler.scale(event) else {
+            return None;
+        };
+        let wakeup = event.id().is_wakeup();
+        let pending = &mut self.pending[wakeup as usize];
+        let other = match pending[1 - sensor] {
+            Some((other, other_ticks)) if ticks.wrapping_sub(other_ticks) <= self.max_skew => other,
+            _ => {
+                pending[sensor] = Some((vector, ticks));
+                return None;
+            }
+        };
+        pending[1 - sensor] = None;
+        let (gravity, linear) = if sensor == GRAVITY {
+            (vector, other)
+        } else {
+            (other, vector)
+        };
+        Some(AccelerationSample {
+            ticks,
+            wakeup,
+            gravity,
+            linear,
+        })
+    }
+
+    /// The timestamps reconstructed from the events seen so far.
+    pub fn timestamps(&self) -> &TimestampTracker {
+        &self.timestamps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fifo::{SensorData, SensorStatus};
+
+    fn vector(id: SensorId, v: [i16; 3]) -> Event {
+        Event::new(id, SensorData::VectorStatus(Vector(v), SensorStatus::High))
+    }
+
+    #[test]
+    fn decomposition() {
+        let g = Vector([0.0, 0.0, 9.81]);
+        let linear = Vector([1.0, -2.0, 0.5]);
+        let total = total_acceleration(g, linear);
+        assert_eq!(total, Vector([1.0, -2.0, 10.31]));
+        assert_eq!(linear_acceleration(total, g), linear);
+        assert_eq!(gravity(total, linear), g);
+    }
+
+    #[test]
+    fn join_by_timestamp() {
+        // 1 LSB = 1/2048 g with a range of 16 g
+        let scaler = Scaler::new();
+        let lsb = scaler.scale_of(SensorId::Gravity).unwrap();
+        let mut joiner = AccelerationJoiner::new(scaler).with_max_skew(10);
+        let timestamp = |ticks| Event::new(SensorId::TimestampLsw, SensorData::Scalar(ticks));
+
+        assert_eq!(joiner.observe(&timestamp(100)), None);
+        assert_eq!(
+            joiner.observe(&vector(SensorId::Gravity, [0, 0, 2048])),
+            None
+        );
+        // Same time, but from the wakeup FIFO
+        assert_eq!(
+            joiner.observe(&vector(SensorId::LinearAccelerationWakeup, [1, 0, 0])),
+            None
+        );
+        assert_eq!(joiner.observe(&timestamp(105)), None);
+        let sample = joiner
+            .observe(&vector(SensorId::LinearAcceleration, [0, 1024, 0]))
+            .unwrap();
+        assert_eq!(sample.ticks, 105);
+        assert!(!sample.wakeup);
+        assert_eq!(sample.gravity, Vector([0.0, 0.0, 2048.0 * lsb]));
+        assert_eq!(sample.total(), Vector([0.0, 1024.0 * lsb, 2048.0 * lsb]));
+
+        // Too far apart
+        assert_eq!(
+            joiner.observe(&vector(SensorId::Gravity, [0, 0, 2048])),
+            None
+        );
+        assert_eq!(joiner.observe(&timestamp(200)), None);
+        assert_eq!(
+            joiner.observe(&vector(SensorId::LinearAcceleration, [0, 0, 0])),
+            None
+        );
+        assert!(joiner
+            .observe(&vector(SensorId::Gravity, [0, 0, 2048]))
+            .is_some());
+        assert_eq!(
+            joiner.observe(&vector(SensorId::Accelerometer, [0, 0, 2048])),
+            None
+        );
+    }
+}
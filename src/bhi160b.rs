@@ -0,0 +1,114 @@
+//! Features that are only available on the BHI160B.
+//!
+//! The BHI160B extends the System parameter page of the original BHI160.
+//! Use [`Bhi160::bhi160b`](crate::Bhi160::bhi160b) to access these features after the chip variant has been checked.
+
+use core::fmt;
+
+use modular_bitfield::{bitfield, specifiers::*};
+
+use crate::{
+    interface::Interface,
+    parameters::{sensors::SensorId, Parameter, ParameterPage},
+    Bhi160, Error,
+};
+
+/// A bitmap of the physical sensors attached to the BHI160B.
+///
+/// Bit `n` is set if the physical sensor with the Sensor Type `n` is present.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PhysicalSensorsPresent(pub u64);
+
+impl fmt::Debug for PhysicalSensorsPresent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries((0..64).filter(|&id| self.is_present(id)))
+            .finish()
+    }
+}
+
+impl PhysicalSensorsPresent {
+    /// Returns `true` if the physical sensor with the given Sensor Type is present.
+    pub fn is_present(&self, sensor_type: u8) -> bool {
+        sensor_type < 64 && self.0 & (1 << sensor_type) != 0
+    }
+}
+
+impl From<[u8; 8]> for PhysicalSensorsPresent {
+    fn from(bytes: [u8; 8]) -> Self {
+        Self(u64::from_le_bytes(bytes))
+    }
+}
+
+impl Parameter for PhysicalSensorsPresent {
+    const PAGE: ParameterPage = ParameterPage::System;
+    const PARAM: u8 = 32;
+    const SIZE: usize = 8;
+}
+
+/// The Physical Sensor Information of a physical sensor (System page, parameter 32 + Sensor Type).
+///
+/// The parameter is read-only, the configuration of the physical sensors (e.g. the oversampling of a barometer) is
+/// chosen by the firmware. Their rate follows the virtual sensors using them, see
+/// [`Bhi160::configure_sensor`](crate::Bhi160::configure_sensor). Only the leading fields are decoded.
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PhysicalSensorInfo {
+    /// The Sensor Type of the physical sensor, 0 if it is not present.
+    pub sensor_type: u8,
+    /// Id of the driver.
+    pub driver_id: u8,
+    /// The version of the driver.
+    pub driver_version: u8,
+    /// Current consumption in 0.1mA/LSB.
+    pub current: u8,
+    /// The current dynamic range of the sensor.
+    pub dynamic_range: u16,
+    #[skip]
+    __: B80,
+}
+
+/// Provides access to the features only available on the BHI160B.
+///
+/// Obtained by [`Bhi160::bhi160b`].
+pub struct Bhi160B<'a, IF>
+where
+    IF: Interface,
+{
+    bhi: &'a mut Bhi160<IF>,
+}
+
+impl<'a, IF> Bhi160B<'a, IF>
+where
+    IF: Interface,
+{
+    pub(crate) fn new(bhi: &'a mut Bhi160<IF>) -> Self {
+        Self { bhi }
+    }
+
+    /// Read which physical sensors are attached to the BHI160B.
+    pub fn physical_sensors_present(&mut self) -> Result<PhysicalSensorsPresent, Error<IF::Error>> {
+        self.bhi.read_param()
+    }
+
+    /// Read the information of a physical sensor, e.g. [`SensorId::Pressure`] for the barometer.
+    ///
+    /// Fails with [`Error::Unsupported`] if `id` is no physical Sensor Type (1-31).
+    pub fn physical_sensor_info(
+        &mut self,
+        id: SensorId,
+    ) -> Result<PhysicalSensorInfo, Error<IF::Error>> {
+        if !matches!(id as u8, 1..=31) {
+            return Err(Error::Unsupported);
+        }
+        let mut buf = [0; 16];
+        self.bhi
+            .read_param_raw(ParameterPage::System, 32 + id as u8, &mut buf)?;
+        Ok(PhysicalSensorInfo::from_bytes(buf))
+    }
+
+    /// Access the underlying driver for functionality shared by all chip variants.
+    pub fn inner(&mut self) -> &mut Bhi160<IF> {
+        self.bhi
+    }
+}
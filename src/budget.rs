@@ -0,0 +1,224 @@
+//! Estimation of the bus bandwidth needed to drain the FIFO.
+//!
+//! High sample rates (e.g. the gyroscope at 200 Hz or more) can produce more data than a slow bus is able
+//! to transfer. [`BusBudget`] compares the data rate of a proposed sensor configuration with the throughput
+//! of the interface before the configuration is applied.
+//!
+//! ```ignore
+//! let budget = BusBudget::i2c(400_000);
+//! let load = budget.check(&[(SensorId::Gyroscope, 400), (SensorId::RotationVector, 100)])?;
+//! ```
+//!
+//! [`FifoBudget`] summarizes how much of the FIFOs the firmware reserves for each sensor, i.e. how long a sensor can
+//! be buffered before its samples are lost or displace the samples of other sensors.
+
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::parameters::sensors::{SensorId, SensorInfo};
+
+/// The share of the throughput above which [`BusBudget::check`] logs a warning, in percent.
+const WARN_UTILIZATION: u32 = 80;
+
+/// The throughput of the interface to the BHI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BusBudget {
+    bytes_per_second: u32,
+}
+
+impl BusBudget {
+    /// A bus which can transfer the given number of bytes per second.
+    pub const fn new(bytes_per_second: u32) -> Self {
+        Self { bytes_per_second }
+    }
+
+    /// An I2C bus with the given clock. Every byte takes 9 clocks (including the acknowledge).
+    pub const fn i2c(clock_hz: u32) -> Self {
+        Self::new(clock_hz / 9)
+    }
+
+    /// An SPI bus with the given clock.
+    pub const fn spi(clock_hz: u32) -> Self {
+        Self::new(clock_hz / 8)
+    }
+
+    pub const fn bytes_per_second(&self) -> u32 {
+        self.bytes_per_second
+    }
+
+    /// Estimates the load caused by the given sensors and their sample rates (in Hz).
+    ///
+    /// Besides the events themselves this accounts for one timestamp event per sample of the fastest sensor
+    /// in each FIFO, which is the worst case.
+    pub fn estimate(&self, sensors: &[(SensorId, u16)]) -> BusLoad {
+        let mut required = 0;
+        let mut max_rate = [0; 2];
+        for &(id, rate) in sensors {
+            required += id.event_size() as u32 * rate as u32;
+            let fifo = &mut max_rate[id.is_wakeup() as usize];
+            *fifo = (*fifo).max(rate as u32);
+        }
+        required += max_rate[0] * SensorId::TimestampLsw.event_size() as u32;
+        required += max_rate[1] * SensorId::TimestampLswWakeup.event_size() as u32;
+        BusLoad {
+            required_bytes_per_second: required,
+            available_bytes_per_second: self.bytes_per_second,
+        }
+    }
+
+    /// Like [`estimate`](Self::estimate) but fails if the bus can not keep up with the sensors.
+    ///
+    /// With the `log` feature a warning is logged if the load exceeds 80% of the throughput.
+    pub fn check(&self, sensors: &[(SensorId, u16)]) -> Result<BusLoad, BusLoad> {
+        let load = self.estimate(sensors);
+        if !load.fits() {
+            return Err(load);
+        }
+        #[cfg(feature = "log")]
+        if load.is_high() {
+            log::warn!(
+                utilization_percent = load.utilization();
+                "Sensor configuration uses most of the bus throughput"
+            );
+        }
+        Ok(load)
+    }
+}
+
+/// The result of [`BusBudget::estimate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BusLoad {
+    pub required_bytes_per_second: u32,
+    pub available_bytes_per_second: u32,
+}
+
+impl BusLoad {
+    /// Returns `true` if the bus can transfer the data in time.
+    pub fn fits(&self) -> bool {
+        self.required_bytes_per_second <= self.available_bytes_per_second
+    }
+
+    /// The required share of the throughput in percent.
+    pub fn utilization(&self) -> u32 {
+        (self.required_bytes_per_second as u64 * 100
+            / self.available_bytes_per_second.max(1) as u64) as u32
+    }
+
+    /// Returns `true` if the load is high enough that [`BusBudget::check`] warns about it.
+    pub fn is_high(&self) -> bool {
+        self.utilization() > WARN_UTILIZATION
+    }
+}
+
+/// The FIFO space the firmware provides for a sensor, see [`SensorInfo::fifo_reserved`] and [`SensorInfo::fifo_max`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FifoReservation {
+    pub id: SensorId,
+    /// The size of an event in bytes (including the Sensor Type).
+    pub event_size: u8,
+    /// The number of events reserved for this sensor. 0 if the sensor only uses the shared part of the FIFO.
+    pub reserved_events: u16,
+    /// The number of events fitting into the whole FIFO.
+    pub max_events: u16,
+}
+
+impl FifoReservation {
+    pub fn from_info(id: SensorId, info: &SensorInfo) -> Self {
+        Self {
+            id,
+            event_size: info.event_size(),
+            reserved_events: info.fifo_reserved(),
+            max_events: info.fifo_max(),
+        }
+    }
+
+    pub fn reserved_bytes(&self) -> usize {
+        self.reserved_events as usize * self.event_size as usize
+    }
+
+    pub fn max_bytes(&self) -> usize {
+        self.max_events as usize * self.event_size as usize
+    }
+
+    /// Returns `true` if the sensor has no reserved space and competes with all other sensors for the shared space.
+    pub fn is_shared(&self) -> bool {
+        self.reserved_events == 0
+    }
+
+    /// How long the reserved space holds the samples at the given rate (in Hz).
+    ///
+    /// Returns `None` for shared sensors (see [`Self::is_shared`]) or a rate of 0.
+    pub fn reserved_duration(&self, rate: u16) -> Option<Duration> {
+        if self.is_shared() || rate == 0 {
+            return None;
+        }
+        Some(Duration::from_micros(
+            self.reserved_events as u64 * 1_000_000 / rate as u64,
+        ))
+    }
+}
+
+/// The FIFO reservations of a set of sensors, see [`Bhi160::fifo_budget`](crate::Bhi160::fifo_budget).
+///
+/// The firmware decides about the reservations, they can not be changed by the host.
+/// Sensors without reserved space (see [`FifoReservation::is_shared`]) may lose samples if other sensors fill the FIFO,
+/// so prefer lower rates or a shorter latency for the heavy consumers.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FifoBudget {
+    pub reservations: Vec<FifoReservation>,
+}
+
+#[cfg(feature = "alloc")]
+impl FifoBudget {
+    pub fn get(&self, id: SensorId) -> Option<&FifoReservation> {
+        self.reservations
+            .iter()
+            .find(|reservation| reservation.id == id)
+    }
+
+    /// The space reserved for the sensors of the wakeup or non-wakeup FIFO in bytes.
+    pub fn reserved_bytes(&self, wakeup: bool) -> usize {
+        self.reservations
+            .iter()
+            .filter(|reservation| reservation.id.is_wakeup() == wakeup)
+            .map(FifoReservation::reserved_bytes)
+            .sum()
+    }
+
+    /// The sensors without reserved space.
+    pub fn shared(&self) -> impl Iterator<Item = &FifoReservation> {
+        self.reservations
+            .iter()
+            .filter(|reservation| reservation.is_shared())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gyro_streaming() {
+        let sensors = [
+            (SensorId::Gyroscope, 800),
+            (SensorId::AccelerometerWakeup, 100),
+        ];
+        let load = BusBudget::new(10_000).estimate(&sensors);
+        // 8 * 800 + 8 * 100 + 3 * 800 + 3 * 100
+        assert_eq!(load.required_bytes_per_second, 9900);
+        assert!(load.fits());
+        assert!(load.is_high());
+
+        assert_eq!(
+            BusBudget::new(9_000)
+                .check(&sensors)
+                .unwrap_err()
+                .utilization(),
+            110
+        );
+        assert!(BusBudget::i2c(400_000).check(&sensors).is_ok());
+    }
+}
@@ -0,0 +1,356 @@
+//! Persistence of the sensor calibration across power cycles.
+//!
+//! The BSX fusion library of the BHI continuously calibrates the accelerometer, gyroscope and magnetometer.
+//! After a reset it starts from scratch, so it can take a while until the sensors report [`SensorStatus::High`] again.
+//! [`Persistence`] snapshots the calibration profiles into a host provided [`Store`] (e.g. a flash page)
+//! whenever a sensor reaches high accuracy and writes them back after a reset.
+//!
+//! The datasheet does not list the calibration profiles of the [Algorithm page](crate::parameters::ParameterPage::Algorithm),
+//! their parameter numbers and sizes depend on the BSX build of the firmware. Take them from the release notes of
+//! the firmware and describe them with a [`Layout`].
+//!
+//! ```ignore
+//! let mut persistence = Persistence::new(store, layout);
+//! // After uploading the firmware and starting the CPU
+//! persistence.restore(&mut bhi)?;
+//! loop {
+//!     bhi.drain_fifo(&mut buf, budget, |event| persistence.observe(&event))?;
+//!     persistence.poll(&mut bhi)?;
+//! }
+//! ```
+
+use crate::{
+    consts::PARAMETER_WRITE_SIZE,
+    fifo::{Event, SensorData, SensorStatus},
+    interface::Interface,
+    parameters::{algorithm::AlgorithmPageGuard, sensors::SensorId},
+    Bhi160, Error,
+};
+
+/// The maximum size of a single calibration profile in bytes.
+pub const MAX_PROFILE_SIZE: usize = 64;
+
+/// The maximum size of a serialized [`Snapshot`] in bytes.
+pub const SNAPSHOT_SIZE: usize = 1 + 3 * (1 + MAX_PROFILE_SIZE);
+
+/// The first byte of a serialized [`Snapshot`]. Used to reject foreign or outdated data.
+const SNAPSHOT_VERSION: u8 = 2;
+
+/// The location of a calibration profile on the Algorithm page.
+///
+/// A parameter write transfers at most 8 bytes, so a profile occupies `size / 8` (rounded up) consecutive
+/// parameters starting at `param`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProfileLayout {
+    pub param: u8,
+    /// The size of the profile in bytes, at most [`MAX_PROFILE_SIZE`].
+    pub size: usize,
+}
+
+impl ProfileLayout {
+    /// Returns the parameter numbers and byte ranges the profile is split into.
+    fn chunks(&self) -> impl Iterator<Item = (u8, core::ops::Range<usize>)> {
+        let size = self.size;
+        (0..size)
+            .step_by(PARAMETER_WRITE_SIZE)
+            .zip(self.param..)
+            .map(move |(start, param)| (param, start..size.min(start + PARAMETER_WRITE_SIZE)))
+    }
+}
+
+/// The locations of the calibration profiles of the physical sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Layout {
+    pub accelerometer: ProfileLayout,
+    pub magnetometer: ProfileLayout,
+    pub gyroscope: ProfileLayout,
+}
+
+impl Layout {
+    fn profiles(&self) -> [ProfileLayout; 3] {
+        [self.accelerometer, self.magnetometer, self.gyroscope]
+    }
+}
+
+/// The calibration profile of a physical sensor.
+///
+/// The content is opaque to the host. It should only be read to be written back later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Profile {
+    len: usize,
+    bytes: [u8; MAX_PROFILE_SIZE],
+}
+
+impl Profile {
+    /// Creates a profile from its raw bytes. Returns `None` if `bytes` is longer than [`MAX_PROFILE_SIZE`].
+    pub fn new(bytes: &[u8]) -> Option<Self> {
+        let mut profile = Self {
+            len: bytes.len(),
+            bytes: [0; MAX_PROFILE_SIZE],
+        };
+        profile.bytes.get_mut(..bytes.len())?.copy_from_slice(bytes);
+        Some(profile)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    fn read<IF>(
+        page: &mut AlgorithmPageGuard<'_, IF>,
+        layout: ProfileLayout,
+    ) -> Result<Self, Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let mut profile = Self::new(&[]).unwrap();
+        profile.len = layout.size;
+        for (param, range) in layout.chunks() {
+            page.read_param_raw(param, &mut profile.bytes[range])?;
+        }
+        Ok(profile)
+    }
+
+    fn write<IF>(
+        &self,
+        page: &mut AlgorithmPageGuard<'_, IF>,
+        layout: ProfileLayout,
+    ) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        if self.len != layout.size {
+            return Err(Error::Unsupported);
+        }
+        for (param, range) in layout.chunks() {
+            page.write_param_raw(param, &self.bytes[range])?;
+        }
+        Ok(())
+    }
+}
+
+/// The calibration profiles of all physical sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    pub accelerometer: Profile,
+    pub magnetometer: Profile,
+    pub gyroscope: Profile,
+}
+
+impl Snapshot {
+    /// Reads the current calibration profiles from the BHI.
+    ///
+    /// # Panics
+    /// If a profile of the `layout` is larger than [`MAX_PROFILE_SIZE`].
+    pub fn read<IF>(bhi: &mut Bhi160<IF>, layout: &Layout) -> Result<Self, Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let mut page = bhi.algorithm_page();
+        let snapshot = Self {
+            accelerometer: Profile::read(&mut page, layout.accelerometer)?,
+            magnetometer: Profile::read(&mut page, layout.magnetometer)?,
+            gyroscope: Profile::read(&mut page, layout.gyroscope)?,
+        };
+        page.commit()?;
+        Ok(snapshot)
+    }
+
+    /// Writes the calibration profiles to the BHI.
+    ///
+    /// Returns [`Error::Unsupported`] if the size of a profile does not match the `layout`.
+    pub fn write<IF>(&self, bhi: &mut Bhi160<IF>, layout: &Layout) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let mut page = bhi.algorithm_page();
+        self.accelerometer.write(&mut page, layout.accelerometer)?;
+        self.magnetometer.write(&mut page, layout.magnetometer)?;
+        self.gyroscope.write(&mut page, layout.gyroscope)?;
+        Ok(page.commit()?)
+    }
+
+    fn profiles(&self) -> [&Profile; 3] {
+        [&self.accelerometer, &self.magnetometer, &self.gyroscope]
+    }
+
+    /// Serializes the snapshot into `buf` and returns the number of bytes written.
+    pub fn to_bytes(&self, buf: &mut [u8; SNAPSHOT_SIZE]) -> usize {
+        buf[0] = SNAPSHOT_VERSION;
+        let mut len = 1;
+        for profile in self.profiles() {
+            buf[len] = profile.len as u8;
+            buf[len + 1..len + 1 + profile.len].copy_from_slice(profile.as_bytes());
+            len += 1 + profile.len;
+        }
+        len
+    }
+
+    /// Parses a snapshot created by [`Self::to_bytes`].
+    ///
+    /// Returns `None` if the data has the wrong version or its profile sizes do not match the `layout`
+    /// (e.g. because the firmware was changed).
+    pub fn from_bytes(bytes: &[u8], layout: &Layout) -> Option<Self> {
+        let (&version, mut rest) = bytes.split_first()?;
+        if version != SNAPSHOT_VERSION {
+            return None;
+        }
+        let mut profiles = [Profile::new(&[]).unwrap(); 3];
+        for (profile, expected) in profiles.iter_mut().zip(layout.profiles()) {
+            let (&len, data) = rest.split_first()?;
+            if len as usize != expected.size || data.len() < expected.size {
+                return None;
+            }
+            let (data, remaining) = data.split_at(expected.size);
+            *profile = Profile::new(data)?;
+            rest = remaining;
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+        let [accelerometer, magnetometer, gyroscope] = profiles;
+        Some(Self {
+            accelerometer,
+            magnetometer,
+            gyroscope,
+        })
+    }
+}
+
+/// A non-volatile storage on the host for a [`Snapshot`].
+pub trait Store {
+    type Error;
+
+    /// Replaces the stored data with `data`.
+    fn save(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Reads the stored data into `buf` and returns the number of bytes read.
+    /// Returns 0 if nothing has been stored yet.
+    fn load(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error>;
+}
+
+/// Errors that can occur while persisting the calibration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PersistError<E, S> {
+    /// Accessing the BHI failed.
+    Interface(Error<E>),
+    /// The [`Store`] returned an error.
+    Store(S),
+}
+
+/// Keeps the calibration profiles in a [`Store`] up to date.
+///
+/// Feed all events to [`observe`](Self::observe). Once the accelerometer, gyroscope or magnetometer
+/// reaches [`SensorStatus::High`] a snapshot is scheduled and saved by the next call to [`poll`](Self::poll).
+pub struct Persistence<S>
+where
+    S: Store,
+{
+    store: S,
+    layout: Layout,
+    status: [Option<SensorStatus>; 3],
+    pending: bool,
+}
+
+impl<S> Persistence<S>
+where
+    S: Store,
+{
+    pub fn new(store: S, layout: Layout) -> Self {
+        Self {
+            store,
+            layout,
+            status: [None; 3],
+            pending: false,
+        }
+    }
+
+    pub fn store(&mut self) -> &mut S {
+        &mut self.store
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    /// Writes the stored calibration profiles to the BHI.
+    ///
+    /// Call this after the firmware has been started. Returns `false` if the store contains no valid snapshot for the [`Layout`].
+    pub fn restore<IF>(
+        &mut self,
+        bhi: &mut Bhi160<IF>,
+    ) -> Result<bool, PersistError<IF::Error, S::Error>>
+    where
+        IF: Interface,
+    {
+        let mut buf = [0; SNAPSHOT_SIZE];
+        let len = self.store.load(&mut buf).map_err(PersistError::Store)?;
+        let Some(snapshot) = Snapshot::from_bytes(&buf[..len.min(SNAPSHOT_SIZE)], &self.layout)
+        else {
+            #[cfg(feature = "log")]
+            log::warn!("No valid calibration snapshot stored");
+            return Ok(false);
+        };
+        snapshot
+            .write(bhi, &self.layout)
+            .map_err(PersistError::Interface)?;
+        self.status = [None; 3];
+        Ok(true)
+    }
+
+    /// Tracks the accuracy reported by the event and schedules a snapshot when a sensor reaches high accuracy.
+    pub fn observe(&mut self, event: &Event) {
+        let index = match event.id().non_wakeup_variant() {
+            SensorId::Accelerometer => 0,
+            SensorId::Gyroscope => 1,
+            SensorId::GeomagneticField => 2,
+            _ => return,
+        };
+        let status = match event.data() {
+            SensorData::VectorStatus(_, status) => *status,
+            _ => return,
+        };
+        if status == SensorStatus::High && self.status[index] != Some(SensorStatus::High) {
+            self.pending = true;
+        }
+        self.status[index] = Some(status);
+    }
+
+    /// Returns `true` if a snapshot is scheduled.
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Saves a snapshot if one is scheduled. Returns `true` if a snapshot was saved.
+    pub fn poll<IF>(
+        &mut self,
+        bhi: &mut Bhi160<IF>,
+    ) -> Result<bool, PersistError<IF::Error, S::Error>>
+    where
+        IF: Interface,
+    {
+        if !self.pending {
+            return Ok(false);
+        }
+        self.save(bhi)?;
+        Ok(true)
+    }
+
+    /// Reads the calibration profiles from the BHI and saves them immediately.
+    pub fn save<IF>(
+        &mut self,
+        bhi: &mut Bhi160<IF>,
+    ) -> Result<(), PersistError<IF::Error, S::Error>>
+    where
+        IF: Interface,
+    {
+        let snapshot = Snapshot::read(bhi, &self.layout).map_err(PersistError::Interface)?;
+        let mut buf = [0; SNAPSHOT_SIZE];
+        let len = snapshot.to_bytes(&mut buf);
+        self.store.save(&buf[..len]).map_err(PersistError::Store)?;
+        self.pending = false;
+        #[cfg(feature = "log")]
+        log::info!("Saved calibration snapshot");
+        Ok(())
+    }
+}
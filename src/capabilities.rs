@@ -0,0 +1,176 @@
+//! A summary of what the firmware running on the BHI supports.
+//!
+//! Different units may ship with different firmwares (e.g. with or without magnetometer).
+//! Use [`Bhi160::capabilities`](crate::Bhi160::capabilities) to adapt the application at runtime.
+//!
+//! [`print_all_sensor_info`] answers the same question for humans, e.g. over a UART:
+//!
+//! ```ignore
+//! bhi.print_all_sensor_info(&mut uart)?;
+//! ```
+
+use core::fmt;
+
+use crate::{
+    firmware::FirmwareIdentity,
+    interface::Interface,
+    parameters::{
+        sensors::{SensorId, SensorInfo},
+        system::FifoControl,
+        ParameterPage,
+    },
+    registers::{AlgorithmId, ChipVariant, HostStatus},
+    Bhi160, Error,
+};
+
+/// A set of virtual sensors (ids 1-63).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorSet(pub u64);
+
+impl SensorSet {
+    pub fn contains(&self, id: SensorId) -> bool {
+        (id as u8) < 64 && self.0 & (1 << id as u8) != 0
+    }
+
+    pub fn insert(&mut self, id: SensorId) {
+        if (id as u8) < 64 {
+            self.0 |= 1 << id as u8;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = SensorId> + '_ {
+        (1..64)
+            .filter(|id| self.0 & (1 << id) != 0)
+            .filter_map(SensorId::from_u8)
+    }
+}
+
+/// The features supported by the firmware running on the BHI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// The chip variant, if known.
+    pub variant: Option<ChipVariant>,
+    /// The versions of the ROM and the RAM patch.
+    pub firmware: FirmwareIdentity,
+    /// The fusion algorithm reported by [`HostStatus`], if known.
+    pub algorithm: Option<AlgorithmId>,
+    /// The virtual sensors provided by the firmware.
+    pub sensors: SensorSet,
+    /// The size of the wakeup FIFO in bytes.
+    pub wakeup_fifo_size: u16,
+    /// The size of the non-wakeup FIFO in bytes.
+    pub non_wakeup_fifo_size: u16,
+}
+
+impl Capabilities {
+    /// Reads the capabilities from the BHI.
+    ///
+    /// This scans the sensor information of all 63 virtual sensors and therefore takes a while.
+    pub fn read<IF>(bhi: &mut Bhi160<IF>) -> Result<Self, Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let mut sensors = SensorSet::default();
+        for id in (1..64).filter_map(SensorId::from_u8) {
+            let mut info = [0; 16];
+            bhi.read_param_raw(ParameterPage::Sensors, id as u8, &mut info)?;
+            if SensorInfo::from_bytes(info).driver_id() != 0 {
+                sensors.insert(id);
+            }
+        }
+        let fifo: FifoControl = bhi.read_param()?;
+        let status: HostStatus = bhi.read_reg()?;
+        Ok(Self {
+            variant: bhi.chip_variant()?,
+            firmware: bhi.firmware_identity()?,
+            algorithm: status.algorithm_id_or_err().ok(),
+            sensors,
+            wakeup_fifo_size: fifo.wakeup_size(),
+            non_wakeup_fifo_size: fifo.non_wakeup_size(),
+        })
+    }
+
+    /// Returns `true` if the firmware provides the given sensor.
+    pub fn has_sensor(&self, id: SensorId) -> bool {
+        self.sensors.contains(id)
+    }
+
+    /// Returns `true` if the firmware provides any wakeup sensor.
+    pub fn supports_wakeup(&self) -> bool {
+        self.sensors.iter().any(SensorId::is_wakeup)
+    }
+}
+
+/// An error of [`print_all_sensor_info`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrintError<E> {
+    /// Reading the sensor information failed.
+    Bhi(Error<E>),
+    /// The output returned an error.
+    Format(fmt::Error),
+}
+
+impl<E> From<Error<E>> for PrintError<E> {
+    fn from(err: Error<E>) -> Self {
+        Self::Bhi(err)
+    }
+}
+
+impl<E> From<fmt::Error> for PrintError<E> {
+    fn from(err: fmt::Error) -> Self {
+        Self::Format(err)
+    }
+}
+
+/// Writes a table of the virtual sensors provided by the firmware and the sizes of the FIFOs to `out`.
+///
+/// Every row holds the Sensor Type, the name, the driver (id, version and part), the range of the rates in Hz, the
+/// FIFO reservation and capacity in events and the size of an event in bytes. Like [`Capabilities::read`] this scans
+/// the sensor information of all 63 virtual sensors and therefore takes a while.
+pub fn print_all_sensor_info<IF>(
+    bhi: &mut Bhi160<IF>,
+    out: &mut impl fmt::Write,
+) -> Result<(), PrintError<IF::Error>>
+where
+    IF: Interface,
+{
+    writeln!(
+        out,
+        "{:>3}  {:<34}  {:<30}  {:>10}  {:>11}  {:>5}",
+        "ID", "Sensor", "Driver", "Rate (Hz)", "FIFO", "Event"
+    )?;
+    for id in (1..64).filter_map(SensorId::from_u8) {
+        let info = bhi.sensor_info(id)?;
+        if info.driver_id() == 0 {
+            continue;
+        }
+        writeln!(
+            out,
+            "{:>3}  {:<34}  {:#04x} v{:<3} {:<20}  {:>4} - {:<3}  {:>5}/{:<5}  {:>5}",
+            id as u8,
+            id.name(),
+            info.driver_id(),
+            info.driver_version(),
+            info.driver().name(),
+            info.min_rate(),
+            info.max_rate(),
+            info.fifo_reserved(),
+            info.fifo_max(),
+            info.event_size(),
+        )?;
+    }
+    let fifo: FifoControl = bhi.read_param()?;
+    writeln!(
+        out,
+        "FIFO: {} bytes wakeup, {} bytes non-wakeup",
+        fifo.wakeup_size(),
+        fifo.non_wakeup_size()
+    )?;
+    Ok(())
+}
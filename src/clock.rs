@@ -0,0 +1,186 @@
+//! A host clock abstraction for the time-based helpers of this crate.
+//!
+//! [`Replay`](crate::replay::Replay) and [`LatencyProbe`](crate::latency::LatencyProbe) read the host time through
+//! the [`Clock`] trait, so they behave the same on hosts with `std` and on embedded targets. Implementations are
+//! provided for `std::time` ([`StdClock`], with the `std` feature), for `embassy-time` ([`EmbassyClock`], with the `embassy-time` feature)
+//! and for any free-running hardware counter ([`CounterClock`]).
+//!
+//! ```ignore
+//! // A 32 bit timer running at 1 MHz
+//! let clock = CounterClock::new(|| timer.counter(), 1_000_000);
+//! let irq_time = clock.now();
+//! ```
+
+use core::{cell::Cell, ops::Add, time::Duration};
+
+/// A point in time of a [`Clock`], counted in microseconds since an arbitrary epoch of that clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Instant {
+    micros: u64,
+}
+
+impl Instant {
+    pub const fn from_micros(micros: u64) -> Self {
+        Self { micros }
+    }
+
+    pub const fn as_micros(&self) -> u64 {
+        self.micros
+    }
+
+    /// The time elapsed from `earlier` to `self`, zero if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        Duration::from_micros(self.micros.saturating_sub(earlier.micros))
+    }
+
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        let micros = u64::try_from(duration.as_micros()).ok()?;
+        Some(Self::from_micros(self.micros.checked_add(micros)?))
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    /// # Panics
+    /// If the result overflows.
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+/// A monotonic source of the host time.
+pub trait Clock {
+    /// The current time. Successive calls must never return an earlier time.
+    fn now(&self) -> Instant;
+
+    /// The time elapsed since `earlier`.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().duration_since(earlier)
+    }
+}
+
+impl<C> Clock for &C
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// A [`Clock`] based on [`std::time::Instant`], counting from its creation.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StdClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Instant {
+        Instant::from_micros(self.epoch.elapsed().as_micros() as u64)
+    }
+}
+
+/// A [`Clock`] based on the time driver of `embassy-time`.
+#[cfg(feature = "embassy-time")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EmbassyClock;
+
+#[cfg(feature = "embassy-time")]
+impl Clock for EmbassyClock {
+    fn now(&self) -> Instant {
+        Instant::from_micros(embassy_time::Instant::now().as_micros())
+    }
+}
+
+/// A [`Clock`] reading a free-running 32 bit counter, e.g. a hardware timer.
+///
+/// The wrap-arounds of the counter are tracked, so [`now`](Clock::now) has to be called at least once per period
+/// of the counter (about 71 minutes for a 1 MHz counter).
+pub struct CounterClock<F>
+where
+    F: Fn() -> u32,
+{
+    read: F,
+    frequency_hz: u32,
+    last: Cell<u32>,
+    wraps: Cell<u64>,
+}
+
+impl<F> CounterClock<F>
+where
+    F: Fn() -> u32,
+{
+    /// Creates a clock reading the counter with `read`, which counts up at `frequency_hz`.
+    pub fn new(read: F, frequency_hz: u32) -> Self {
+        assert!(frequency_hz > 0, "the counter frequency must not be zero");
+        Self {
+            read,
+            frequency_hz,
+            last: Cell::new(0),
+            wraps: Cell::new(0),
+        }
+    }
+}
+
+impl<F> Clock for CounterClock<F>
+where
+    F: Fn() -> u32,
+{
+    fn now(&self) -> Instant {
+        let count = (self.read)();
+        if count < self.last.get() {
+            self.wraps.set(self.wraps.get() + 1);
+        }
+        self.last.set(count);
+        let ticks = (self.wraps.get() << 32) | count as u64;
+        Instant::from_micros((ticks as u128 * 1_000_000 / self.frequency_hz as u128) as u64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counter_wrap_around() {
+        let counter = Cell::new(u32::MAX - 999);
+        let clock = CounterClock::new(|| counter.get(), 1000);
+        let start = clock.now();
+        counter.set(counter.get().wrapping_add(1500));
+        assert_eq!(clock.elapsed(start), Duration::from_millis(1500));
+        assert_eq!(clock.now().as_micros(), (1 << 32) * 1000 + 500_000);
+    }
+
+    #[test]
+    fn instant_arithmetic() {
+        let start = Instant::from_micros(1000);
+        let later = start + Duration::from_millis(2);
+        assert_eq!(later.as_micros(), 3000);
+        assert_eq!(later.duration_since(start), Duration::from_millis(2));
+        assert_eq!(start.duration_since(later), Duration::ZERO);
+        assert_eq!(
+            Instant::from_micros(u64::MAX).checked_add(Duration::from_micros(1)),
+            None
+        );
+    }
+}
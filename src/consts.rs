@@ -0,0 +1,58 @@
+//! Constants from the datasheets of the BHI160 and BHI160B.
+//!
+//! Use these to size buffers and to configure timers instead of repeating the numbers in your code.
+//! The values shared by both variants are defined at the top level, the others in [`bhi160`] and [`bhi160b`].
+//!
+//! The FIFO sizes are the nominal sizes of the RAM reserved for the FIFOs.
+//! The sizes configured by the running firmware can be read with [`FifoControl`](crate::parameters::system::FifoControl).
+
+use crate::registers::ChipVariant;
+
+/// The frequency of the timestamp counter of the BHI in Hz, i.e. one tick is 31.25 µs.
+///
+/// Applies to the timestamp events in the FIFO and [`HostIrqTimestamp`](crate::registers::HostIrqTimestamp).
+/// See [`ticks_to_micros`](crate::timestamp::ticks_to_micros) for conversions.
+pub const TICK_FREQUENCY_HZ: u32 = 32_000;
+
+/// The size of the window of registers (starting at `0x00`) through which the FIFO is read.
+///
+/// A single burst read of the FIFO should not be longer than this.
+pub const FIFO_WINDOW_SIZE: usize = 0x32;
+
+/// The size of the parameter read buffer, i.e. the maximum size of a parameter read.
+pub const PARAMETER_READ_SIZE: usize = 16;
+
+/// The size of the parameter load window, i.e. the maximum size of a parameter write.
+pub const PARAMETER_WRITE_SIZE: usize = 8;
+
+/// Constants of the BHI160.
+pub mod bhi160 {
+    /// The nominal size of the non-wakeup and wakeup FIFO combined in bytes.
+    pub const FIFO_SIZE: usize = 8 * 1024;
+
+    /// The time after power-on or a [`ResetRequest`](crate::registers::ResetRequest) until the host interface is ready, in ms.
+    pub const RESET_TIME_MS: u32 = 10;
+
+    /// The time after starting the CPU until the firmware reports [`Initialized`](crate::packet::MetaEvent::Initialized), in ms.
+    pub const FIRMWARE_START_TIME_MS: u32 = 200;
+}
+
+/// Constants of the BHI160B.
+pub mod bhi160b {
+    /// The nominal size of the non-wakeup and wakeup FIFO combined in bytes.
+    pub const FIFO_SIZE: usize = 10 * 1024;
+
+    /// The time after power-on or a [`ResetRequest`](crate::registers::ResetRequest) until the host interface is ready, in ms.
+    pub const RESET_TIME_MS: u32 = 10;
+
+    /// The time after starting the CPU until the firmware reports [`Initialized`](crate::packet::MetaEvent::Initialized), in ms.
+    pub const FIRMWARE_START_TIME_MS: u32 = 200;
+}
+
+/// The nominal size of both FIFOs combined of the given variant in bytes.
+pub const fn fifo_size(variant: ChipVariant) -> usize {
+    match variant {
+        ChipVariant::Bhi160 => bhi160::FIFO_SIZE,
+        ChipVariant::Bhi160B => bhi160b::FIFO_SIZE,
+    }
+}
@@ -0,0 +1,223 @@
+//! Capturing the state of the BHI when the firmware reports a fault.
+//!
+//! The [`Error`](MetaEvent::Error) meta event only carries the error code and the debug state.
+//! [`Bhi160::drain_fifo_diagnosed`] reads the remaining diagnostic registers and the physical sensor status
+//! as soon as the event has been read, so transient faults are captured with their context.
+//!
+//! [`SelfTestReport`] collects the results of a self-test run by [`Bhi160::self_test`].
+//!
+//! [`SupportBundle`] combines the capabilities, the diagnostic registers and the most recent
+//! [`Error`](MetaEvent::Error) meta events into one snapshot. With the `serde` feature it can be serialized
+//! (e.g. with `postcard`) and sent by units in the field:
+//!
+//! ```ignore
+//! let bundle = bhi.support_bundle()?;
+//! let bytes = postcard::to_slice(&bundle, &mut buf)?;
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::{
+    capabilities::Capabilities,
+    fifo::{Event, MetaEvent, SensorData},
+    interface::Interface,
+    parameters::system::PhysicalSensorStatus,
+    registers::{ChipStatus, DebugState, DebugValue, ErrorRegister, HostStatus},
+    Bhi160, Error,
+};
+#[cfg(feature = "alloc")]
+use crate::{fifo::SelfTestOutcome, parameters::sensors::SensorId};
+
+/// The state of the BHI captured right after an [`Error`](MetaEvent::Error) meta event was read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorReport {
+    /// The error code reported by the meta event.
+    pub event_error: u8,
+    /// The debug state reported by the meta event.
+    pub event_debug_state: u8,
+    pub error: ErrorRegister,
+    pub debug_value: DebugValue,
+    pub debug_state: DebugState,
+    pub host_status: HostStatus,
+    pub chip_status: ChipStatus,
+    pub physical_sensors: PhysicalSensorStatus,
+}
+
+impl ErrorReport {
+    /// Returns the error code and debug state if `event` is an [`Error`](MetaEvent::Error) meta event.
+    pub fn error_event(event: &Event) -> Option<(u8, u8)> {
+        match event.data() {
+            SensorData::MetaEvent(MetaEvent::Error(error, debug_state)) => {
+                Some((*error, *debug_state))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads the diagnostic registers and the physical sensor status.
+    pub fn capture<IF>(
+        bhi: &mut Bhi160<IF>,
+        event_error: u8,
+        event_debug_state: u8,
+    ) -> Result<Self, Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        Ok(Self {
+            event_error,
+            event_debug_state,
+            error: bhi.read_reg()?,
+            debug_value: bhi.read_reg()?,
+            debug_state: bhi.read_reg()?,
+            host_status: bhi.read_reg()?,
+            chip_status: bhi.read_reg()?,
+            physical_sensors: bhi.read_param()?,
+        })
+    }
+}
+
+/// The results of [`Bhi160::self_test`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SelfTestReport {
+    /// The reported results in the order of their arrival.
+    pub results: Vec<(SensorId, SelfTestOutcome)>,
+    /// The requested sensors which did not report a result.
+    pub missing: Vec<SensorId>,
+}
+
+#[cfg(feature = "alloc")]
+impl SelfTestReport {
+    /// Returns the sensor and the outcome if `event` is a [`SelfTestResult`](MetaEvent::SelfTestResult) meta event.
+    pub fn result_event(event: &Event) -> Option<(SensorId, SelfTestOutcome)> {
+        match event.data() {
+            SensorData::MetaEvent(MetaEvent::SelfTestResult(id, outcome)) => Some((*id, *outcome)),
+            _ => None,
+        }
+    }
+
+    /// The last reported outcome of `id`.
+    pub fn get(&self, id: SensorId) -> Option<SelfTestOutcome> {
+        self.results
+            .iter()
+            .rev()
+            .find(|(result_id, _)| *result_id == id)
+            .map(|(_, outcome)| *outcome)
+    }
+
+    /// Returns `true` if all requested sensors reported and passed.
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.results.iter().all(|(_, outcome)| outcome.passed())
+    }
+
+    /// The sensors which reported a failure.
+    pub fn failed(&self) -> impl Iterator<Item = (SensorId, SelfTestOutcome)> + '_ {
+        self.results
+            .iter()
+            .copied()
+            .filter(|(_, outcome)| !outcome.passed())
+    }
+}
+
+/// The number of [`Error`](MetaEvent::Error) meta events kept by [`RecentErrors`].
+pub const RECENT_ERRORS: usize = 4;
+
+/// The most recent [`Error`](MetaEvent::Error) meta events read from the FIFO.
+///
+/// Recorded by [`Bhi160::drain_fifo`] and its variants and kept across resets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecentErrors {
+    /// The error codes and debug states, stored as a ring buffer.
+    errors: [(u8, u8); RECENT_ERRORS],
+    /// The number of error events seen in total.
+    total: u32,
+}
+
+impl RecentErrors {
+    /// Records an error event, dropping the oldest one if already [`RECENT_ERRORS`] are kept.
+    pub fn push(&mut self, error: u8, debug_state: u8) {
+        self.errors[self.total as usize % RECENT_ERRORS] = (error, debug_state);
+        self.total = self.total.wrapping_add(1);
+    }
+
+    /// The number of error events seen in total, including the dropped ones.
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// The kept error codes and debug states, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        let kept = (self.total as usize).min(RECENT_ERRORS);
+        (self.total as usize - kept..self.total as usize).map(|n| self.errors[n % RECENT_ERRORS])
+    }
+}
+
+/// The raw values of the diagnostic registers and the physical sensor status.
+///
+/// Unlike [`ErrorReport`], this can be captured at any time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiagnosticRegisters {
+    pub error: u8,
+    pub debug_value: u8,
+    pub debug_state: u8,
+    pub host_status: u8,
+    pub chip_status: u8,
+    pub physical_sensors: [u8; 15],
+}
+
+impl DiagnosticRegisters {
+    /// Reads the diagnostic registers and the physical sensor status.
+    pub fn capture<IF>(bhi: &mut Bhi160<IF>) -> Result<Self, Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let ErrorRegister(error) = bhi.read_reg()?;
+        let DebugValue(debug_value) = bhi.read_reg()?;
+        let DebugState(debug_state) = bhi.read_reg()?;
+        let host_status: HostStatus = bhi.read_reg()?;
+        let chip_status: ChipStatus = bhi.read_reg()?;
+        let physical_sensors: PhysicalSensorStatus = bhi.read_param()?;
+        Ok(Self {
+            error,
+            debug_value,
+            debug_state,
+            host_status: host_status.into_bytes()[0],
+            chip_status: chip_status.into_bytes()[0],
+            physical_sensors: physical_sensors.into_bytes(),
+        })
+    }
+
+    pub fn host_status(&self) -> HostStatus {
+        HostStatus::from_bytes([self.host_status])
+    }
+
+    pub fn chip_status(&self) -> ChipStatus {
+        ChipStatus::from_bytes([self.chip_status])
+    }
+
+    pub fn physical_sensors(&self) -> PhysicalSensorStatus {
+        PhysicalSensorStatus::from_bytes(self.physical_sensors)
+    }
+}
+
+/// A snapshot of the state of the BHI for remote debugging, see the [module documentation](self).
+///
+/// Obtained by [`Bhi160::support_bundle`]. The firmware identity is part of the
+/// [`capabilities`](Capabilities::firmware).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupportBundle {
+    /// The layout of the bundle, [`SupportBundle::FORMAT_VERSION`] when created by this crate.
+    pub format_version: u8,
+    pub capabilities: Capabilities,
+    pub registers: DiagnosticRegisters,
+    pub recent_errors: RecentErrors,
+}
+
+impl SupportBundle {
+    /// Incremented whenever the serialized layout of the bundle changes.
+    pub const FORMAT_VERSION: u8 = 1;
+}
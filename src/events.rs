@@ -0,0 +1,103 @@
+//! Event sources reading directly from the BHI.
+//!
+//! [`Events`] is a blocking iterator over the events currently stored in the FIFO.
+//! With the `async` feature it also implements [`futures_core::Stream`], so it can be consumed using
+//! `StreamExt` combinators. The stream does not wait for new data: it ends once the FIFO is empty.
+//! Wait for the host interrupt (e.g. using an async GPIO) and create a new stream afterwards.
+//!
+//! ```ignore
+//! loop {
+//!     irq.wait_for_high().await?;
+//!     let mut events = bhi.events(&mut buf)?;
+//!     while let Some(event) = events.next().await {
+//!         handle(event?);
+//!     }
+//! }
+//! ```
+
+use alloc::collections::VecDeque;
+
+use crate::{fifo::Event, interface::Interface, Bhi160, Error, Throttle};
+
+/// An iterator over the events in the FIFO.
+///
+/// Obtained by [`Bhi160::events`]. The FIFO is read one transfer at a time, whenever all previously read events were consumed.
+/// Errors end the iteration after they have been returned.
+pub struct Events<'a, IF>
+where
+    IF: Interface,
+{
+    bhi: &'a mut Bhi160<IF>,
+    buf: &'a mut [u8],
+    queue: VecDeque<Event>,
+    done: bool,
+    skipped: usize,
+}
+
+impl<'a, IF> Events<'a, IF>
+where
+    IF: Interface,
+{
+    pub(crate) fn new(bhi: &'a mut Bhi160<IF>, buf: &'a mut [u8]) -> Self {
+        Self {
+            bhi,
+            buf,
+            queue: VecDeque::new(),
+            done: false,
+            skipped: 0,
+        }
+    }
+
+    /// The number of zero bytes skipped so far, see [`Bhi160::set_fifo_padding`].
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Reads the next transfer. Returns `false` if the FIFO is empty.
+    fn fill(&mut self) -> Result<bool, Error<IF::Error>> {
+        let queue = &mut self.queue;
+        let progress =
+            self.bhi
+                .drain_fifo_throttled(self.buf, Throttle::single_transfer(), |event| {
+                    queue.push_back(event)
+                })?;
+        self.skipped += progress.skipped;
+        Ok(progress.bytes > 0)
+    }
+}
+
+impl<'a, IF> Iterator for Events<'a, IF>
+where
+    IF: Interface,
+{
+    type Item = Result<Event, Error<IF::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() && !self.done {
+            match self.fill() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.queue.pop_front().map(Ok)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'a, IF> futures_core::Stream for Events<'a, IF>
+where
+    IF: Interface,
+{
+    type Item = Result<Event, Error<IF::Error>>;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        _cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Self::Item>> {
+        core::task::Poll::Ready(self.get_mut().next())
+    }
+}
@@ -0,0 +1,1267 @@
+//! Decoding of the events read from the FIFO.
+
+#[cfg(feature = "std")]
+use std::io::{Cursor, Read};
+
+use modular_bitfield::Specifier;
+
+use crate::{
+    math::{Quaternion, Vector},
+    parameters::{
+        sensors::SensorId,
+        system::{self, SensorPowerMode},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SensorStatus {
+    Unreliable,
+    Low,
+    Medium,
+    High,
+}
+
+impl TryFrom<u8> for SensorStatus {
+    type Error = ();
+    fn try_from(src: u8) -> Result<Self, Self::Error> {
+        match src {
+            0 => Ok(Self::Unreliable),
+            1 => Ok(Self::Low),
+            2 => Ok(Self::Medium),
+            3 => Ok(Self::High),
+            _ => Err(()),
+        }
+    }
+}
+
+// #[derive(Debug, Clone, PartialEq, Eq)]
+// pub struct VectorStatus {
+//     vector: Vector<i16>,
+//     status: SensorStatus,
+// }
+
+// impl VectorStatus {
+//     fn read(reader: &mut impl Read) -> Result<Self, std::io::Error> {
+//         let vector = Vector([
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//         ]);
+//         let status = reader.read_u8()?.try_into().unwrap(); //TODO: Don't us unwrap
+//         Ok(Self { vector, status })
+//     }
+
+//     pub fn vector(&self) -> &Vector<i16> {
+//         &self.vector
+//     }
+
+//     pub fn status(&self) -> SensorStatus {
+//         self.status
+//     }
+// }
+
+// #[derive(Debug, Clone, PartialEq, Eq)]
+// pub struct VectorBiasStatus {
+//     vector: Vector<i16>,
+//     bias: Vector<i16>,
+//     status: SensorStatus,
+// }
+
+// impl VectorBiasStatus {
+//     fn read(reader: &mut impl Read) -> Result<Self, std::io::Error> {
+//         let vector = Vector([
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//         ]);
+//         let bias = Vector([
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//         ]);
+//         let status = reader.read_u8()?.try_into().unwrap(); //TODO: Don't us unwrap
+//         Ok(Self {
+//             vector,
+//             bias,
+//             status,
+//         })
+//     }
+
+//     pub fn vector(&self) -> &Vector<i16> {
+//         &self.vector
+//     }
+
+//     pub fn bias(&self) -> &Vector<i16> {
+//         &self.bias
+//     }
+
+//     pub fn status(&self) -> SensorStatus {
+//         self.status
+//     }
+// }
+
+// #[derive(Debug, Clone, PartialEq, Eq)]
+// pub struct QuaternionAccuracy {
+//     quaternion: Quaternion<i16>,
+//     accuracy: i16,
+// }
+
+// impl QuaternionAccuracy {
+//     fn read(reader: &mut impl Read) -> Result<Self, std::io::Error> {
+//         let quaternion = Quaternion::new(
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//             reader.read_i16::<LittleEndian>()?,
+//         );
+//         let accuracy = reader.read_i16::<LittleEndian>()?;
+//         Ok(Self {
+//             quaternion,
+//             accuracy,
+//         })
+//     }
+
+//     pub fn quaternion(&self) -> &Quaternion<i16> {
+//         &self.quaternion
+//     }
+
+//     pub fn status(&self) -> i16 {
+//         self.accuracy
+//     }
+// }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MetaEvent {
+    /// 1, Sensor Type, -/-
+    FlushComplete(SensorId),
+    /// 2, Sensor Type, -/-
+    SampleRateChanged(SensorId),
+    /// 3, Sensor Type, Power Mode
+    PowerModeChanged(SensorId, PowerMode),
+    /// 4, Error Register, Debug State
+    Error(u8, u8), //TODO Values
+    /// 11, Sensor Type, Sensor Status Bits
+    ///
+    /// The status bits use the same layout as the flags of [`PhysicalSensorStatus`](system::PhysicalSensorStatus).
+    SensorError(SensorId, system::SensorStatus),
+    /// 12, Loss Count LSB, Loss Count MSB
+    FifoOverflow(u16),
+    /// 13, Sensor Type, -/-
+    DynamicRangeChanged(SensorId),
+    /// 14, Bytes Remaining LSB, Bytes Remaining MSB
+    FifoWatermark(u16),
+    /// 15, Sensor Type, Test Result
+    SelfTestResult(SensorId, SelfTestOutcome),
+    /// 16, RAM Ver LSB, RAM Ver MSB
+    Initialized(u16),
+    /// A reserved (5-10) or unknown meta event, or a known meta event referring to an unknown sensor type.
+    ///
+    /// Newer firmwares may report meta events this crate does not know about yet.
+    /// These are passed through unmodified instead of aborting the decoding of the FIFO.
+    Unknown { id: u8, payload: [u8; 2] },
+}
+
+/// The result of the self-test of a sensor, see [`MetaEvent::SelfTestResult`].
+///
+/// 0 means the test passed, otherwise the lower three bits flag the failed axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SelfTestOutcome(pub u8);
+
+impl SelfTestOutcome {
+    pub fn passed(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn x_failed(&self) -> bool {
+        self.0 & 0b001 != 0
+    }
+
+    pub fn y_failed(&self) -> bool {
+        self.0 & 0b010 != 0
+    }
+
+    pub fn z_failed(&self) -> bool {
+        self.0 & 0b100 != 0
+    }
+
+    /// Returns `true` if the value has bits set besides the axis flags, i.e. the failure is not axis specific.
+    pub fn other_failure(&self) -> bool {
+        self.0 & !0b111 != 0
+    }
+}
+
+/// The power mode reported by [`MetaEvent::PowerModeChanged`].
+///
+/// The raw value is kept, as firmwares may report power modes this crate does not know about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerMode(pub u8);
+
+impl PowerMode {
+    /// Returns `None` if the power mode is unknown.
+    pub fn mode(&self) -> Option<SensorPowerMode> {
+        SensorPowerMode::from_bytes(self.0).ok()
+    }
+}
+
+impl MetaEvent {
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        Self::decode(bytes).unwrap_or(Self::Unknown {
+            id: bytes[0],
+            payload: [bytes[1], bytes[2]],
+        })
+    }
+
+    fn decode(bytes: [u8; 3]) -> Option<Self> {
+        match bytes[0] {
+            1 => Some(Self::FlushComplete(SensorId::from_bytes(bytes[1]).ok()?)),
+            2 => Some(Self::SampleRateChanged(
+                SensorId::from_bytes(bytes[1]).ok()?,
+            )),
+            3 => Some(Self::PowerModeChanged(
+                SensorId::from_bytes(bytes[1]).ok()?,
+                PowerMode(bytes[2]),
+            )),
+            4 => Some(Self::Error(bytes[1], bytes[2])),
+            11 => Some(Self::SensorError(
+                SensorId::from_bytes(bytes[1]).ok()?,
+                system::SensorStatus::from_bytes([bytes[2]]),
+            )),
+            12 => Some(Self::FifoOverflow(u16::from_le_bytes([bytes[1], bytes[2]]))),
+            13 => Some(Self::DynamicRangeChanged(
+                SensorId::from_bytes(bytes[1]).ok()?,
+            )),
+            14 => Some(Self::FifoWatermark(u16::from_le_bytes([
+                bytes[1], bytes[2],
+            ]))),
+            15 => Some(Self::SelfTestResult(
+                SensorId::from_bytes(bytes[1]).ok()?,
+                SelfTestOutcome(bytes[2]),
+            )),
+            16 => Some(Self::Initialized(u16::from_le_bytes([bytes[1], bytes[2]]))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SensorData {
+    None,
+    Event(u8),
+    Scalar(i32),
+    VectorStatus(Vector<i16>, SensorStatus),
+    VectorBiasStatus(Vector<i16>, Vector<i16>, SensorStatus),
+    QuaternionAccuracy(Quaternion<i16>, i16),
+    VectorTimestamp(Vector<i32>, u32),
+    Debug([u8; 13]),
+    MetaEvent(MetaEvent),
+}
+
+/// Reads a status byte, failing on values out of range instead of panicking.
+fn read_status<S: Source>(reader: &mut S) -> Result<SensorStatus, S::Error> {
+    SensorStatus::try_from(reader.read_u8()?).map_err(|()| S::invalid("invalid sensor status"))
+}
+
+/// The bytes an event is decoded from, either a slice or (with `std`) a [`Read`].
+trait Source {
+    type Error;
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], Self::Error>;
+
+    /// The error for data which is not a valid event.
+    fn invalid(msg: &'static str) -> Self::Error;
+
+    fn read_u8(&mut self) -> Result<u8, Self::Error> {
+        Ok(u8::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Self::Error> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_i16(&mut self) -> Result<i16, Self::Error> {
+        Ok(i16::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u24(&mut self) -> Result<u32, Self::Error> {
+        let [b0, b1, b2] = self.read_array()?;
+        Ok(u32::from_le_bytes([b0, b1, b2, 0]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Self::Error> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Self::Error> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+}
+
+impl Source for &[u8] {
+    type Error = ();
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], ()> {
+        let (bytes, rest) = self.split_first_chunk().ok_or(())?;
+        *self = rest;
+        Ok(*bytes)
+    }
+
+    fn invalid(_msg: &'static str) {}
+}
+
+/// Adapts a [`Read`] to [`Source`].
+#[cfg(feature = "std")]
+struct IoSource<'a, R>(&'a mut R);
+
+#[cfg(feature = "std")]
+impl<'a, R> Source for IoSource<'a, R>
+where
+    R: Read,
+{
+    type Error = std::io::Error;
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], std::io::Error> {
+        let mut bytes = [0; N];
+        self.0.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn invalid(msg: &'static str) -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+    }
+}
+
+impl SensorData {
+    /// Returns the vector if this is a [`Self::VectorStatus`] or [`Self::VectorBiasStatus`].
+    pub fn as_vector(&self) -> Option<&Vector<i16>> {
+        match self {
+            Self::VectorStatus(vec, _) | Self::VectorBiasStatus(vec, _, _) => Some(vec),
+            _ => None,
+        }
+    }
+
+    /// Returns the quaternion if this is a [`Self::QuaternionAccuracy`].
+    pub fn as_quaternion(&self) -> Option<&Quaternion<i16>> {
+        match self {
+            Self::QuaternionAccuracy(quat, _) => Some(quat),
+            _ => None,
+        }
+    }
+
+    /// Returns the value if this is a [`Self::Scalar`].
+    pub fn as_scalar(&self) -> Option<i32> {
+        match self {
+            Self::Scalar(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// Returns the meta event if this is a [`Self::MetaEvent`].
+    pub fn as_meta_event(&self) -> Option<&MetaEvent> {
+        match self {
+            Self::MetaEvent(event) => Some(event),
+            _ => None,
+        }
+    }
+
+    fn read_vector_status<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        let vec = Vector([reader.read_i16()?, reader.read_i16()?, reader.read_i16()?]);
+        let status = read_status(reader)?;
+        Ok(Self::VectorStatus(vec, status))
+    }
+
+    fn read_vector_bias_status<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        let vec = Vector([reader.read_i16()?, reader.read_i16()?, reader.read_i16()?]);
+        let bias = Vector([reader.read_i16()?, reader.read_i16()?, reader.read_i16()?]);
+        let status = read_status(reader)?;
+        Ok(Self::VectorBiasStatus(vec, bias, status))
+    }
+
+    fn read_quaternion_accuracy<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        let quat = Quaternion::new(
+            reader.read_i16()?,
+            reader.read_i16()?,
+            reader.read_i16()?,
+            reader.read_i16()?,
+        );
+        let accuracy = reader.read_i16()?;
+        Ok(Self::QuaternionAccuracy(quat, accuracy))
+    }
+
+    fn read_vector_timestamp<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        let vec = Vector([reader.read_i32()?, reader.read_i32()?, reader.read_i32()?]);
+        let timestamp = reader.read_u32()?;
+        Ok(Self::VectorTimestamp(vec, timestamp))
+    }
+
+    fn read_metaevent<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        Ok(Self::MetaEvent(MetaEvent::from_bytes(reader.read_array()?)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Event {
+    id: SensorId,
+    data: SensorData,
+}
+
+impl Event {
+    pub fn new(id: SensorId, data: SensorData) -> Self {
+        Self { id, data }
+    }
+
+    /// Reads a single event from `reader`.
+    #[cfg(feature = "std")]
+    pub fn read(reader: &mut impl Read) -> Result<Self, std::io::Error> {
+        Self::decode(&mut IoSource(reader))
+    }
+
+    /// Decodes the event at the start of `bytes`.
+    ///
+    /// Returns `None` if `bytes` ends before the event or does not start with a valid event.
+    pub fn from_bytes(mut bytes: &[u8]) -> Option<Self> {
+        Self::decode(&mut bytes).ok()
+    }
+
+    fn decode<S: Source>(reader: &mut S) -> Result<Self, S::Error> {
+        use SensorId::*;
+        let id = SensorId::from_u8(reader.read_u8()?)
+            .ok_or_else(|| S::invalid("unknown sensor type"))?;
+        let data = match id {
+            None => SensorData::None,
+            RotationVector
+            | RotationVectorWakeup
+            | GameRotationVector
+            | GameRotationVectorWakeup
+            | GeomagneticRotationVector
+            | GeomagneticRotationVectorWakeup => SensorData::read_quaternion_accuracy(reader)?,
+            Accelerometer
+            | AccelerometerWakeup
+            | GeomagneticField
+            | GeomagneticFieldWakeup
+            | Orientation
+            | OrientationWakeup
+            | Gyroscope
+            | GyroscopeWakeup
+            | Gravity
+            | GravityWakeup
+            | LinearAcceleration
+            | LinearAccelerationWakeup => SensorData::read_vector_status(reader)?,
+            Light | LightWakeup | Proximity | ProximityWakeup | Humidity | HumidityWakeup => {
+                SensorData::Scalar(reader.read_i16()? as i32)
+            }
+            StepCounter | StepCounterWakeup => SensorData::Scalar(reader.read_u16()? as i32),
+            Temperature | TemperatureWakeup | AmbientTemperature | AmbientTemperatureWakeup => {
+                SensorData::Scalar(reader.read_i16()? as i32)
+            }
+            Pressure | PressureWakeup => SensorData::Scalar(reader.read_u24()? as i32),
+            SignificantMotion
+            | SignificantMotionWakeup
+            | StepDetector
+            | StepDetectorWakeup
+            | TiltDetector
+            | TiltDetectorWakeup
+            | WakeGesture
+            | WakeGestureWakeup
+            | GlanceGesture
+            | GlanceGestureWakeup
+            | PickUpGesture
+            | PickUpGestureWakeup => SensorData::Event(reader.read_u8()?),
+            MagneticFieldUncalibrated
+            | MagneticFieldUncalibratedWakeup
+            | GyroscopeUncalibrated
+            | GyroscopeUncalibratedWakeup => SensorData::read_vector_bias_status(reader)?,
+            HeartRate | HeartRateWakeup => SensorData::Scalar(reader.read_u8()? as i32),
+            ActivityRecognition | ActivityRecognitionWakeup => {
+                SensorData::Scalar(reader.read_u16()? as i32)
+            }
+            Debug => SensorData::Debug(reader.read_array()?),
+            RawAccel | RawMag | RawGyro => SensorData::read_vector_timestamp(reader)?,
+            TimestampLsw | TimestampLswWakeup => SensorData::Scalar(reader.read_u16()? as i32),
+            TimestampMsw | TimestampMswWakeup => SensorData::Scalar(reader.read_u16()? as i32),
+            MetaEvent | MetaEventWakeup => SensorData::read_metaevent(reader)?,
+        };
+        Ok(Self { id, data })
+    }
+
+    pub fn id(&self) -> SensorId {
+        self.id
+    }
+
+    pub fn data(&self) -> &SensorData {
+        &self.data
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self.id(), SensorId::None)
+    }
+
+    /// Returns the data if this event was reported by `id` or its wakeup variant.
+    fn data_of(&self, id: SensorId) -> Option<&SensorData> {
+        (self.id.non_wakeup_variant() == id).then_some(&self.data)
+    }
+
+    /// Returns the data if this is a accelerometer event (wakeup or non-wakeup).
+    pub fn accel(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::Accelerometer)?.as_vector()
+    }
+
+    /// Returns the data if this is a gyroscope event (wakeup or non-wakeup).
+    pub fn gyro(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::Gyroscope)?.as_vector()
+    }
+
+    /// Returns the data if this is a geomagnetic field event (wakeup or non-wakeup).
+    pub fn mag(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::GeomagneticField)?.as_vector()
+    }
+
+    /// Returns the data if this is a orientation event (wakeup or non-wakeup).
+    pub fn orientation(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::Orientation)?.as_vector()
+    }
+
+    /// Returns the data if this is a gravity event (wakeup or non-wakeup).
+    pub fn gravity(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::Gravity)?.as_vector()
+    }
+
+    /// Returns the data if this is a linear acceleration event (wakeup or non-wakeup).
+    pub fn linear_acceleration(&self) -> Option<&Vector<i16>> {
+        self.data_of(SensorId::LinearAcceleration)?.as_vector()
+    }
+
+    /// Returns the data if this is a rotation vector event (wakeup or non-wakeup).
+    pub fn rotation_vector(&self) -> Option<&Quaternion<i16>> {
+        self.data_of(SensorId::RotationVector)?.as_quaternion()
+    }
+
+    /// Returns the data if this is a game rotation vector event (wakeup or non-wakeup).
+    pub fn game_rotation_vector(&self) -> Option<&Quaternion<i16>> {
+        self.data_of(SensorId::GameRotationVector)?.as_quaternion()
+    }
+
+    /// Returns the data if this is a geomagnetic rotation vector event (wakeup or non-wakeup).
+    pub fn geomagnetic_rotation_vector(&self) -> Option<&Quaternion<i16>> {
+        self.data_of(SensorId::GeomagneticRotationVector)?
+            .as_quaternion()
+    }
+
+    /// Returns the data if this is a pressure event (wakeup or non-wakeup).
+    pub fn pressure(&self) -> Option<i32> {
+        self.data_of(SensorId::Pressure)?.as_scalar()
+    }
+
+    /// Returns the data if this is a step counter event (wakeup or non-wakeup).
+    pub fn step_count(&self) -> Option<i32> {
+        self.data_of(SensorId::StepCounter)?.as_scalar()
+    }
+
+    /// Returns the meta event if this is a meta event (wakeup or non-wakeup).
+    pub fn meta_event(&self) -> Option<&MetaEvent> {
+        self.data.as_meta_event()
+    }
+}
+
+/// The data read from the FIFO is inconsistent.
+///
+/// This usually means the data got shifted or corrupted on the bus.
+/// See [`validate_fifo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CorruptFifo {
+    /// The offset of the first inconsistent event within the FIFO data.
+    pub offset: usize,
+}
+
+/// Sanity-checks raw FIFO data before it is decoded.
+///
+/// Walks the events in `data` and checks that every Sensor Type is known and that status bytes hold valid values.
+/// Checking stops at the first [`SensorId::None`] (i.e. zero padding) or at an incomplete event at the end of `data`
+/// (e.g. because the buffer was smaller than the FIFO).
+///
+/// Returns the number of bytes covered by complete events.
+/// If `data` contains the complete FIFO as announced by [`BytesRemaining`](crate::registers::BytesRemaining)
+/// this should match the length of `data`.
+pub fn validate_fifo(data: &[u8]) -> Result<usize, CorruptFifo> {
+    let mut offset = 0;
+    while let Some(&id) = data.get(offset) {
+        let id = SensorId::from_u8(id).ok_or(CorruptFifo { offset })?;
+        if id == SensorId::None {
+            break;
+        }
+        let size = id.event_size();
+        let event = match data.get(offset..offset + size) {
+            Some(event) => event,
+            None => break,
+        };
+        if !has_valid_status(id, event) {
+            return Err(CorruptFifo { offset });
+        }
+        offset += size;
+    }
+    Ok(offset)
+}
+
+/// Checks the status byte of the vector sensors (except the raw sensors), other events have none.
+fn has_valid_status(id: SensorId, event: &[u8]) -> bool {
+    match (id.event_size(), event.last()) {
+        (8 | 14, Some(&status)) if id != SensorId::Debug => SensorStatus::try_from(status).is_ok(),
+        _ => true,
+    }
+}
+
+/// The size of the largest event in bytes.
+pub const MAX_EVENT_SIZE: usize = 17;
+
+/// Decodes all complete events in `data` and passes them to `f`.
+///
+/// Decoding stops at an incomplete event at the end of `data`, so it can be completed by the next chunk read from the FIFO.
+/// Zero padding (i.e. [`SensorId::None`]) is skipped up to the end of `data`, a zero followed by other data yields a
+/// [`CorruptFifo`] like with [`Padding::Strict`].
+///
+/// Returns the number of bytes consumed.
+pub fn decode_events(data: &[u8], mut f: impl FnMut(Event)) -> Result<usize, CorruptFifo> {
+    decode_events_with::<core::convert::Infallible>(data, &[], |decoded| {
+        let Decoded::Event(event) = decoded;
+        f(event)
+    })
+}
+
+/// Decodes the events of a Sensor Type which [`Event::from_bytes`] does not know or should decode differently,
+/// e.g. custom virtual sensors of a firmware.
+#[derive(Debug, Clone, Copy)]
+pub struct CustomDecoder<T> {
+    /// The raw Sensor Type.
+    pub id: u8,
+    /// The number of bytes following the Sensor Type.
+    pub payload_size: usize,
+    /// Decodes the payload, `None` marks the event as corrupt.
+    pub decode: fn(&[u8]) -> Option<T>,
+}
+
+impl<T> CustomDecoder<T> {
+    pub const fn new(id: u8, payload_size: usize, decode: fn(&[u8]) -> Option<T>) -> Self {
+        Self {
+            id,
+            payload_size,
+            decode,
+        }
+    }
+}
+
+/// An event decoded by [`decode_events_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Decoded<T> {
+    /// An event decoded by the built-in decoder.
+    Event(Event),
+    /// The raw Sensor Type and the payload decoded by a [`CustomDecoder`].
+    Custom(u8, T),
+}
+
+/// Like [`decode_events`] but decodes the Sensor Types registered in `decoders` with the custom decoders.
+///
+/// The built-in decoding is used for all other Sensor Types.
+pub fn decode_events_with<T>(
+    data: &[u8],
+    decoders: &[CustomDecoder<T>],
+    f: impl FnMut(Decoded<T>),
+) -> Result<usize, CorruptFifo> {
+    decode_events_padded(data, decoders, Padding::Strict, &mut 0, f)
+}
+
+/// Like [`decode_events_with`] but handles zero bytes according to `padding`.
+///
+/// The number of zero bytes skipped with [`Padding::Lenient`] is added to `skipped`.
+pub fn decode_events_padded<T>(
+    data: &[u8],
+    decoders: &[CustomDecoder<T>],
+    padding: Padding,
+    skipped: &mut usize,
+    mut f: impl FnMut(Decoded<T>),
+) -> Result<usize, CorruptFifo> {
+    let mut offset = 0;
+    while let Some(&id) = data.get(offset) {
+        if id == 0 && padding == Padding::Lenient {
+            let zeros = data[offset..].iter().take_while(|&&byte| byte == 0).count();
+            offset += zeros;
+            *skipped += zeros;
+            continue;
+        }
+        if let Some(decoder) = decoders.iter().find(|decoder| decoder.id == id) {
+            let event = match data.get(offset..offset + 1 + decoder.payload_size) {
+                Some(event) => event,
+                None => break,
+            };
+            let value = (decoder.decode)(&event[1..]).ok_or(CorruptFifo { offset })?;
+            f(Decoded::Custom(id, value));
+            offset += event.len();
+            continue;
+        }
+        let size = match SensorId::from_u8(id) {
+            Some(SensorId::None) if data[offset..].iter().all(|&byte| byte == 0) => {
+                return Ok(data.len())
+            }
+            Some(SensorId::None) => return Err(CorruptFifo { offset }),
+            Some(id) => id.event_size(),
+            None => return Err(CorruptFifo { offset }),
+        };
+        let event = match data.get(offset..offset + size) {
+            Some(event) => event,
+            None => break,
+        };
+        f(Decoded::Event(
+            Event::from_bytes(event).ok_or(CorruptFifo { offset })?,
+        ));
+        offset += size;
+    }
+    Ok(offset)
+}
+
+/// A borrowed view of a single event in a FIFO buffer.
+///
+/// Unlike [`Event`] nothing is copied or decoded up front. The accessors decode only the requested values,
+/// which keeps hot paths on small MCUs cheap. Obtained by [`EventRefs`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventRef<'a> {
+    id: SensorId,
+    bytes: &'a [u8],
+}
+
+impl<'a> EventRef<'a> {
+    pub fn id(&self) -> SensorId {
+        self.id
+    }
+
+    /// The raw bytes of the event, including the Sensor Type.
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The raw bytes of the event following the Sensor Type.
+    pub fn payload(&self) -> &'a [u8] {
+        &self.bytes[1..]
+    }
+
+    /// Decodes the 16 bit integers of a vector sensor (e.g. [`SensorId::Accelerometer`]).
+    ///
+    /// Returns `None` if the event carries no 16 bit vector, use [`raw_vector`](Self::raw_vector) for the raw sensors.
+    pub fn vector(&self) -> Option<Vector<i16, 3>> {
+        match self.id.event_size() {
+            8 | 14 if self.id != SensorId::Debug => {
+                let mut vector = [0; 3];
+                for (value, bytes) in vector.iter_mut().zip(self.payload().chunks_exact(2)) {
+                    *value = i16::from_le_bytes([bytes[0], bytes[1]]);
+                }
+                Some(Vector(vector))
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the 32 bit integers and the timestamp of a raw sensor (e.g. [`SensorId::RawAccel`]).
+    ///
+    /// Returns `None` for all other sensors.
+    pub fn raw_vector(&self) -> Option<(Vector<i32, 3>, u32)> {
+        if !matches!(
+            self.id,
+            SensorId::RawAccel | SensorId::RawMag | SensorId::RawGyro
+        ) {
+            return None;
+        }
+        let mut words = self
+            .payload()
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let mut vector = [0; 3];
+        for value in &mut vector {
+            *value = words.next()? as i32;
+        }
+        Some((Vector(vector), words.next()?))
+    }
+
+    /// Decodes the accuracy of the vector sensors (except the raw sensors).
+    pub fn status(&self) -> Option<SensorStatus> {
+        match self.id.event_size() {
+            8 | 14 if self.id != SensorId::Debug => {
+                SensorStatus::try_from(self.bytes[self.bytes.len() - 1]).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes the whole event.
+    pub fn to_event(&self) -> Event {
+        // The length and the status have been checked by `EventRefs`, so reading can not fail
+        Event::from_bytes(self.bytes).unwrap_or(Event::new(SensorId::None, SensorData::None))
+    }
+}
+
+/// How [`EventRefs`] and [`decode_events_padded`] handle zero bytes (i.e. [`SensorId::None`]) between the events.
+///
+/// Some hosts read past the end of the FIFO and receive zero padding. Stray zeros in the middle of the data are
+/// usually caused by the bus, e.g. by a transfer split at the wrong boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Padding {
+    /// Zeros up to the end of the data are padding and end the iteration.
+    /// A zero followed by other data yields a [`CorruptFifo`] at the offset of the zero.
+    #[default]
+    Strict,
+    /// Zeros are skipped wherever they occur and counted, see [`EventRefs::skipped`] and
+    /// [`DrainProgress::skipped`](crate::DrainProgress::skipped).
+    Lenient,
+}
+
+/// An iterator over the complete events in a buffer, see [`EventRef`].
+///
+/// Stops at an incomplete event like [`decode_events`], zero bytes are handled according to the [`Padding`].
+/// Use [`consumed`](Self::consumed) to find the start of an incomplete event.
+/// Unknown Sensor Types and invalid status bytes yield a [`CorruptFifo`] and end the iteration.
+#[derive(Debug, Clone)]
+pub struct EventRefs<'a> {
+    data: &'a [u8],
+    offset: usize,
+    padding: Padding,
+    skipped: usize,
+}
+
+impl<'a> EventRefs<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            padding: Padding::default(),
+            skipped: 0,
+        }
+    }
+
+    /// Sets how zero bytes between the events are handled, [`Padding::Strict`] by default.
+    pub fn with_padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn consumed(&self) -> usize {
+        self.offset
+    }
+
+    /// The number of zero bytes skipped so far with [`Padding::Lenient`].
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+impl<'a> Iterator for EventRefs<'a> {
+    type Item = Result<EventRef<'a>, CorruptFifo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.padding == Padding::Lenient {
+            let zeros = self.data[self.offset..]
+                .iter()
+                .take_while(|&&byte| byte == 0)
+                .count();
+            self.offset += zeros;
+            self.skipped += zeros;
+        }
+        let offset = self.offset;
+        let id = match SensorId::from_u8(*self.data.get(offset)?) {
+            Some(SensorId::None) => {
+                self.offset = self.data.len();
+                if self.data[offset..].iter().any(|&byte| byte != 0) {
+                    return Some(Err(CorruptFifo { offset }));
+                }
+                return None;
+            }
+            Some(id) => id,
+            None => {
+                self.offset = self.data.len();
+                return Some(Err(CorruptFifo { offset }));
+            }
+        };
+        let bytes = self.data.get(offset..offset + id.event_size())?;
+        if !has_valid_status(id, bytes) {
+            self.offset = self.data.len();
+            return Some(Err(CorruptFifo { offset }));
+        }
+        self.offset += bytes.len();
+        Some(Ok(EventRef { id, bytes }))
+    }
+}
+
+/// Decodes all events in `data`, which has to contain complete events only (e.g. a whole FIFO transfer).
+///
+/// Returns an error if the data is inconsistent or ends with an incomplete event.
+#[cfg(feature = "alloc")]
+pub fn decode_events_vec(data: &[u8]) -> Result<alloc::vec::Vec<Event>, CorruptFifo> {
+    let mut events = alloc::vec::Vec::new();
+    let consumed = decode_events(data, |event| events.push(event))?;
+    if consumed != data.len() {
+        return Err(CorruptFifo { offset: consumed });
+    }
+    Ok(events)
+}
+
+/// Reads events from a [`Read`] source until the data ends or a [`SensorId::None`] (padding) is encountered.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct EventReader<R>
+where
+    R: Read,
+{
+    reader: R,
+    /// The number of bytes up to the end of the last complete event.
+    position: usize,
+    /// The number of bytes read after the last complete event.
+    partial: usize,
+}
+
+#[cfg(feature = "std")]
+impl<R> EventReader<R>
+where
+    R: Read,
+{
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            position: 0,
+            partial: 0,
+        }
+    }
+
+    /// The number of bytes read from the source up to the end of the last complete event.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The number of bytes read from the source after the last complete event,
+    /// i.e. the bytes of a truncated event or padding.
+    pub fn partial(&self) -> usize {
+        self.partial
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> EventReader<Cursor<T>>
+where
+    T: AsRef<[u8]>,
+{
+    /// The data after the last complete event.
+    ///
+    /// Once the iteration stopped this contains the bytes which could not be parsed,
+    /// e.g. a partial event which can be completed with the next FIFO read.
+    pub fn remaining(&self) -> &[u8] {
+        let start = self.reader.position() as usize - self.partial;
+        &self.reader.get_ref().as_ref()[start..]
+    }
+}
+
+/// Counts the bytes read from the inner reader.
+#[cfg(feature = "std")]
+struct CountingReader<'a, R> {
+    inner: &'a mut R,
+    count: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R> Read for CountingReader<'a, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.count += len;
+        Ok(len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R> Iterator for EventReader<R>
+where
+    R: Read,
+{
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut reader = CountingReader {
+            inner: &mut self.reader,
+            count: 0,
+        };
+        match Event::read(&mut reader) {
+            Ok(event) if !event.is_none() => {
+                self.position += self.partial + reader.count;
+                self.partial = 0;
+                Some(event)
+            }
+            _ => {
+                self.partial += reader.count;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "async", feature = "std"))]
+impl<R> futures_core::Stream for EventReader<R>
+where
+    R: Read + Unpin,
+{
+    type Item = Event;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        _cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Self::Item>> {
+        core::task::Poll::Ready(self.get_mut().next())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "std")]
+    use std::io::Cursor;
+
+    #[test]
+    fn custom_decoder() {
+        const DECODERS: [CustomDecoder<u32>; 2] = [
+            // A custom virtual sensor
+            CustomDecoder::new(26, 4, |payload| {
+                Some(u32::from_le_bytes(payload.try_into().ok()?))
+            }),
+            // Overrides the built-in decoding of the heart rate
+            CustomDecoder::new(21, 1, |payload| {
+                (payload[0] != 0).then_some(payload[0] as u32)
+            }),
+        ];
+        let data = [26, 1, 2, 0, 0, 21, 60, 19, 5, 0, 26, 1];
+        let mut events = Vec::new();
+        assert_eq!(
+            decode_events_with(&data, &DECODERS, |event| events.push(event)),
+            Ok(10)
+        );
+        assert_eq!(
+            events,
+            [
+                Decoded::Custom(26, 0x0201),
+                Decoded::Custom(21, 60),
+                Decoded::Event(Event::new(SensorId::StepCounter, SensorData::Scalar(5))),
+            ]
+        );
+        assert_eq!(
+            decode_events_with(&[21, 0], &DECODERS, |_| {}),
+            Err(CorruptFifo { offset: 0 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_single() {
+        let data = [0x01, 0xFE, 0xFF, 0x05, 0x00, 0x69, 0x08, 0x02];
+        let mut cursor = Cursor::new(data);
+        let event = Event::read(&mut cursor).expect("Cannot read event");
+        assert_eq!(
+            event,
+            Event {
+                id: SensorId::Accelerometer,
+                data: SensorData::VectorStatus(Vector([-2, 5, 2153]), SensorStatus::Medium),
+            }
+        )
+    }
+
+    #[test]
+    fn decode_single() {
+        let data = [0x01, 0xFE, 0xFF, 0x05, 0x00, 0x69, 0x08, 0x02, 0x12];
+        assert_eq!(
+            Event::from_bytes(&data),
+            Some(Event::new(
+                SensorId::Accelerometer,
+                SensorData::VectorStatus(Vector([-2, 5, 2153]), SensorStatus::Medium),
+            ))
+        );
+        assert_eq!(Event::from_bytes(&data[..7]), None);
+        assert_eq!(Event::from_bytes(&[0x01, 0, 0, 0, 0, 0, 0, 0x07]), None);
+        assert_eq!(Event::from_bytes(&[0x1A, 0x00]), None);
+    }
+
+    #[test]
+    fn read_power_mode_changed() {
+        let event = MetaEvent::from_bytes([3, SensorId::Accelerometer as u8, 7]);
+        let MetaEvent::PowerModeChanged(SensorId::Accelerometer, mode) = event else {
+            panic!("unexpected {event:?}");
+        };
+        assert_eq!(mode.mode(), Some(SensorPowerMode::Active));
+
+        // An unknown power mode is passed through
+        let event = MetaEvent::from_bytes([3, SensorId::Accelerometer as u8, 0x42]);
+        assert_eq!(
+            event,
+            MetaEvent::PowerModeChanged(SensorId::Accelerometer, PowerMode(0x42))
+        );
+        assert_eq!(PowerMode(0x42).mode(), None);
+    }
+
+    #[test]
+    fn read_sensor_error() {
+        let status = 0b1110_0010;
+        let event = MetaEvent::from_bytes([11, SensorId::Gyroscope as u8, status]);
+        let MetaEvent::SensorError(SensorId::Gyroscope, flags) = event else {
+            panic!("unexpected {event:?}");
+        };
+        assert!(flags.i2c_nack() && !flags.data_lost());
+        assert_eq!(flags.sensor_power_mode(), SensorPowerMode::Active);
+
+        let mut bank = [0; 15];
+        bank[9] = status;
+        assert_eq!(
+            system::PhysicalSensorStatus::from_bytes(bank).gyro_flags(),
+            flags
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_unknown_meta_event() {
+        let data = [
+            0xFE, 0x1F, 0x12, 0x34, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let events: Vec<_> = EventReader::new(Cursor::new(data)).collect();
+        assert_eq!(
+            events,
+            [
+                Event {
+                    id: SensorId::MetaEvent,
+                    data: SensorData::MetaEvent(MetaEvent::Unknown {
+                        id: 0x1F,
+                        payload: [0x12, 0x34],
+                    }),
+                },
+                Event {
+                    id: SensorId::Accelerometer,
+                    data: SensorData::VectorStatus(Vector([2, 0, 0]), SensorStatus::Unreliable),
+                },
+            ]
+        )
+    }
+
+    #[test]
+    fn validate_corrupt_fifo() {
+        let valid = [0x01, 0xFE, 0xFF, 0x05, 0x00, 0x69, 0x08, 0x02, 0x12, 0x00];
+        assert_eq!(validate_fifo(&valid), Ok(valid.len()));
+        assert_eq!(validate_fifo(&valid[..9]), Ok(8));
+
+        let bad_status = [0x12, 0x00, 0x01, 0xFE, 0xFF, 0x05, 0x00, 0x69, 0x08, 0x07];
+        assert_eq!(validate_fifo(&bad_status), Err(CorruptFifo { offset: 2 }));
+
+        let unknown_id = [0x12, 0x00, 0x1A, 0x00];
+        assert_eq!(validate_fifo(&unknown_id), Err(CorruptFifo { offset: 2 }));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn reader_remaining() {
+        // A complete accelerometer event followed by a truncated one
+        let data = [0x01, 0xFE, 0xFF, 0x05, 0x00, 0x69, 0x08, 0x02, 0x01, 0x03];
+        let mut reader = EventReader::new(Cursor::new(data));
+        assert!(reader.next().is_some());
+        assert!(reader.next().is_none());
+        assert_eq!(reader.position(), 8);
+        assert_eq!(reader.partial(), 2);
+        assert_eq!(reader.remaining(), &[0x01, 0x03]);
+        assert_eq!(reader.into_inner().position(), 10);
+    }
+
+    #[test]
+    fn borrowed_events() {
+        let data = [0xFC, 0x34, 0x12, 1, 0xFE, 0xFF, 5, 0, 0x69, 8, 2, 1, 0];
+        let mut refs = EventRefs::new(&data);
+        let timestamp = refs.next().unwrap().unwrap();
+        assert_eq!(timestamp.payload(), [0x34, 0x12]);
+        assert_eq!(timestamp.vector(), None);
+        let accel = refs.next().unwrap().unwrap();
+        assert_eq!(accel.vector(), Some(Vector([-2, 5, 2153])));
+        assert_eq!(accel.status(), Some(SensorStatus::Medium));
+        assert_eq!(
+            accel.to_event().data(),
+            &SensorData::VectorStatus(Vector([-2, 5, 2153]), SensorStatus::Medium)
+        );
+        assert!(refs.next().is_none());
+        assert_eq!(refs.consumed(), 11);
+    }
+
+    #[test]
+    fn raw_event() {
+        let mut data = vec![SensorId::RawGyro as u8];
+        for value in [-2i32, 70_000, 5] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(0x1234_5678u32.to_le_bytes());
+        let expected = (Vector([-2, 70_000, 5]), 0x1234_5678);
+        assert_eq!(
+            Event::from_bytes(&data).unwrap().data(),
+            &SensorData::VectorTimestamp(expected.0, expected.1)
+        );
+        let event = EventRefs::new(&data).next().unwrap().unwrap();
+        assert_eq!(event.raw_vector(), Some(expected));
+        assert_eq!(event.vector(), None);
+        assert_eq!(event.status(), None);
+        assert_eq!(
+            event.to_event().data(),
+            &SensorData::VectorTimestamp(expected.0, expected.1)
+        );
+    }
+
+    #[test]
+    fn zero_padding() {
+        // A timestamp, a stray zero, a timestamp and trailing padding
+        let data = [0xFC, 0x34, 0x12, 0, 0xFC, 0x35, 0x12, 0, 0];
+        let mut strict = EventRefs::new(&data);
+        assert!(strict.next().unwrap().is_ok());
+        assert_eq!(strict.next(), Some(Err(CorruptFifo { offset: 3 })));
+        assert_eq!(strict.next(), None);
+        let mut padded = EventRefs::new(&data[4..]);
+        assert!(padded.next().unwrap().is_ok());
+        assert_eq!(padded.next(), None);
+        assert_eq!(padded.consumed(), 5);
+        let mut events = 0;
+        assert_eq!(
+            decode_events(&data, |_| events += 1),
+            Err(CorruptFifo { offset: 3 })
+        );
+        assert_eq!(decode_events(&data[4..], |_| events += 1), Ok(5));
+        assert_eq!(events, 2);
+
+        let mut lenient = EventRefs::new(&data).with_padding(Padding::Lenient);
+        let payloads: Vec<_> = lenient
+            .by_ref()
+            .map(|event| event.unwrap().payload())
+            .collect();
+        assert_eq!(payloads, [[0x34, 0x12], [0x35, 0x12]]);
+        assert_eq!(lenient.skipped(), 3);
+        assert_eq!(lenient.consumed(), data.len());
+
+        let mut skipped = 0;
+        let decoders: [CustomDecoder<()>; 0] = [];
+        assert_eq!(
+            decode_events_padded(&data, &decoders, Padding::Lenient, &mut skipped, |_| {
+                events += 1
+            }),
+            Ok(data.len())
+        );
+        assert_eq!((events, skipped), (4, 3));
+    }
+}
@@ -0,0 +1,217 @@
+//! Filters for the stream of decoded events.
+
+use crate::{
+    fifo::{Event, SensorData},
+    parameters::sensors::SensorId,
+    timestamp::TimestampTracker,
+};
+
+/// The number of sensor ids (wakeup and non-wakeup) which can be configured in a [`Dedup`].
+const SENSOR_SLOTS: usize = 64;
+
+/// Drops repeated identical events of a sensor within a configurable window.
+///
+/// Some firmwares emit bursts of identical events for on-change sensors such as gestures or the step detector.
+/// An event is dropped if the previous event of the same sensor carried the same data and is at most
+/// the configured number of ticks older. The window starts at the first event of a burst, so a sensor which keeps
+/// reporting the same value is still passed through once per window.
+///
+/// The timestamps are reconstructed from the timestamp events, so all events have to be passed to [`Self::filter`].
+#[derive(Debug, Clone)]
+pub struct Dedup {
+    timestamps: TimestampTracker,
+    windows: [u32; SENSOR_SLOTS],
+    last: [Option<(SensorData, u32)>; SENSOR_SLOTS],
+}
+
+impl Default for Dedup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dedup {
+    /// Creates a filter which does not drop any events.
+    pub fn new() -> Self {
+        Self {
+            timestamps: TimestampTracker::new(),
+            windows: [0; SENSOR_SLOTS],
+            last: [None; SENSOR_SLOTS],
+        }
+    }
+
+    /// Sets the window (in ticks) for the given sensor. 0 disables the filter for this sensor.
+    ///
+    /// Only sensors with ids below 64 (i.e. not the debug, raw, timestamp or meta events) can be filtered.
+    pub fn with_window(mut self, id: SensorId, ticks: u32) -> Self {
+        self.set_window(id, ticks);
+        self
+    }
+
+    /// See [`Self::with_window`].
+    pub fn set_window(&mut self, id: SensorId, ticks: u32) {
+        if let Some(window) = self.windows.get_mut(id as usize) {
+            *window = ticks;
+            self.last[id as usize] = None;
+        }
+    }
+
+    /// Processes an event and returns `false` if it is a duplicate which should be dropped.
+    pub fn filter(&mut self, event: &Event) -> bool {
+        let tick = self.timestamps.update(event);
+        let slot = event.id() as usize;
+        let window = match self.windows.get(slot) {
+            Some(&window) if window > 0 => window,
+            _ => return true,
+        };
+        if let Some((data, last)) = &self.last[slot] {
+            if data == event.data() && tick.wrapping_sub(*last) <= window {
+                return false;
+            }
+        }
+        self.last[slot] = Some((*event.data(), tick));
+        true
+    }
+
+    /// The timestamps reconstructed from the events seen so far.
+    pub fn timestamps(&self) -> &TimestampTracker {
+        &self.timestamps
+    }
+}
+
+/// How a [`Decimator`] thins out the samples of a sensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Decimation {
+    /// Forward every sample.
+    None,
+    /// Forward only every nth sample, starting with the first one.
+    Every(u32),
+    /// Forward a sample only if it is at least the given number of ticks younger than the last forwarded one.
+    MinSpacing(u32),
+}
+
+/// Forwards only a subset of the samples of configured sensors.
+///
+/// This allows e.g. to log 200 Hz raw data while updating a UI with 25 Hz.
+/// Events of sensors without a configured [`Decimation`] (including timestamp and meta events) are always forwarded.
+///
+/// The timestamps are reconstructed from the timestamp events, so all events have to be passed to [`Self::filter`].
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    timestamps: TimestampTracker,
+    rules: [Decimation; SENSOR_SLOTS],
+    /// The number of samples dropped since the last forwarded one.
+    skipped: [u32; SENSOR_SLOTS],
+    last: [Option<u32>; SENSOR_SLOTS],
+}
+
+impl Default for Decimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decimator {
+    /// Creates a decimator which forwards all events.
+    pub fn new() -> Self {
+        Self {
+            timestamps: TimestampTracker::new(),
+            rules: [Decimation::None; SENSOR_SLOTS],
+            skipped: [0; SENSOR_SLOTS],
+            last: [None; SENSOR_SLOTS],
+        }
+    }
+
+    /// Sets the decimation of the given sensor.
+    ///
+    /// Only sensors with ids below 64 (i.e. not the debug, raw, timestamp or meta events) can be decimated.
+    pub fn with(mut self, id: SensorId, decimation: Decimation) -> Self {
+        self.set(id, decimation);
+        self
+    }
+
+    /// See [`Self::with`].
+    pub fn set(&mut self, id: SensorId, decimation: Decimation) {
+        if let Some(rule) = self.rules.get_mut(id as usize) {
+            *rule = decimation;
+            self.skipped[id as usize] = 0;
+            self.last[id as usize] = None;
+        }
+    }
+
+    /// Processes an event and returns `true` if it should be forwarded.
+    pub fn filter(&mut self, event: &Event) -> bool {
+        let tick = self.timestamps.update(event);
+        let slot = event.id() as usize;
+        let forward = match self.rules.get(slot) {
+            None | Some(Decimation::None) => return true,
+            Some(Decimation::Every(n)) => self.last[slot].is_none() || self.skipped[slot] + 1 >= *n,
+            Some(Decimation::MinSpacing(ticks)) => match self.last[slot] {
+                Some(last) => tick.wrapping_sub(last) >= *ticks,
+                None => true,
+            },
+        };
+        if forward {
+            self.skipped[slot] = 0;
+            self.last[slot] = Some(tick);
+        } else {
+            self.skipped[slot] += 1;
+        }
+        forward
+    }
+
+    /// The timestamps reconstructed from the events seen so far.
+    pub fn timestamps(&self) -> &TimestampTracker {
+        &self.timestamps
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fifo::SensorStatus, math::Vector};
+
+    fn timestamp(lsw: u16) -> Event {
+        Event::new(SensorId::TimestampLsw, SensorData::Scalar(lsw as i32))
+    }
+
+    #[test]
+    fn drop_burst() {
+        let step = Event::new(SensorId::StepDetector, SensorData::Event(0));
+        let mut dedup = Dedup::new().with_window(SensorId::StepDetector, 10);
+        let passed: Vec<bool> = [
+            timestamp(100),
+            step,
+            timestamp(105),
+            step,
+            timestamp(111),
+            step,
+        ]
+        .iter()
+        .map(|event| dedup.filter(event))
+        .collect();
+        assert_eq!(passed, [true, true, true, false, true, true]);
+    }
+
+    #[test]
+    fn decimate() {
+        let accel = Event::new(
+            SensorId::Accelerometer,
+            SensorData::VectorStatus(Vector([0, 0, 0]), SensorStatus::High),
+        );
+        let mut every = Decimator::new().with(SensorId::Accelerometer, Decimation::Every(3));
+        let passed: Vec<bool> = (0..7).map(|_| every.filter(&accel)).collect();
+        assert_eq!(passed, [true, false, false, true, false, false, true]);
+
+        let mut spaced = Decimator::new().with(SensorId::Accelerometer, Decimation::MinSpacing(20));
+        let passed: Vec<bool> = [0, 10, 19, 20, 35, 45]
+            .into_iter()
+            .flat_map(|tick| [timestamp(tick), accel])
+            .map(|event| spaced.filter(&event))
+            .collect();
+        assert_eq!(
+            passed,
+            [true, true, true, false, true, false, true, true, true, false, true, true]
+        );
+    }
+}
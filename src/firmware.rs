@@ -1,3 +1,10 @@
+use core::fmt;
+
+use crate::{
+    interface::Interface,
+    registers::{ChipVariant, RamVersion, RomVersion, UploadAddress, UploadCrc},
+    Bhi160, Error,
+};
 
 /// Wraps a firmware blob to allow extracting various information such as signature and crc.
 /// You will need to download the correct firmware blob for your sesnor from bosch [here](https://www.bosch-sensortec.com/products/smart-sensors/bhi160-firmware/).
@@ -30,18 +37,18 @@ where
     pub fn new(inner: T) -> Option<Self> {
         if inner.as_ref().len() < HEADER_LEN {
             #[cfg(feature = "log")]
-            log::error!("Firmware too short");
+            log::error!(len = inner.as_ref().len(); "Firmware too short");
             return None;
         }
         let result = Self(inner);
         if result.signature() != SIGNATURE {
             #[cfg(feature = "log")]
-            log::error!("Firmware signature missmatch: is {:04x} should be {:04x}", result.signature(), SIGNATURE);
+            log::error!(signature = result.signature(), expected = SIGNATURE; "Firmware signature missmatch");
             return None;
         }
         if result.data_len() + 16 != result.0.as_ref().len() {
             #[cfg(feature = "log")]
-            log::error!("Firmware length missmatch: is {} should be {}", result.data_len(), result.0.as_ref().len() + 16);
+            log::error!(len = result.0.as_ref().len(), expected = result.data_len() + 16; "Firmware length missmatch");
             return None
         }
         Some(result)
@@ -63,16 +70,423 @@ where
         u32::from_le_bytes(self.0.as_ref()[4..=7].try_into().unwrap())
     }
     
+    /// Get the flags of the firmware header.
+    pub fn flags(&self) -> u32 {
+        u32::from_le_bytes(self.0.as_ref()[8..=11].try_into().unwrap())
+    }
+
     /// Get the length of the body.
     pub fn data_len(&self) -> usize {
         u16::from_le_bytes(self.0.as_ref()[12..=13].try_into().unwrap()) as usize
     }
 
+    /// Get a summary of the header information.
+    pub fn info(&self) -> FirmwareInfo {
+        FirmwareInfo {
+            rom_version: self.rom_version(),
+            crc: self.crc(),
+            data_len: self.data_len(),
+            flags: self.flags(),
+        }
+    }
+
     /// Get the body of the firmware (i.e. the actual data to upload).
     /// This performes the necessary byteswapping required (see section 10.22 of the datasheet).
     pub fn body(&self) -> impl Iterator<Item = u8> + '_ {
         self.0.as_ref()[HEADER_LEN..]
-            .array_chunks::<4>()
+            .chunks_exact(4)
             .flat_map(|chunk| chunk.iter().rev().copied())
     }
+
+    /// Get the body in bursts of 16 bytes, byte swapped like [`Self::body`].
+    ///
+    /// Only complete bursts are returned. The rest of a body whose length is not a multiple of 16
+    /// is returned by [`Self::body_remainder`].
+    pub fn body_chunks(&self) -> impl Iterator<Item = [u8; 16]> + '_ {
+        self.0.as_ref()[HEADER_LEN..].chunks_exact(16).map(|chunk| {
+            let mut burst = [0; 16];
+            for (swapped, word) in burst.chunks_exact_mut(4).zip(chunk.chunks_exact(4)) {
+                swapped.copy_from_slice(word);
+                swapped.reverse();
+            }
+            burst
+        })
+    }
+
+    /// Get the bytes of the body following the last complete chunk of [`Self::body_chunks`], byte swapped.
+    pub fn body_remainder(&self) -> impl Iterator<Item = u8> + '_ {
+        let body = &self.0.as_ref()[HEADER_LEN..];
+        body[body.len() / 16 * 16..]
+            .chunks_exact(4)
+            .flat_map(|word| word.iter().rev().copied())
+    }
+}
+
+/// A firmware blob whose body is compressed with [`heatshrink`](crate::heatshrink) (with the `heatshrink` feature).
+///
+/// The blob consists of the 16 byte header of the original firmware followed by the compressed body.
+/// The body is decompressed on the fly, upload it with
+/// [`Uploader::upload_compressed_firmware`].
+#[cfg(feature = "heatshrink")]
+pub struct CompressedFirmware<T>(T)
+where
+    T: AsRef<[u8]>;
+
+#[cfg(feature = "heatshrink")]
+impl<T> CompressedFirmware<T>
+where
+    T: AsRef<[u8]>,
+{
+    /// Create a new `CompressedFirmware`.
+    ///
+    /// Returns `None` if the header is invalid or the body does not decompress to the length announced by the header.
+    /// This decompresses the whole body once.
+    pub fn new(inner: T) -> Option<Self> {
+        if inner.as_ref().len() < HEADER_LEN {
+            #[cfg(feature = "log")]
+            log::error!(len = inner.as_ref().len(); "Firmware too short");
+            return None;
+        }
+        let result = Self(inner);
+        if result.header().signature() != SIGNATURE {
+            #[cfg(feature = "log")]
+            log::error!(signature = result.header().signature(), expected = SIGNATURE; "Firmware signature missmatch");
+            return None;
+        }
+        let len = result.decompress().count();
+        if len != result.data_len() {
+            #[cfg(feature = "log")]
+            log::error!(len = len, expected = result.data_len(); "Compressed firmware length missmatch");
+            return None;
+        }
+        Some(result)
+    }
+
+    /// The header of the original firmware.
+    fn header(&self) -> Firmware<&[u8]> {
+        Firmware(&self.0.as_ref()[..HEADER_LEN])
+    }
+
+    /// The raw blob, e.g. to be written to a file.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+
+    /// Get the expected CRC, see [`Firmware::crc`].
+    pub fn crc(&self) -> u32 {
+        self.header().crc()
+    }
+
+    /// Get the length of the decompressed body.
+    pub fn data_len(&self) -> usize {
+        self.header().data_len()
+    }
+
+    /// Get a summary of the header information.
+    pub fn info(&self) -> FirmwareInfo {
+        self.header().info()
+    }
+
+    fn decompress(&self) -> impl Iterator<Item = u8> + '_ {
+        crate::heatshrink::Decoder::new(&self.0.as_ref()[HEADER_LEN..]).take(self.data_len())
+    }
+
+    /// Get the decompressed body, byte swapped like [`Firmware::body`].
+    pub fn body(&self) -> impl Iterator<Item = u8> + '_ {
+        let mut body = self.decompress();
+        core::iter::from_fn(move || {
+            let mut word = [0; 4];
+            for slot in &mut word {
+                *slot = body.next()?;
+            }
+            Some(word)
+        })
+        .flat_map(|word| word.into_iter().rev())
+    }
+
+    /// Get the decompressed body in bursts of 16 bytes, like [`Firmware::body_chunks`].
+    pub fn body_chunks(&self) -> impl Iterator<Item = [u8; 16]> + '_ {
+        let mut body = self.body();
+        (0..self.data_len() / 16).map(move |_| {
+            let mut burst = [0; 16];
+            for (slot, byte) in burst.iter_mut().zip(&mut body) {
+                *slot = byte;
+            }
+            burst
+        })
+    }
+
+    /// Get the bytes of the body following the last complete chunk of [`Self::body_chunks`], like
+    /// [`Firmware::body_remainder`]. This decompresses the whole body again.
+    pub fn body_remainder(&self) -> impl Iterator<Item = u8> + '_ {
+        self.body().skip(self.data_len() / 16 * 16)
+    }
+}
+
+#[cfg(all(feature = "heatshrink", feature = "alloc"))]
+impl CompressedFirmware<alloc::vec::Vec<u8>> {
+    /// Compresses the body of `firmware`, e.g. in a build script.
+    pub fn compress<T>(firmware: &Firmware<T>) -> Self
+    where
+        T: AsRef<[u8]>,
+    {
+        let (header, body) = firmware.0.as_ref().split_at(HEADER_LEN);
+        let mut blob = header.to_vec();
+        blob.extend(crate::heatshrink::compress(body));
+        Self(blob)
+    }
+}
+
+/// A part of a RAM patch which is uploaded to a specific address.
+///
+/// Some patches are distributed as multiple segments which do not form a contiguous block.
+/// Upload them with [`Uploader::upload_segment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment<T>
+where
+    T: AsRef<[u8]>,
+{
+    /// The RAM address of the first byte (see [`UploadAddress`](crate::registers::UploadAddress)).
+    pub address: u16,
+    /// The data to upload, already byte swapped like [`Firmware::body`].
+    /// The length should be a multiple of 4.
+    pub data: T,
+}
+
+impl<T> Segment<T>
+where
+    T: AsRef<[u8]>,
+{
+    pub fn new(address: u16, data: T) -> Self {
+        Self { address, data }
+    }
+}
+
+/// The address of the Upload Data register.
+const UPLOAD_DATA_ADDR: u8 = 0x96;
+
+/// Streams a RAM patch to the BHI while the upload mode is enabled.
+///
+/// Obtained by [`Bhi160::begin_upload`]. The guard borrows the driver, so nothing else (e.g. reading the FIFO)
+/// can interleave with the upload and corrupt the patch. Use [`Self::finish`] to check the CRC and leave the upload
+/// mode, otherwise the upload mode is disabled (ignoring errors) when the guard is dropped.
+///
+/// ```ignore
+/// bhi.halt_cpu()?;
+/// let mut upload = bhi.begin_upload()?;
+/// upload.upload_firmware(&firmware)?;
+/// upload.finish(firmware.crc())?;
+/// bhi.start_cpu()?;
+/// ```
+pub struct Uploader<'a, IF>
+where
+    IF: Interface,
+{
+    bhi: &'a mut Bhi160<IF>,
+    finished: bool,
+}
+
+impl<'a, IF> Uploader<'a, IF>
+where
+    IF: Interface,
+{
+    pub(crate) fn new(bhi: &'a mut Bhi160<IF>) -> Self {
+        Self {
+            bhi,
+            finished: false,
+        }
+    }
+
+    /// Sets the RAM address the following chunks are written to.
+    pub fn set_address(&mut self, address: u16) -> Result<(), IF::Error> {
+        self.bhi.write_reg(UploadAddress(address))
+    }
+
+    /// Writes a chunk of the patch (already byte swapped) at the current address, which is advanced by the BHI.
+    ///
+    /// The length should be a multiple of 4.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> Result<(), IF::Error> {
+        self.bhi.bus_write(UPLOAD_DATA_ADDR, chunk)
+    }
+
+    /// Writes the body of `firmware` starting at address 0.
+    ///
+    /// The body is taken from `firmware` burst by burst (see [`Firmware::body_chunks`]), so no intermediate buffer
+    /// is needed. Returns the CRC calculated by the BHI so far.
+    pub fn upload_firmware<T>(&mut self, firmware: &Firmware<T>) -> Result<u32, IF::Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.set_address(0)?;
+        self.bhi
+            .upload_body(firmware.body_chunks(), firmware.body_remainder())
+    }
+
+    /// Writes the decompressed body of `firmware` starting at address 0, see [`Self::upload_firmware`].
+    ///
+    /// The body is decompressed on the fly burst by burst (see [`CompressedFirmware::body_chunks`]).
+    ///
+    /// Returns the CRC calculated by the BHI so far.
+    #[cfg(feature = "heatshrink")]
+    pub fn upload_compressed_firmware<T>(
+        &mut self,
+        firmware: &CompressedFirmware<T>,
+    ) -> Result<u32, IF::Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.set_address(0)?;
+        self.bhi
+            .upload_body(firmware.body_chunks(), firmware.body_remainder())
+    }
+
+    /// Writes a segment of the patch at its address.
+    ///
+    /// The BHI accumulates the CRC over all uploaded bytes until the upload mode is left, so the CRC checked by
+    /// [`Self::finish`] covers all segments and can be compared to the CRC of the complete patch.
+    pub fn upload_segment<T>(&mut self, segment: &Segment<T>) -> Result<(), IF::Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.set_address(segment.address)?;
+        for chunk in segment.data.as_ref().chunks(16) {
+            self.write_chunk(chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the CRC the BHI calculated over all chunks written so far.
+    pub fn crc(&mut self) -> Result<u32, IF::Error> {
+        let UploadCrc(crc) = self.bhi.read_reg()?;
+        Ok(crc)
+    }
+
+    /// Reads the uploaded patch back and compares it with `firmware`, see [`Bhi160::verify_uploaded_firmware`].
+    ///
+    /// The upload mode is still enabled here, which is required for reading the upload data port.
+    pub fn verify<T>(&mut self, firmware: &Firmware<T>) -> Result<bool, Error<IF::Error>>
+    where
+        T: AsRef<[u8]>,
+    {
+        self.bhi.verify_uploaded_firmware(firmware)
+    }
+
+    /// Compares the CRC of the upload with `expected_crc` (e.g. [`Firmware::crc`]) and disables the upload mode.
+    ///
+    /// The upload mode is disabled even if the CRC does not match, which is reported as [`Error::CrcMismatch`].
+    /// Afterwards the CPU may be started using [`Bhi160::start_cpu`].
+    pub fn finish(mut self, expected_crc: u32) -> Result<(), Error<IF::Error>> {
+        // The upload mode is left even if reading the CRC fails
+        let crc = self.crc();
+        let ended = self.bhi.end_upload();
+        self.finished = true;
+        ended?;
+        let crc = crc?;
+        if crc != expected_crc {
+            #[cfg(feature = "log")]
+            log::error!(crc = crc, expected = expected_crc; "Firmware CRC mismatch");
+            return Err(Error::CrcMismatch {
+                expected: expected_crc,
+                actual: crc,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, IF> Drop for Uploader<'a, IF>
+where
+    IF: Interface,
+{
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.bhi.end_upload();
+        }
+    }
+}
+
+/// A summary of the header information of a [`Firmware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FirmwareInfo {
+    /// See [`Firmware::rom_version`].
+    pub rom_version: u16,
+    /// See [`Firmware::crc`].
+    pub crc: u32,
+    /// See [`Firmware::data_len`].
+    pub data_len: usize,
+    /// See [`Firmware::flags`].
+    pub flags: u32,
+}
+
+/// Firmware releases published by Bosch, identified by their RAM version
+/// (see [`RamVersion`](crate::registers::RamVersion)).
+///
+/// A release contains patches for different magnetometers (e.g. BMM150 or AKM) which share the same version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KnownFirmware {
+    /// `7183_di01.2.1.10836` for the BHI160.
+    Di01,
+    /// `7183_di03.2.1.11696` for the BHI160B.
+    Di03,
+}
+
+impl KnownFirmware {
+    pub fn from_ram_version(version: u16) -> Option<Self> {
+        match version {
+            10836 => Some(Self::Di01),
+            11696 => Some(Self::Di03),
+            _ => None,
+        }
+    }
+
+    pub fn ram_version(&self) -> u16 {
+        match self {
+            Self::Di01 => 10836,
+            Self::Di03 => 11696,
+        }
+    }
+
+    /// The name of the release as used in the firmware file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Di01 => "7183_di01.2.1.10836",
+            Self::Di03 => "7183_di03.2.1.11696",
+        }
+    }
+
+    /// The chip variant the release is built for.
+    pub fn variant(&self) -> ChipVariant {
+        match self {
+            Self::Di01 => ChipVariant::Bhi160,
+            Self::Di03 => ChipVariant::Bhi160B,
+        }
+    }
+}
+
+/// The versions of the firmware running on the BHI, e.g. for diagnostics and support requests.
+///
+/// Obtained by [`Bhi160::firmware_identity`](crate::Bhi160::firmware_identity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareIdentity {
+    pub rom_version: RomVersion,
+    pub ram_version: RamVersion,
+}
+
+impl FirmwareIdentity {
+    /// The known release of the loaded RAM patch, if any.
+    pub fn known_firmware(&self) -> Option<KnownFirmware> {
+        self.ram_version.known_firmware()
+    }
+}
+
+impl fmt::Display for FirmwareIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ROM {:#06x}, ", self.rom_version.0)?;
+        match (self.ram_version.is_loaded(), self.known_firmware()) {
+            (false, _) => write!(f, "no RAM patch"),
+            (true, Some(known)) => write!(f, "RAM {} ({})", self.ram_version.0, known.name()),
+            (true, None) => write!(f, "RAM {} (unknown)", self.ram_version.0),
+        }
+    }
 }
@@ -0,0 +1,85 @@
+//! Helpers to test the FIFO decoding against recorded FIFO dumps.
+//!
+//! A dump is a text file containing the raw FIFO bytes as whitespace separated hex values.
+//! Everything following a `#` up to the end of the line is ignored, so dumps can be annotated:
+//! ```text
+//! # Accelerometer
+//! 01 FE FF 05 00 69 08 02
+//! ```
+//! Record dumps from your own hardware (e.g. by printing the buffer returned by
+//! [`Bhi160::read_fifo`](crate::Bhi160::read_fifo)) and use [`assert_dump`] to guard against regressions.
+//! The dumps used by the tests of this crate live in `tests/fixtures`.
+
+use std::{fmt, io::Cursor, path::Path};
+
+use crate::fifo::{Event, EventReader};
+
+/// A dump could not be loaded.
+#[derive(Debug)]
+pub enum FixtureError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The token in the given line is not a hex encoded byte.
+    InvalidByte { line: usize, token: String },
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read dump: {err}"),
+            Self::InvalidByte { line, token } => {
+                write!(f, "invalid byte {token:?} in line {line}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixtureError {}
+
+impl From<std::io::Error> for FixtureError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Parses the textual representation of a dump into raw FIFO bytes.
+pub fn parse_dump(text: &str) -> Result<Vec<u8>, FixtureError> {
+    let mut bytes = Vec::new();
+    for (line_nr, line) in text.lines().enumerate() {
+        let data = line.split('#').next().unwrap_or_default();
+        for token in data.split_whitespace() {
+            let token = token.trim_start_matches("0x");
+            let byte = u8::from_str_radix(token, 16).map_err(|_| FixtureError::InvalidByte {
+                line: line_nr + 1,
+                token: token.into(),
+            })?;
+            bytes.push(byte);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Loads a dump from a file.
+pub fn load_dump(path: impl AsRef<Path>) -> Result<Vec<u8>, FixtureError> {
+    parse_dump(&std::fs::read_to_string(path)?)
+}
+
+/// Decodes all events of a dump.
+pub fn decode_dump(bytes: &[u8]) -> Vec<Event> {
+    EventReader::new(Cursor::new(bytes)).collect()
+}
+
+/// Loads a dump from a file and asserts that it decodes to the expected events.
+///
+/// # Panics
+/// Panics if the dump cannot be loaded or the decoded events do not match.
+pub fn assert_dump(path: impl AsRef<Path>, expected: &[Event]) {
+    let path = path.as_ref();
+    let bytes = load_dump(path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+    assert_eq!(
+        decode_dump(&bytes),
+        expected,
+        "decoded events of {} do not match",
+        path.display()
+    );
+}
@@ -0,0 +1,28 @@
+//! Floating-point functions which are not available in `core`.
+//!
+//! The backend is selected by features:
+//! * `libm`: precise implementations from the [`libm`](https://crates.io/crates/libm) crate.
+//! * `micromath`: fast approximations with small code size from the [`micromath`](https://crates.io/crates/micromath) crate.
+//! * Neither: the implementations of the standard library with `std`, otherwise `libm`.
+//!
+//! If both features are enabled `libm` is used.
+
+macro_rules! float_fn {
+    ($name:ident, $libm:ident, $($arg:ident),+) => {
+        #[inline]
+        pub(crate) fn $name($($arg: f32),+) -> f32 {
+            #[cfg(any(feature = "libm", not(any(feature = "micromath", feature = "std"))))]
+            return libm::$libm($($arg),+);
+            #[cfg(all(feature = "micromath", not(feature = "libm")))]
+            return micromath::F32Ext::$name($($arg),+);
+            #[cfg(all(feature = "std", not(any(feature = "libm", feature = "micromath"))))]
+            return f32::$name($($arg),+);
+        }
+    };
+}
+
+float_fn!(sqrt, sqrtf, x);
+float_fn!(sin, sinf, x);
+float_fn!(asin, asinf, x);
+float_fn!(acos, acosf, x);
+float_fn!(atan2, atan2f, y, x);
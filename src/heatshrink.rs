@@ -0,0 +1,196 @@
+//! The [heatshrink](https://github.com/atomicobject/heatshrink) compression of firmware bodies (with the `heatshrink`
+//! feature).
+//!
+//! Embedding a RAM patch costs about 16 KB of flash, which is a lot on small MCUs. With this feature the body of the
+//! patch can be stored compressed, see [`CompressedFirmware`](crate::firmware::CompressedFirmware), and is
+//! decompressed on the fly during the upload. The decompression needs a window of [`WINDOW_SIZE`] bytes on the stack.
+//!
+//! The parameters of the compression are fixed to a window of [`WINDOW_BITS`] and a lookahead of [`LOOKAHEAD_BITS`]
+//! (the defaults of heatshrink). The body can be compressed at build time, e.g. in a build script with [`compress`]
+//! (with the `alloc` feature) or with the heatshrink command line tool:
+//!
+//! ```text
+//! head -c 16 Bosch_PCB_7183_di03_BMI160-7183_di03.2.1.11696_170103.fw > firmware.hs
+//! tail -c +17 Bosch_PCB_7183_di03_BMI160-7183_di03.2.1.11696_170103.fw | heatshrink -e -w 8 -l 4 >> firmware.hs
+//! ```
+
+/// The size of the window in bits (`-w` of heatshrink).
+pub const WINDOW_BITS: u32 = 8;
+/// The size of the lookahead in bits (`-l` of heatshrink).
+pub const LOOKAHEAD_BITS: u32 = 4;
+/// The number of bytes a back-reference may reach back.
+pub const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+/// The longest back-reference.
+const MAX_MATCH: usize = 1 << LOOKAHEAD_BITS;
+
+/// Reads the bits of a byte slice, most significant bit first.
+#[derive(Debug, Clone)]
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// The position in bits.
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, count: u32) -> Option<u16> {
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.data.get(self.position / 8)?;
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u16;
+            self.position += 1;
+        }
+        Some(value)
+    }
+}
+
+/// Decompresses a heatshrink stream byte by byte.
+///
+/// The iteration ends at the end of the input or at a back-reference before the start of the output,
+/// i.e. at corrupt data. The caller has to check that the expected number of bytes was produced.
+#[derive(Debug, Clone)]
+pub(crate) struct Decoder<'a> {
+    input: BitReader<'a>,
+    window: [u8; WINDOW_SIZE],
+    /// The number of bytes produced so far.
+    produced: usize,
+    /// The distance and the remaining length of the current back-reference.
+    backref: (usize, usize),
+}
+
+impl<'a> Decoder<'a> {
+    pub(crate) fn new(input: &'a [u8]) -> Self {
+        Self {
+            input: BitReader {
+                data: input,
+                position: 0,
+            },
+            window: [0; WINDOW_SIZE],
+            produced: 0,
+            backref: (0, 0),
+        }
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = if self.backref.1 > 0 {
+            self.backref.1 -= 1;
+            self.window[(self.produced - self.backref.0) % WINDOW_SIZE]
+        } else if self.input.bits(1)? == 1 {
+            self.input.bits(8)? as u8
+        } else {
+            let distance = self.input.bits(WINDOW_BITS)? as usize + 1;
+            let len = self.input.bits(LOOKAHEAD_BITS)? as usize + 1;
+            if distance > self.produced {
+                return None;
+            }
+            self.backref = (distance, len - 1);
+            self.window[(self.produced - distance) % WINDOW_SIZE]
+        };
+        self.window[self.produced % WINDOW_SIZE] = byte;
+        self.produced += 1;
+        Some(byte)
+    }
+}
+
+/// Compresses `data` with the parameters expected by [`CompressedFirmware`](crate::firmware::CompressedFirmware).
+///
+/// The output is compatible with `heatshrink -e -w 8 -l 4`, but not necessarily identical.
+#[cfg(feature = "alloc")]
+pub fn compress(data: &[u8]) -> alloc::vec::Vec<u8> {
+    let mut output = BitWriter::default();
+    let mut position = 0;
+    while position < data.len() {
+        let window_start = position.saturating_sub(WINDOW_SIZE);
+        let lookahead = &data[position..data.len().min(position + MAX_MATCH)];
+        let (distance, len) = (window_start..position)
+            .map(|start| {
+                let len = data[start..]
+                    .iter()
+                    .zip(lookahead)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                (position - start, len)
+            })
+            .max_by_key(|&(distance, len)| (len, usize::MAX - distance))
+            .unwrap_or((0, 0));
+        // A back-reference takes 13 bits, a literal 9 bits
+        if len >= 2 {
+            output.push(0, 1);
+            output.push(distance as u16 - 1, WINDOW_BITS);
+            output.push(len as u16 - 1, LOOKAHEAD_BITS);
+            position += len;
+        } else {
+            output.push(1, 1);
+            output.push(data[position] as u16, 8);
+            position += 1;
+        }
+    }
+    output.bytes
+}
+
+/// Writes bits to a byte vector, most significant bit first.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default)]
+struct BitWriter {
+    bytes: alloc::vec::Vec<u8>,
+    /// The number of bits used in the last byte.
+    used: u32,
+}
+
+#[cfg(feature = "alloc")]
+impl BitWriter {
+    fn push(&mut self, value: u16, count: u32) {
+        for bit in (0..count).rev() {
+            if self.bytes.is_empty() || self.used == 8 {
+                self.bytes.push(0);
+                self.used = 0;
+            }
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= (((value >> bit) & 1) as u8) << (7 - self.used);
+            self.used += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_backref() {
+        // Literal 'a', then 3 bytes at distance 1, then literal 'b'
+        let stream = [0b1011_0000, 0b1000_0000, 0b0000_1010, 0b1100_0100];
+        let decoded: Vec<_> = Decoder::new(&stream).collect();
+        assert_eq!(decoded, b"aaaab");
+
+        // A back-reference before the start of the output
+        assert_eq!(Decoder::new(&[0b0000_0000, 0b0000_0000]).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn round_trip() {
+        let data: Vec<u8> = (0..2000u32)
+            .map(|i| {
+                if i % 300 < 100 {
+                    (i % 7) as u8
+                } else {
+                    (i * 31 % 251) as u8
+                }
+            })
+            .collect();
+        let compressed = compress(&data);
+        assert!(compressed.len() < data.len());
+        assert_eq!(
+            Decoder::new(&compressed)
+                .take(data.len())
+                .collect::<Vec<_>>(),
+            data
+        );
+        assert!(compress(&[]).is_empty());
+    }
+}
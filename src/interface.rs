@@ -2,17 +2,50 @@ pub trait Interface {
     type Error;
     fn read<'a>(&mut self, addr: u8, buf: &'a mut [u8]) -> Result<(), Self::Error>;
     fn write(&mut self, addr: u8, buf: &[u8]) -> Result<(), Self::Error>;
+
+    /// Writes `data` starting at `write_addr`, then reads `buf.len()` bytes starting at `read_addr`.
+    ///
+    /// The driver uses this in hot paths, e.g. to send a parameter request and read the first acknowledgment.
+    /// Buses which can combine both accesses more efficiently (e.g. SPI under manual chip select control)
+    /// may override it, the default performs a [`write`](Self::write) followed by a [`read`](Self::read).
+    /// [`I2c`] keeps the default, as embedded-hal merges adjacent writes of a transaction.
+    fn write_then_read(
+        &mut self,
+        write_addr: u8,
+        data: &[u8],
+        read_addr: u8,
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.write(write_addr, data)?;
+        self.read(read_addr, buf)
+    }
 }
 
 pub const I2C_ADDR1: u8 = 0x28;
 pub const I2C_ADDR2: u8 = 0x29;
 
+/// The data port of the firmware upload. Consecutive writes to it continue at the current upload address.
+const UPLOAD_DATA_ADDR: u8 = 0x96;
+
+/// Errors of the [`I2c`] interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum I2cError<E> {
+    /// The I2C bus returned an error.
+    Bus(E),
+    /// A register write does not fit into a single transaction of [`I2c::max_write_len`] bytes.
+    ///
+    /// Only writes to the firmware upload data port can be split.
+    WriteTooLong,
+}
+
 pub struct I2c<Inner>
 where
     Inner: embedded_hal::i2c::I2c,
 {
     inner: Inner,
     addr: u8,
+    max_write_len: usize,
 }
 
 impl<Inner> I2c<Inner>
@@ -20,7 +53,32 @@ where
     Inner: embedded_hal::i2c::I2c,
 {
     pub fn new(inner: Inner, addr: u8) -> Self {
-        Self { inner, addr }
+        Self {
+            inner,
+            addr,
+            max_write_len: usize::MAX,
+        }
+    }
+
+    /// Limits the length of a single write transaction (including the register address) to `len` bytes.
+    ///
+    /// Longer writes to the firmware upload data port are split into multiple transactions at 4 byte boundaries.
+    /// Other writes fail with [`I2cError::WriteTooLong`], as splitting them would not be atomic. The longest
+    /// register write of the driver is a parameter write with 8 data bytes, so `len` should be at least 9.
+    ///
+    /// # Panics
+    /// If `len` is less than 5, as at least one word has to fit next to the register address.
+    pub fn with_max_write_len(mut self, len: usize) -> Self {
+        assert!(
+            len >= 5,
+            "the maximum write length has to be at least 5 bytes"
+        );
+        self.max_write_len = len;
+        self
+    }
+
+    pub fn max_write_len(&self) -> usize {
+        self.max_write_len
     }
 
     pub fn addr(&self) -> u8 {
@@ -30,13 +88,24 @@ where
     pub fn into_inner(self) -> Inner {
         self.inner
     }
+
+    fn write_transaction(&mut self, addr: u8, buf: &[u8]) -> Result<(), I2cError<Inner::Error>> {
+        let addr = [addr];
+        let mut operations = [
+            embedded_hal::i2c::Operation::Write(&addr),
+            embedded_hal::i2c::Operation::Write(buf),
+        ];
+        self.inner
+            .transaction(self.addr, &mut operations)
+            .map_err(I2cError::Bus)
+    }
 }
 
 impl<Inner> Interface for I2c<Inner>
 where
     Inner: embedded_hal::i2c::I2c,
 {
-    type Error = Inner::Error;
+    type Error = I2cError<Inner::Error>;
 
     fn read<'a>(&mut self, addr: u8, buf: &'a mut [u8]) -> Result<(), Self::Error> {
         let addr = [addr];
@@ -44,15 +113,21 @@ where
             embedded_hal::i2c::Operation::Write(&addr),
             embedded_hal::i2c::Operation::Read(buf),
         ];
-        self.inner.transaction(self.addr, &mut operations)
+        self.inner
+            .transaction(self.addr, &mut operations)
+            .map_err(I2cError::Bus)
     }
 
-    fn write(&mut self, addr: u8, buf: &[u8]) -> Result<(), Self::Error> {
-        let addr = [addr];
-        let mut operations = [
-            embedded_hal::i2c::Operation::Write(&addr),
-            embedded_hal::i2c::Operation::Write(buf),
-        ];
-        self.inner.transaction(self.addr, &mut operations)
+    fn write(&mut self, addr: u8, mut buf: &[u8]) -> Result<(), Self::Error> {
+        let max = self.max_write_len - 1;
+        if buf.len() > max && addr != UPLOAD_DATA_ADDR {
+            return Err(I2cError::WriteTooLong);
+        }
+        while buf.len() > max {
+            let (chunk, rest) = buf.split_at(max & !3);
+            self.write_transaction(addr, chunk)?;
+            buf = rest;
+        }
+        self.write_transaction(addr, buf)
     }
 }
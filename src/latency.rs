@@ -0,0 +1,170 @@
+//! Measurement of the latency between the generation of a sample and its processing on the host.
+//!
+//! The timestamp of a sample (see [`timestamp`](crate::timestamp)) and the
+//! [Host IRQ timestamp](crate::Bhi160::irq_tick) are counted by the same clock of the BHI, so their difference
+//! is the time the sample waited in the FIFO until the host was interrupted. [`LatencyProbe`] adds the time the host
+//! needed from the interrupt until parsing the transfer, measured with any host clock, and collects the statistics.
+//!
+//! ```ignore
+//! let mut probe = LatencyProbe::new();
+//! // On every host interrupt, with `irq_time = clock.now()` taken by the interrupt handler
+//! let irq_tick = bhi.irq_tick()?;
+//! probe.begin_transfer_since(irq_tick, irq_time, &clock);
+//! bhi.drain_fifo(&mut buf, usize::MAX, |event| probe.observe(&event))?;
+//! // Later
+//! let stats = probe.stats();
+//! println!("{:?} ticks (mean {:?})", stats.min()..=stats.max(), stats.mean());
+//! ```
+
+use crate::{
+    clock::{Clock, Instant},
+    fifo::Event,
+    timestamp::{micros_to_ticks, ticks_to_micros, TimestampTracker},
+};
+
+/// The distribution of the measured latencies in ticks of the timestamp counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LatencyStats {
+    count: u32,
+    min: u32,
+    max: u32,
+    sum: u64,
+}
+
+impl LatencyStats {
+    /// Adds a latency to the statistics.
+    pub fn record(&mut self, ticks: u32) {
+        if self.count == 0 {
+            self.min = ticks;
+            self.max = ticks;
+        } else {
+            self.min = self.min.min(ticks);
+            self.max = self.max.max(ticks);
+        }
+        self.count = self.count.saturating_add(1);
+        self.sum += ticks as u64;
+    }
+
+    /// The number of samples measured.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The smallest latency, `None` if nothing was measured.
+    pub fn min(&self) -> Option<u32> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// The largest latency, `None` if nothing was measured.
+    pub fn max(&self) -> Option<u32> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// The mean latency rounded down, `None` if nothing was measured.
+    pub fn mean(&self) -> Option<u32> {
+        (self.count > 0).then(|| (self.sum / self.count as u64) as u32)
+    }
+
+    /// The mean latency in microseconds, `None` if nothing was measured.
+    pub fn mean_micros(&self) -> Option<u64> {
+        self.mean().map(ticks_to_micros)
+    }
+}
+
+/// Measures the latency of every sample passed to [`observe`](Self::observe), see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LatencyProbe {
+    timestamps: TimestampTracker,
+    /// The Host IRQ timestamp of the current transfer plus the delay of the host, in ticks.
+    parse_tick: Option<u32>,
+    stats: LatencyStats,
+}
+
+impl LatencyProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts measuring a new transfer.
+    ///
+    /// `irq_tick` is the [Host IRQ timestamp](crate::Bhi160::irq_tick) read before the transfer and
+    /// `host_delay_micros` the time elapsed on the host between the interrupt and parsing the transfer.
+    pub fn begin_transfer(&mut self, irq_tick: u32, host_delay_micros: u64) {
+        self.parse_tick = Some(irq_tick.wrapping_add(micros_to_ticks(host_delay_micros)));
+    }
+
+    /// Like [`begin_transfer`](Self::begin_transfer), but measures the delay of the host with `clock`,
+    /// from `irq_time` (the time the interrupt was handled) until now.
+    pub fn begin_transfer_since(&mut self, irq_tick: u32, irq_time: Instant, clock: &impl Clock) {
+        self.begin_transfer(irq_tick, clock.elapsed(irq_time).as_micros() as u64);
+    }
+
+    /// Processes an event of the current transfer. All events have to be passed in FIFO order.
+    ///
+    /// Only the samples of virtual sensors are measured. Events before the first call to
+    /// [`begin_transfer`](Self::begin_transfer) only update the timestamps.
+    pub fn observe(&mut self, event: &Event) {
+        let generated = self.timestamps.update(event);
+        if let (Some(parsed), Some(_)) = (self.parse_tick, event.id().kind()) {
+            // Samples generated while the transfer was read are newer than the interrupt
+            let latency = parsed.wrapping_sub(generated) as i32;
+            self.stats.record(latency.max(0) as u32);
+        }
+    }
+
+    /// The statistics of all samples measured so far.
+    pub fn stats(&self) -> LatencyStats {
+        self.stats
+    }
+
+    /// Clears the statistics, the timestamps are kept.
+    pub fn reset_stats(&mut self) {
+        self.stats = LatencyStats::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        fifo::{SensorData, SensorStatus},
+        math::Vector,
+        parameters::sensors::SensorId,
+    };
+
+    fn event(id: SensorId, data: SensorData) -> Event {
+        Event::new(id, data)
+    }
+
+    #[test]
+    fn latency_stats() {
+        let accel = event(
+            SensorId::Accelerometer,
+            SensorData::VectorStatus(Vector([0; 3]), SensorStatus::High),
+        );
+        let mut probe = LatencyProbe::new();
+        probe.observe(&accel);
+        assert_eq!(probe.stats().count(), 0);
+        assert_eq!(probe.stats().mean(), None);
+
+        // Interrupt at tick 1000, parsed 1 ms (32 ticks) later
+        probe.begin_transfer(1000, 1000);
+        probe.observe(&event(SensorId::TimestampLsw, SensorData::Scalar(900)));
+        probe.observe(&accel);
+        probe.observe(&event(SensorId::TimestampLsw, SensorData::Scalar(1010)));
+        probe.observe(&accel);
+        // Generated after the transfer was parsed, e.g. due to a skewed host clock
+        probe.observe(&event(SensorId::TimestampLsw, SensorData::Scalar(1040)));
+        probe.observe(&accel);
+
+        let stats = probe.stats();
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.min(), Some(0));
+        assert_eq!(stats.max(), Some(132));
+        assert_eq!(stats.mean(), Some(51));
+        assert_eq!(stats.mean_micros(), Some(1593));
+
+        probe.reset_stats();
+        assert_eq!(probe.stats(), LatencyStats::default());
+    }
+}
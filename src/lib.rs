@@ -5,19 +5,288 @@
 //! ## More Information
 //! * [Sensor website](https://www.bosch-sensortec.com/products/smart-sensors/bhi160b/)
 //! * [Datasheet](https://www.bosch-sensortec.com/media/boschsensortec/downloads/datasheets/bst-bhi160b-ds000.pdf)
+//!
+//! ## Stable toolchains
+//! The typed register and parameter accessors (e.g. [`Bhi160::read_reg`]) use `generic_const_exprs` and require
+//! a nightly toolchain with the `nightly` feature (`features = ["nightly"]`). Without it, the crate builds on stable:
+//! the typed accessors take the size as an additional generic parameter, which is inferred from the conversion
+//! of the register or parameter (`let status: ChipStatus = bhi.read_reg()?`). The byte based accessors
+//! [`Bhi160::read_reg_raw`], [`Bhi160::write_reg_raw`], [`Bhi160::read_param_raw`] and [`Bhi160::write_param_raw`]
+//! as well as the FIFO and the [`packet`] decoding are the same for both.
+//!
+//! ## Features
+//! Without features the crate is `no_std` and does not allocate. All features are additive:
+//! * `nightly`: the typed register and parameter API based on `generic_const_exprs`, see above.
+//! * `alloc`: the APIs returning collections, e.g. `Bhi160::read_events_vec`, `Bhi160::self_test` and `events`.
+//! * `std` (implies `alloc`): the APIs based on `std::io` and `std::time` (`packet::EventReader`, `fixture`,
+//!   `replay`, `clock::StdClock`) and `sync`.
+//! * `log` and `defmt`: logging with the respective crate, `defmt` also derives `defmt::Format` for the errors
+//!   and the events.
+//! * `async`: `futures_core::Stream` for the event sources.
+//! * `serde`, `uom`, `embassy-time`: integrations with these crates.
+//! * `heatshrink`: storing the firmware compressed, see `heatshrink`.
+//! * `metrics`: counters of the bus usage, see `metrics`.
+//! * `libm`, `micromath`: the backend of the floating-point functions. Without `std`, `libm` is used by default.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(incomplete_features)]
-#![feature(array_chunks, array_zip, generic_const_exprs)]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use consts::{PARAMETER_READ_SIZE, PARAMETER_WRITE_SIZE};
 use interface::Interface;
-use parameters::Parameter;
-use registers::Register;
+use parameters::{sensors::SensorId, Parameter};
+use registers::{ReadableRegister, Register, WritableRegister};
 
+pub mod acceleration;
+pub mod bhi160b;
+pub mod budget;
+pub mod calibration;
+pub mod capabilities;
+pub mod clock;
+pub mod consts;
+pub mod diagnostics;
+#[cfg(feature = "alloc")]
+pub mod events;
+pub mod fifo;
+pub mod filter;
 pub mod firmware;
+#[cfg(feature = "std")]
+pub mod fixture;
+mod float;
+#[cfg(feature = "heatshrink")]
+pub mod heatshrink;
 pub mod interface;
+pub mod latency;
+pub mod math;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod packet;
 pub mod parameters;
+pub mod prelude;
+pub mod profiles;
 pub mod registers;
+#[cfg(feature = "std")]
+pub mod replay;
+pub mod ring;
+pub mod scaled;
+#[cfg(feature = "std")]
+pub mod sync;
+pub mod timestamp;
+#[cfg(feature = "uom")]
+pub mod units;
+
+/// Errors that can occur when interacting with the BHI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// The underlying [`Interface`] returned an error.
+    Interface(E),
+    /// The data read from the FIFO is inconsistent.
+    CorruptFifo(packet::CorruptFifo),
+    /// The provided buffer is too small for the requested operation.
+    BufferTooSmall,
+    /// The requested sensor or operation is not supported.
+    Unsupported,
+    /// The BHI is not in the state required for the requested operation or did not reach the requested state.
+    ///
+    /// Also returned by the parameter accesses if the BHI does not acknowledge a request within the
+    /// poll limit set by [`Bhi160::set_ack_poll_limit`].
+    InvalidState,
+    /// The BHI rejected the access to a parameter, even after [`PARAMETER_RETRIES`] retries.
+    ///
+    /// Usually the parameter page or number is not supported by the running firmware.
+    ParameterRejected(parameters::ParameterPage, u8),
+    /// The CRC calculated by the BHI over an uploaded RAM patch does not match the expected CRC.
+    CrcMismatch { expected: u32, actual: u32 },
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(err: E) -> Self {
+        Self::Interface(err)
+    }
+}
+
+/// Limits the work done by a single call to [`Bhi160::drain_fifo_throttled`].
+///
+/// Useful for host loops which share the bus with other tasks and must not be starved by a burst of FIFO data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Throttle {
+    max_events: usize,
+    max_bytes: usize,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl Throttle {
+    /// Does not limit the number of events or bytes.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_events: usize::MAX,
+            max_bytes: usize::MAX,
+        }
+    }
+
+    /// Reads exactly one transfer (if the FIFO is not empty) per call.
+    ///
+    /// The limits are only checked before starting a transfer, so any byte limit stops after the first one.
+    pub const fn single_transfer() -> Self {
+        Self::unlimited().with_max_bytes(1)
+    }
+
+    /// Limits the number of events processed per call.
+    pub const fn with_max_events(mut self, max_events: usize) -> Self {
+        self.max_events = max_events;
+        self
+    }
+
+    /// Limits the number of bytes read per call.
+    pub const fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    fn exhausted(&self, progress: &DrainProgress) -> bool {
+        progress.events >= self.max_events || progress.bytes >= self.max_bytes
+    }
+}
+
+/// Selects the [`Throttle`] of [`Bhi160::drain_on_interrupt`] depending on the cause of the interrupt.
+///
+/// The default drains the FIFO completely when a latency expired or an immediate event occurred,
+/// but reads only a single transfer when a watermark was reached. Power-sensitive designs can thereby
+/// handle watermark interrupts in small steps. The remaining data is read by the next call.
+/// Zero bytes in the FIFO data are handled as selected by [`Bhi160::set_fifo_padding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DrainPolicy {
+    pub latency: Throttle,
+    pub immediate: Throttle,
+    pub watermark: Throttle,
+    /// Used if [`IntStatus`](registers::IntStatus) has no cause set, e.g. for a spurious or polled interrupt.
+    pub other: Throttle,
+}
+
+impl Default for DrainPolicy {
+    fn default() -> Self {
+        Self {
+            latency: Throttle::unlimited(),
+            immediate: Throttle::unlimited(),
+            watermark: Throttle::single_transfer(),
+            other: Throttle::unlimited(),
+        }
+    }
+}
+
+impl DrainPolicy {
+    /// Always drains the FIFO completely.
+    pub fn full() -> Self {
+        Self {
+            watermark: Throttle::unlimited(),
+            ..Self::default()
+        }
+    }
+
+    /// Reads a single transfer per interrupt, whatever the cause.
+    pub fn single_transfer() -> Self {
+        Self {
+            latency: Throttle::single_transfer(),
+            immediate: Throttle::single_transfer(),
+            watermark: Throttle::single_transfer(),
+            other: Throttle::single_transfer(),
+        }
+    }
+
+    /// The throttle to use for an interrupt with the given cause.
+    pub fn throttle(&self, cause: Option<registers::InterruptCause>) -> Throttle {
+        match cause {
+            Some(registers::InterruptCause::Latency) => self.latency,
+            Some(registers::InterruptCause::Immediate) => self.immediate,
+            Some(registers::InterruptCause::Watermark) => self.watermark,
+            None => self.other,
+        }
+    }
+}
+
+/// The work done by a call to [`Bhi160::drain_fifo_throttled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct DrainProgress {
+    /// The number of events passed to the callback.
+    pub events: usize,
+    /// The number of bytes read from the FIFO.
+    pub bytes: usize,
+    /// `true` if the FIFO was empty at the end, `false` if the call stopped because of the [`Throttle`].
+    pub complete: bool,
+    /// The number of zero bytes skipped with [`Padding::Lenient`](packet::Padding::Lenient).
+    pub skipped: usize,
+}
+
+/// Statistics of a single read by [`Bhi160::read_fifo_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FifoReadReport {
+    /// The number of bytes read into the buffer.
+    pub bytes_read: usize,
+    /// The number of bytes of the current transfer which did not fit into the buffer.
+    pub bytes_remaining_after: usize,
+    /// `true` if the buffer was too small to hold the whole transfer.
+    /// The rest of the transfer has to be read or the transfer has to be aborted
+    /// (see [`HostInterfaceControl::abort_transfer`](registers::HostInterfaceControl::abort_transfer)).
+    pub truncated: bool,
+}
+
+/// The beginning of the FIFO read by [`Bhi160::peek_fifo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FifoPeek<'a> {
+    /// The first bytes of the transfer.
+    pub data: &'a [u8],
+    /// The size of the whole transfer as announced by [`BytesRemaining`](registers::BytesRemaining).
+    pub transfer_size: usize,
+}
+
+impl<'a> FifoPeek<'a> {
+    /// The complete events in [`data`](Self::data).
+    pub fn events(&self) -> packet::EventRefs<'a> {
+        packet::EventRefs::new(self.data)
+    }
+}
+
+/// The state of a parameter read by [`Bhi160::poll_param`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamPoll<T> {
+    requested: bool,
+    _param: core::marker::PhantomData<T>,
+}
+
+impl<T> Default for ParamPoll<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> ParamPoll<T> {
+    pub const fn new() -> Self {
+        Self {
+            requested: false,
+            _param: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns `true` if the read was requested but the value was not returned yet.
+    pub fn is_pending(&self) -> bool {
+        self.requested
+    }
+}
+
+/// How often a status register is polled while waiting for the BHI to reach a requested state.
+const STATE_POLL_ATTEMPTS: usize = 100;
+
+/// How often a parameter request is repeated after the BHI acknowledged it with an error.
+pub const PARAMETER_RETRIES: usize = 2;
 
 /// The main interface to interact with a BHI160(B).
 pub struct Bhi160<IF>
@@ -25,6 +294,30 @@ where
     IF: Interface,
 {
     interface: IF,
+    /// The last value written to [`ParameterPageSelect`](registers::ParameterPageSelect), if known.
+    page_select: Option<[u8; 1]>,
+    /// The capabilities of the running firmware, if already read.
+    capabilities: Option<capabilities::Capabilities>,
+    /// The coordinate system requested by [`Bhi160::set_coordinate_system`], re-applied after a reset.
+    coordinate_system: Option<registers::CoordinateSystem>,
+    /// The Significant Motion sensor armed by [`Bhi160::arm_significant_motion`] until it fires.
+    significant_motion: Option<OneShot>,
+    /// The last [`Error`](packet::MetaEvent::Error) meta events read from the FIFO.
+    recent_errors: diagnostics::RecentErrors,
+    /// How zero bytes in the FIFO data are handled, see [`Bhi160::set_fifo_padding`].
+    fifo_padding: packet::Padding,
+    /// How often the [`ParameterAcknowledge`](registers::ParameterAcknowledge) is polled for a parameter request.
+    ack_poll_limit: usize,
+    #[cfg(feature = "metrics")]
+    metrics: metrics::BusMetrics,
+}
+
+/// A one-shot sensor which disables itself after its event was reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct OneShot {
+    id: SensorId,
+    /// Arm the sensor again as soon as it fired.
+    rearm: bool,
 }
 
 impl<IF> Bhi160<IF>
@@ -32,7 +325,30 @@ where
     IF: Interface,
 {
     pub fn new(interface: IF) -> Self {
-        Self { interface }
+        Self {
+            interface,
+            page_select: None,
+            capabilities: None,
+            coordinate_system: None,
+            significant_motion: None,
+            recent_errors: diagnostics::RecentErrors::default(),
+            fifo_padding: packet::Padding::default(),
+            ack_poll_limit: STATE_POLL_ATTEMPTS,
+            #[cfg(feature = "metrics")]
+            metrics: metrics::BusMetrics::default(),
+        }
+    }
+
+    /// Returns a reference to the underlying interface.
+    pub fn interface(&self) -> &IF {
+        &self.interface
+    }
+
+    /// Returns a mutable reference to the underlying interface.
+    ///
+    /// Changing the parameter page behind the driver's back requires a call to [`Self::invalidate_page_cache`].
+    pub fn interface_mut(&mut self) -> &mut IF {
+        &mut self.interface
     }
 
     /// Reads a register from the BHI.
@@ -40,113 +356,627 @@ where
     /// See [`registers`] for more information.
     /// For reading parameters you may want to use [`read_param`].
     /// For reading from the data fifo see [`read_fifo`].
+    #[cfg(feature = "nightly")]
+    #[inline]
     pub fn read_reg<T>(&mut self) -> Result<T, IF::Error>
     where
-        T: Register + From<[u8; T::SIZE]>,
+        T: ReadableRegister + From<[u8; T::SIZE]>,
     {
         let mut buf = [0; T::SIZE];
-        self.interface.read(T::ADDR, &mut buf)?;
+        self.read_reg_raw(T::ADDR, &mut buf)?;
+        Ok(buf.into())
+    }
+
+    /// Reads a register from the BHI, the size `N` is inferred from the conversion of `T`.
+    #[cfg(not(feature = "nightly"))]
+    #[inline]
+    pub fn read_reg<T, const N: usize>(&mut self) -> Result<T, IF::Error>
+    where
+        T: ReadableRegister + From<[u8; N]>,
+    {
+        let mut buf = [0; N];
+        self.read_reg_raw(T::ADDR, &mut buf)?;
         Ok(buf.into())
     }
 
+    /// Reads `buf.len()` bytes starting at register `addr`.
+    pub fn read_reg_raw(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), IF::Error> {
+        self.bus_read(addr, buf)
+    }
+
+    /// Reads from the interface. All accesses of the driver go through this and [`bus_write`](Self::bus_write).
+    #[inline]
+    fn bus_read(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), IF::Error> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_read(addr, buf.len());
+        self.interface.read(addr, buf)
+    }
+
+    /// Writes to the interface, see [`bus_read`](Self::bus_read).
+    #[inline]
+    fn bus_write(&mut self, addr: u8, data: &[u8]) -> Result<(), IF::Error> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_write(addr, data.len());
+        self.interface.write(addr, data)
+    }
+
+    /// Writes and reads in one combined transaction if the interface supports it,
+    /// see [`Interface::write_then_read`].
+    #[inline]
+    fn bus_write_then_read(
+        &mut self,
+        write_addr: u8,
+        data: &[u8],
+        read_addr: u8,
+        buf: &mut [u8],
+    ) -> Result<(), IF::Error> {
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.record_write(write_addr, data.len());
+            self.metrics.record_read(read_addr, buf.len());
+        }
+        self.interface
+            .write_then_read(write_addr, data, read_addr, buf)
+    }
+
+    /// The bus usage since the driver was created or the statistics were reset, see [`metrics`].
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> metrics::BusMetrics {
+        self.metrics
+    }
+
+    /// Resets the bus statistics.
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.metrics = metrics::BusMetrics::default();
+    }
+
     /// Write a register to the BHI.
     /// 
     /// See [`registers`] for more information.
     /// If you want to write parameters you may want to use [`write_param`].
-    /// For uploading a firmware blob see [`upload_raw_firmware`].
+    /// For uploading a firmware blob see [`begin_upload`](Self::begin_upload).
+    #[cfg(feature = "nightly")]
+    #[inline]
     pub fn write_reg<T>(&mut self, reg: T) -> Result<(), IF::Error>
     where
-        T: Register + Into<[u8; T::SIZE]>,
+        T: WritableRegister + Into<[u8; T::SIZE]>,
     {
-        let data = &reg.into();
+        self.write_reg_raw(T::ADDR, &reg.into())
+    }
+
+    /// Writes a register to the BHI, the size `N` is inferred from the conversion of `T`.
+    #[cfg(not(feature = "nightly"))]
+    #[inline]
+    pub fn write_reg<T, const N: usize>(&mut self, reg: T) -> Result<(), IF::Error>
+    where
+        T: WritableRegister + Into<[u8; N]>,
+    {
+        self.write_reg_raw(T::ADDR, &reg.into())
+    }
+
+    /// Writes `data` starting at register `addr`.
+    ///
+    /// This is the non-generic part of [`write_reg`](Self::write_reg), so the driver's caches
+    /// (e.g. of the parameter page) are kept up to date.
+    pub fn write_reg_raw(&mut self, addr: u8, data: &[u8]) -> Result<(), IF::Error> {
+        self.track_write(addr, data);
+        self.bus_write(addr, data)
+    }
+
+    /// Logs and checks a register write and updates the caches affected by it.
+    fn track_write(&mut self, addr: u8, data: &[u8]) {
         #[cfg(feature = "log")]
         log::info!(
-            "Writing to {}({:02x}h): {:?}",
-            core::any::type_name::<T>(),
-            T::ADDR,
+            reg_addr = addr,
+            reg_name = registers::describe(addr).map_or("unknown", |desc| desc.name),
+            len = data.len();
+            "Writing register: {:02x?}",
             data
         );
-        self.interface.write(T::ADDR, data)
+        // Catch custom `Register` impls which target the wrong address before they reach the bus
+        if let Some(desc) = registers::describe(addr) {
+            debug_assert!(
+                desc.access != registers::Access::ReadOnly,
+                "write to the read-only register {}",
+                desc.name
+            );
+            debug_assert!(
+                addr as usize + data.len() <= desc.addr as usize + desc.size,
+                "write of {} bytes exceeds the register {}",
+                data.len(),
+                desc.name
+            );
+        }
+        if addr == registers::ParameterPageSelect::ADDR || addr == registers::ResetRequest::ADDR {
+            self.page_select = None;
+        }
+        // A reset or (re)starting the CPU may change the running firmware
+        if addr == registers::ResetRequest::ADDR || addr == registers::ChipControl::ADDR {
+            self.capabilities = None;
+        }
+    }
+
+    /// Forget the cached state of the [`ParameterPageSelect`](registers::ParameterPageSelect) register.
+    ///
+    /// The driver skips writing the page select register if it did not change since the last parameter access.
+    /// Call this if the BHI was reset by other means than [`ResetRequest`](registers::ResetRequest)
+    /// (e.g. a power cycle) or the register was modified behind the driver's back.
+    pub fn invalidate_page_cache(&mut self) {
+        self.page_select = None;
+    }
+
+    /// Selects the parameter page and size unless they are already selected.
+    fn select_page(&mut self, page: parameters::ParameterPage, size: u8) -> Result<(), IF::Error> {
+        let select: [u8; 1] = registers::ParameterPageSelect::new()
+            .with_parameter_page(page)
+            .with_parameter_size(size)
+            .into();
+        if self.page_select != Some(select) {
+            #[cfg(feature = "log")]
+            log::info!(param_page:? = page, len = size; "Selecting parameter page");
+            self.bus_write(registers::ParameterPageSelect::ADDR, &select)?;
+            self.page_select = Some(select);
+        }
+        Ok(())
     }
 
     /// Convinience method that allows read-modify-write operations on registers.
+    #[cfg(feature = "nightly")]
     pub fn update_reg<T>(&mut self, f: impl FnOnce(T) -> T) -> Result<(), IF::Error>
     where
-        T: Register + From<[u8; T::SIZE]> + Into<[u8; T::SIZE]>,
+        T: ReadableRegister + WritableRegister + From<[u8; T::SIZE]> + Into<[u8; T::SIZE]>,
     {
         let reg = self.read_reg()?;
         let reg = f(reg);
         self.write_reg(reg)
     }
 
+    /// Convinience method that allows read-modify-write operations on registers.
+    #[cfg(not(feature = "nightly"))]
+    pub fn update_reg<T, const N: usize>(&mut self, f: impl FnOnce(T) -> T) -> Result<(), IF::Error>
+    where
+        T: ReadableRegister + WritableRegister + From<[u8; N]> + Into<[u8; N]>,
+    {
+        let reg = self.read_reg()?;
+        let reg = f(reg);
+        self.write_reg(reg)
+    }
+
+    /// Reads the [`RevisionId`](registers::RevisionId) to determine the variant of the chip.
+    ///
+    /// Returns `None` if the revision is unknown.
+    pub fn chip_variant(&mut self) -> Result<Option<registers::ChipVariant>, IF::Error> {
+        Ok(registers::RevisionId::variant(&self.read_reg()?))
+    }
+
+    /// Gives access to the features only available on the BHI160B.
+    ///
+    /// Returns `None` if the chip is not a BHI160B.
+    pub fn bhi160b(&mut self) -> Result<Option<bhi160b::Bhi160B<'_, IF>>, IF::Error> {
+        match self.chip_variant()? {
+            Some(registers::ChipVariant::Bhi160B) => Ok(Some(bhi160b::Bhi160B::new(self))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gives access to the parameters of the [Algorithm page](parameters::ParameterPage::Algorithm).
+    ///
+    /// The returned guard acknowledges the accesses by selecting [`Page0`](parameters::ParameterPage::Page0)
+    /// when it is committed or dropped.
+    pub fn algorithm_page(&mut self) -> parameters::algorithm::AlgorithmPageGuard<'_, IF> {
+        parameters::algorithm::AlgorithmPageGuard::new(self)
+    }
+
+    /// Returns the capabilities of the running firmware.
+    ///
+    /// The capabilities are read on the first call and cached afterwards.
+    /// The cache is cleared on a reset and whenever the [`ChipControl`](registers::ChipControl) register is written
+    /// (e.g. when the CPU is started after a firmware upload).
+    pub fn capabilities(&mut self) -> Result<&capabilities::Capabilities, Error<IF::Error>> {
+        if self.capabilities.is_none() {
+            self.capabilities = Some(capabilities::Capabilities::read(self)?);
+        }
+        Ok(self.capabilities.as_ref().unwrap())
+    }
+
+    /// Reads the capabilities again, e.g. after the firmware was changed behind the driver's back.
+    pub fn refresh_capabilities(
+        &mut self,
+    ) -> Result<&capabilities::Capabilities, Error<IF::Error>> {
+        self.capabilities = None;
+        self.capabilities()
+    }
+
+    /// Creates a [`Scaler`](scaled::Scaler) for the dynamic ranges currently used by the physical sensors.
+    ///
+    /// The ranges are read from the [`PhysicalSensorStatus`](parameters::system::PhysicalSensorStatus).
+    /// Call this again (or [`Scaler::set_dynamic_ranges`](scaled::Scaler::set_dynamic_ranges)) after a
+    /// [`DynamicRangeChanged`](packet::MetaEvent::DynamicRangeChanged) meta event.
+    pub fn scaler(&mut self) -> Result<scaled::Scaler, Error<IF::Error>> {
+        let status: parameters::system::PhysicalSensorStatus = self.read_param()?;
+        Ok(scaled::Scaler::new().with_dynamic_ranges(&status))
+    }
+
+    /// The most recent [`Error`](packet::MetaEvent::Error) meta events read from the FIFO.
+    pub fn recent_errors(&self) -> &diagnostics::RecentErrors {
+        &self.recent_errors
+    }
+
+    /// Writes a table of all sensors provided by the firmware to `out`, see [`capabilities::print_all_sensor_info`].
+    pub fn print_all_sensor_info(
+        &mut self,
+        out: &mut impl core::fmt::Write,
+    ) -> Result<(), capabilities::PrintError<IF::Error>> {
+        capabilities::print_all_sensor_info(self, out)
+    }
+
+    /// Captures a [`SupportBundle`](diagnostics::SupportBundle) for remote debugging.
+    ///
+    /// The capabilities are read if not cached yet, the diagnostic registers are always read.
+    pub fn support_bundle(&mut self) -> Result<diagnostics::SupportBundle, Error<IF::Error>> {
+        Ok(diagnostics::SupportBundle {
+            format_version: diagnostics::SupportBundle::FORMAT_VERSION,
+            capabilities: *self.capabilities()?,
+            registers: diagnostics::DiagnosticRegisters::capture(self)?,
+            recent_errors: self.recent_errors,
+        })
+    }
+
+    /// Reads the timestamp (in ticks of the BHI's timestamp counter) at which the host interrupt was last asserted.
+    ///
+    /// See [`HostIrqTimestamp`](registers::HostIrqTimestamp).
+    pub fn irq_tick(&mut self) -> Result<u32, IF::Error> {
+        let registers::HostIrqTimestamp(tick) = self.read_reg()?;
+        Ok(tick)
+    }
+
+    /// Disables the host interrupt for both the wakeup and non-wakeup FIFO while `f` is executed.
+    ///
+    /// Setting the interrupt disable bits de-asserts the host interrupt and prevents it from being
+    /// re-asserted while e.g. the FIFO is read. Afterwards the previous state of the two interrupt disable bits is
+    /// restored, regardless of the result of `f`. Other bits of the
+    /// [`HostInterfaceControl`](registers::HostInterfaceControl) register changed by `f` are kept.
+    pub fn with_interrupts_masked<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R, IF::Error> {
+        let control: registers::HostInterfaceControl = self.read_reg()?;
+        self.write_reg(
+            control
+                .with_wakeup_fifo_host_interrupt_disable(true)
+                .with_non_wakeup_fifo_host_interrupt_disable(true),
+        )?;
+        let result = f(self);
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_wakeup_fifo_host_interrupt_disable(
+                control.wakeup_fifo_host_interrupt_disable(),
+            )
+            .with_non_wakeup_fifo_host_interrupt_disable(
+                control.non_wakeup_fifo_host_interrupt_disable(),
+            )
+        })?;
+        Ok(result)
+    }
+
     /// Read a parameter from the BHI.
     /// 
     /// See [`parameters`] for more information.
-    pub fn read_param<T>(&mut self) -> Result<T, IF::Error>
+    #[cfg(feature = "nightly")]
+    #[inline]
+    pub fn read_param<T>(&mut self) -> Result<T, Error<IF::Error>>
     where
         T: Parameter + From<[u8; T::SIZE]>,
     {
         debug_assert!(T::SIZE <= 16);
+        let mut buf = [0; T::SIZE];
+        self.read_param_raw(T::PAGE, T::PARAM, &mut buf)?;
+        Ok(buf.into())
+    }
+
+    /// Read a parameter from the BHI, the size `N` is inferred from the conversion of `T`.
+    #[cfg(not(feature = "nightly"))]
+    #[inline]
+    pub fn read_param<T, const N: usize>(&mut self) -> Result<T, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; N]>,
+    {
+        let mut buf = [0; N];
+        self.read_param_raw(T::PAGE, T::PARAM, &mut buf)?;
+        Ok(buf.into())
+    }
+
+    /// Reads the bytes of a parameter into `buf` without converting them.
+    ///
+    /// Useful for dumping, comparing or forwarding parameters verbatim. Returns the filled part of `buf`
+    /// ([`T::SIZE`](Parameter::SIZE) bytes) or [`Error::BufferTooSmall`] if it cannot hold the parameter.
+    pub fn read_param_into<'a, T>(
+        &mut self,
+        buf: &'a mut [u8],
+    ) -> Result<&'a [u8], Error<IF::Error>>
+    where
+        T: Parameter,
+    {
+        let buf = buf.get_mut(..T::SIZE).ok_or(Error::BufferTooSmall)?;
+        self.read_param_raw(T::PAGE, T::PARAM, buf)?;
+        Ok(buf)
+    }
+
+    /// Sets how often the [`ParameterAcknowledge`](registers::ParameterAcknowledge) is read while waiting for the
+    /// BHI to acknowledge a parameter request, 100 by default.
+    ///
+    /// A request which is still unacknowledged afterwards fails with [`Error::InvalidState`]. The time this takes
+    /// depends on the bus speed, so raise the limit on fast buses if a busy firmware acknowledges too late.
+    ///
+    /// # Panics
+    /// If `polls` is 0.
+    pub fn set_ack_poll_limit(&mut self, polls: usize) {
+        assert!(polls > 0, "at least one poll is required");
+        self.ack_poll_limit = polls;
+    }
+
+    /// Reads the raw bytes of a parameter selected at runtime into `buf`.
+    ///
+    /// The length of `buf` is requested as size of the parameter.
+    /// If the BHI rejects the request, it is repeated up to [`PARAMETER_RETRIES`] times before
+    /// [`Error::ParameterRejected`] is returned. If it does not acknowledge the request at all,
+    /// [`Error::InvalidState`] is returned, see [`set_ack_poll_limit`](Self::set_ack_poll_limit).
+    ///
+    /// # Panics
+    /// If `buf` is longer than 16 bytes.
+    pub fn read_param_raw(
+        &mut self,
+        page: parameters::ParameterPage,
+        param: u8,
+        buf: &mut [u8],
+    ) -> Result<(), Error<IF::Error>> {
         #[cfg(feature = "log")]
-        log::info!(
-            "Reading param {}(page: {:?}, param: {}, size: {})",
-            core::any::type_name::<T>(),
-            T::PAGE,
-            T::PARAM,
-            T::SIZE
-        );
-        self.write_reg(
-            registers::ParameterPageSelect::new()
-                .with_parameter_page(T::PAGE)
-                .with_parameter_size(if T::SIZE < 16 { T::SIZE as u8 } else { 0 }),
+        log::info!(param_page:? = page, param_num = param, len = buf.len(); "Reading parameter");
+        for _ in 0..=PARAMETER_RETRIES {
+            let request = self.select_param_read(page, param, buf.len())?;
+            if self.await_param_ack(request)? {
+                #[cfg(feature = "metrics")]
+                metrics::BusMetrics::bump(&mut self.metrics.parameter_reads);
+                return Ok(self.bus_read(0x3B, buf)?);
+            }
+        }
+        Err(Error::ParameterRejected(page, param))
+    }
+
+    /// Writes `request` and reads the [`ParameterAcknowledge`](registers::ParameterAcknowledge) right away,
+    /// in one transaction if the interface supports it (see [`Interface::write_then_read`]).
+    fn send_param_request(
+        &mut self,
+        request: registers::ParameterRequest,
+    ) -> Result<registers::ParameterAcknowledge, IF::Error> {
+        let data = [request.into()];
+        self.track_write(registers::ParameterRequest::ADDR, &data);
+        let mut ack = [0; 1];
+        self.bus_write_then_read(
+            registers::ParameterRequest::ADDR,
+            &data,
+            registers::ParameterAcknowledge::ADDR,
+            &mut ack,
         )?;
+        Ok(ack.into())
+    }
+
+    /// Sends `request` and polls [`ParameterAcknowledge`](registers::ParameterAcknowledge) until it matches.
+    ///
+    /// Returns `false` if the BHI acknowledged the request with an error. The parameter interface is then parked
+    /// by writing a 0 request, which is the documented recovery, so the request can be repeated.
+    /// Fails with [`Error::InvalidState`] if the request is not acknowledged at all.
+    fn await_param_ack(
+        &mut self,
+        request: registers::ParameterRequest,
+    ) -> Result<bool, Error<IF::Error>> {
+        let mut ack = self.send_param_request(request)?;
+        let request: u8 = request.into();
+        let mut polls = 1;
+        loop {
+            #[cfg(feature = "metrics")]
+            metrics::BusMetrics::bump(&mut self.metrics.acknowledge_polls);
+            match ack {
+                registers::ParameterAcknowledge::Error => break,
+                registers::ParameterAcknowledge::RequestId(x) if x == request => {
+                    #[cfg(feature = "log")]
+                    log::debug!(param_request = request, polls = polls; "Parameter request acknowledged");
+                    #[cfg(not(feature = "log"))]
+                    let _ = polls;
+                    return Ok(true);
+                }
+                _ => {}
+            }
+            if polls >= self.ack_poll_limit {
+                #[cfg(feature = "log")]
+                log::error!(param_request = request, polls = polls; "Parameter request not acknowledged");
+                return Err(Error::InvalidState);
+            }
+            polls += 1;
+            ack = self.read_reg()?;
+        }
         #[cfg(feature = "log")]
-        log::info!("Write read param request");
-        self.write_reg(
+        log::warn!(param_request = request, polls = polls; "Parameter request rejected");
+        #[cfg(feature = "metrics")]
+        metrics::BusMetrics::bump(&mut self.metrics.parameter_rejections);
+        self.park_param_interface()?;
+        Ok(false)
+    }
+
+    /// Parks the parameter interface after a rejected request by writing a 0 request.
+    fn park_param_interface(&mut self) -> Result<(), Error<IF::Error>> {
+        let mut ack = self.send_param_request(
             registers::ParameterRequest::new()
-                .with_parameter(T::PARAM)
+                .with_parameter(0)
                 .with_request(registers::Request::Read),
         )?;
-        loop {
-            match self.read_reg()? {
-                registers::ParameterAcknowledge::Error => todo!(),
-                registers::ParameterAcknowledge::RequestId(x) if x == T::PARAM => break,
-                _ => continue,
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            if ack == registers::ParameterAcknowledge::RequestId(0) {
+                return Ok(());
             }
+            ack = self.read_reg()?;
         }
+        Err(Error::InvalidState)
+    }
+
+    /// Selects the page for a read of `size` bytes (at most 16) of a parameter and returns the request to send.
+    fn select_param_read(
+        &mut self,
+        page: parameters::ParameterPage,
+        param: u8,
+        size: usize,
+    ) -> Result<registers::ParameterRequest, IF::Error> {
+        assert!(
+            size <= PARAMETER_READ_SIZE,
+            "parameters are at most 16 bytes"
+        );
+        self.select_page(page, if size < 16 { size as u8 } else { 0 })?;
+        Ok(registers::ParameterRequest::new()
+            .with_parameter(param)
+            .with_request(registers::Request::Read))
+    }
+
+    /// Reads a parameter step by step, without waiting for the BHI.
+    ///
+    /// The first call requests the parameter, every call checks the acknowledgment once and returns
+    /// [`Poll::Pending`](core::task::Poll::Pending) until the value can be read. This allows superloops to do other work
+    /// in between instead of blocking in [`read_param`](Self::read_param). `poll` tracks the state of the read
+    /// and can be reused once the value was returned. Other parameter accesses must not be interleaved.
+    ///
+    /// Fails with [`Error::ParameterRejected`] if the BHI rejected the request. The parameter interface is parked
+    /// like in [`read_param`](Self::read_param), so the read can be started again with the same `poll`.
+    #[cfg(feature = "nightly")]
+    pub fn poll_param<T>(
+        &mut self,
+        poll: &mut ParamPoll<T>,
+    ) -> Result<core::task::Poll<T>, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; T::SIZE]>,
+    {
         let mut buf = [0; T::SIZE];
-        self.interface.read(0x3B, &mut buf)?;
-        Ok(buf.into())
+        Ok(self
+            .poll_param_raw(&mut poll.requested, T::PAGE, T::PARAM, &mut buf)?
+            .map(|()| buf.into()))
+    }
+
+    /// Reads a parameter step by step, the size `N` is inferred from the conversion of `T`.
+    #[cfg(not(feature = "nightly"))]
+    pub fn poll_param<T, const N: usize>(
+        &mut self,
+        poll: &mut ParamPoll<T>,
+    ) -> Result<core::task::Poll<T>, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; N]>,
+    {
+        let mut buf = [0; N];
+        Ok(self
+            .poll_param_raw(&mut poll.requested, T::PAGE, T::PARAM, &mut buf)?
+            .map(|()| buf.into()))
+    }
+
+    /// The non-generic part of [`poll_param`](Self::poll_param), fills `buf` once the parameter is ready.
+    fn poll_param_raw(
+        &mut self,
+        requested: &mut bool,
+        page: parameters::ParameterPage,
+        param: u8,
+        buf: &mut [u8],
+    ) -> Result<core::task::Poll<()>, Error<IF::Error>> {
+        let ack = if *requested {
+            self.read_reg()?
+        } else {
+            let request = self.select_param_read(page, param, buf.len())?;
+            *requested = true;
+            self.send_param_request(request)?
+        };
+        #[cfg(feature = "metrics")]
+        metrics::BusMetrics::bump(&mut self.metrics.acknowledge_polls);
+        match ack {
+            registers::ParameterAcknowledge::Error => {
+                *requested = false;
+                #[cfg(feature = "log")]
+                log::warn!(param_page:? = page, param_num = param; "Parameter request rejected");
+                #[cfg(feature = "metrics")]
+                metrics::BusMetrics::bump(&mut self.metrics.parameter_rejections);
+                self.park_param_interface()?;
+                Err(Error::ParameterRejected(page, param))
+            }
+            registers::ParameterAcknowledge::RequestId(x) if x == param => {
+                *requested = false;
+                #[cfg(feature = "metrics")]
+                metrics::BusMetrics::bump(&mut self.metrics.parameter_reads);
+                self.bus_read(0x3B, buf)?;
+                Ok(core::task::Poll::Ready(()))
+            }
+            _ => Ok(core::task::Poll::Pending),
+        }
     }
 
     /// Write a parameter to the BHI.
     /// 
     /// See [`parameters`] for more information.
-    pub fn write_param<T>(&mut self, param: T) -> Result<(), IF::Error>
+    #[inline]
+    #[cfg(feature = "nightly")]
+    pub fn write_param<T>(&mut self, param: T) -> Result<(), Error<IF::Error>>
     where
         T: Parameter + Into<[u8; T::SIZE]>,
     {
         debug_assert!(T::SIZE <= 8);
-        self.interface.write(0x5C, &param.into())?;
+        self.write_param_raw(T::PAGE, T::PARAM, &param.into())
+    }
 
-        self.write_reg(
-            registers::ParameterPageSelect::new()
-                .with_parameter_page(T::PAGE)
-                .with_parameter_size(if T::SIZE < 8 { T::SIZE as u8 } else { 0 }),
-        )?;
+    /// Write a parameter to the BHI, the size `N` is inferred from the conversion of `T`.
+    #[cfg(not(feature = "nightly"))]
+    pub fn write_param<T, const N: usize>(&mut self, param: T) -> Result<(), Error<IF::Error>>
+    where
+        T: Parameter + Into<[u8; N]>,
+    {
+        self.write_param_raw(T::PAGE, T::PARAM, &param.into())
+    }
+
+    /// Writes the raw bytes of a parameter selected at runtime (at most 8).
+    ///
+    /// The whole parameter load window (`0x5C`-`0x63`) is written with `data` padded with zeros,
+    /// so no stale bytes of earlier writes are left in the window.
+    /// If the BHI rejects the request, it is repeated up to [`PARAMETER_RETRIES`] times before
+    /// [`Error::ParameterRejected`] is returned. If it does not acknowledge the request at all,
+    /// [`Error::InvalidState`] is returned, see [`set_ack_poll_limit`](Self::set_ack_poll_limit).
+    ///
+    /// # Panics
+    /// If `data` is longer than 8 bytes.
+    pub fn write_param_raw(
+        &mut self,
+        page: parameters::ParameterPage,
+        param: u8,
+        data: &[u8],
+    ) -> Result<(), Error<IF::Error>> {
+        assert!(
+            data.len() <= PARAMETER_WRITE_SIZE,
+            "parameters are at most 8 bytes"
+        );
+        #[cfg(feature = "log")]
+        log::info!(param_page:? = page, param_num = param, len = data.len(); "Writing parameter");
+        let mut window = [0; PARAMETER_WRITE_SIZE];
+        window[..data.len()].copy_from_slice(data);
+        self.bus_write(0x5C, &window)?;
 
+        let size = data.len();
         let request = registers::ParameterRequest::new()
-            .with_parameter(T::PARAM)
+            .with_parameter(param)
             .with_request(registers::Request::Write);
-        self.write_reg(request.clone())?;
-        let request = request.into();
-        loop {
-            match self.read_reg()? {
-                registers::ParameterAcknowledge::Error => todo!(),
-                registers::ParameterAcknowledge::RequestId(x) if x == request => break,
-                _ => continue,
+        let mut accepted = false;
+        for _ in 0..=PARAMETER_RETRIES {
+            self.select_page(page, if size < 8 { size as u8 } else { 0 })?;
+            if self.await_param_ack(request)? {
+                accepted = true;
+                break;
             }
         }
+        if !accepted {
+            return Err(Error::ParameterRejected(page, param));
+        }
+        #[cfg(feature = "metrics")]
+        metrics::BusMetrics::bump(&mut self.metrics.parameter_writes);
         self.write_reg(
             registers::ParameterRequest::new()
                 .with_parameter(0)
@@ -155,44 +985,957 @@ where
         Ok(())
     }
 
-    /// Upload a raw firmware to the BHI.
-    /// 
-    /// The raw firmware is the body part of the firmware file.
-    /// See [`firmware`] for more info.
-    /// Returns the crc32 on success
-    pub fn upload_raw_firmware(&mut self, firmware: &[u8]) -> Result<u32, IF::Error> {
-        // Disable cpu and enable upload
-        //log::info!("Chip control: {:?}", self.read_reg::<registers::ChipControl>()?);
+    /// Configures a sensor selected at runtime.
+    ///
+    /// This is equivalent to writing the corresponding Sensor Configuration parameter
+    /// (e.g. [`AccelerometerConfig`](parameters::sensors::AccelerometerConfig)) but also supports the wakeup variants.
+    /// A sample rate of 0 disables the sensor.
+    pub fn configure_sensor(
+        &mut self,
+        id: SensorId,
+        config: parameters::sensors::SensorConfig,
+    ) -> Result<(), Error<IF::Error>> {
+        if !matches!(id as u8, 1..=63) {
+            return Err(Error::Unsupported);
+        }
+        self.write_param_raw(
+            parameters::ParameterPage::Sensors,
+            id as u8 + 64,
+            &config.into_bytes(),
+        )?;
+        if self.significant_motion.map(|armed| armed.id) == Some(id) {
+            self.significant_motion = None;
+        }
+        Ok(())
+    }
+
+    /// Arms the Significant Motion sensor (the wakeup variant if `wakeup` is `true`).
+    ///
+    /// Significant Motion is a one-shot sensor: the BHI disables it after reporting the event.
+    /// The driver tracks this while draining the FIFO (see [`drain_fifo`](Self::drain_fifo)): once the event was read
+    /// the sensor is either armed again (if `rearm` is `true`) or no longer reported by
+    /// [`significant_motion_armed`](Self::significant_motion_armed).
+    /// Configuring the sensor with [`configure_sensor`](Self::configure_sensor) stops the tracking.
+    ///
+    /// Returns the id of the armed sensor.
+    pub fn arm_significant_motion(
+        &mut self,
+        wakeup: bool,
+        rearm: bool,
+    ) -> Result<SensorId, Error<IF::Error>> {
+        let kind = parameters::sensors::SensorKind::SignificantMotion;
+        let config = parameters::sensors::SensorConfig::new().with_sample_rate(1);
+        let id = if wakeup {
+            self.enable_wakeup_sensor(kind.id(false), config)?
+        } else {
+            self.configure_sensor(kind.id(false), config)?;
+            kind.id(false)
+        };
+        self.significant_motion = Some(OneShot { id, rearm });
+        Ok(id)
+    }
+
+    /// Returns the id of the Significant Motion sensor armed by [`arm_significant_motion`](Self::arm_significant_motion)
+    /// if it did not fire yet (or is armed again).
+    pub fn significant_motion_armed(&self) -> Option<SensorId> {
+        self.significant_motion.map(|armed| armed.id)
+    }
+
+    /// Updates the state of the one-shot sensors after `id` fired.
+    fn one_shot_fired(&mut self, id: SensorId) -> Result<(), Error<IF::Error>> {
+        match self.significant_motion {
+            Some(armed) if armed.id == id && armed.rearm => {
+                self.write_param_raw(
+                    parameters::ParameterPage::Sensors,
+                    id as u8 + 64,
+                    &parameters::sensors::SensorConfig::new()
+                        .with_sample_rate(1)
+                        .into_bytes(),
+                )?;
+            }
+            Some(armed) if armed.id == id => self.significant_motion = None,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Enables the first of `candidates` which is provided by the running firmware.
+    ///
+    /// Allows running on firmwares with different sensor sets, e.g. with
+    /// [`SensorKind::ORIENTATION_FALLBACK`](parameters::sensors::SensorKind::ORIENTATION_FALLBACK)
+    /// the rotation vector is used if available, otherwise the game rotation vector or the orientation.
+    /// The non-wakeup variant is configured with `config`. The firmware is scanned once, see [`capabilities`](Self::capabilities).
+    ///
+    /// Returns the enabled sensor or [`Error::Unsupported`] if the firmware provides none of `candidates`.
+    pub fn enable_best_of(
+        &mut self,
+        candidates: &[parameters::sensors::SensorKind],
+        config: parameters::sensors::SensorConfig,
+    ) -> Result<parameters::sensors::SensorKind, Error<IF::Error>> {
+        let sensors = self.capabilities()?.sensors;
+        let kind = candidates
+            .iter()
+            .copied()
+            .find(|kind| sensors.contains(kind.id(false)))
+            .ok_or(Error::Unsupported)?;
+        self.configure_sensor(kind.id(false), config)?;
+        Ok(kind)
+    }
+
+    /// Enables the sensors of `config` in their order and sets the host suspend state.
+    ///
+    /// Other sensors keep their configuration. Fails with [`Error::Unsupported`] before configuring any sensor
+    /// if the running firmware does not provide all sensors of `config` (see [`capabilities`](Self::capabilities)).
+    /// If a later step fails, the sensors of `config` configured so far are disabled again before returning,
+    /// so a retry starts from a known state.
+    pub fn apply_config(
+        &mut self,
+        config: &profiles::Bhi160Config<'_>,
+    ) -> Result<(), profiles::ConfigError<IF::Error>> {
+        let fail = |step, error| profiles::ConfigError {
+            step,
+            error,
+            rollback_error: None,
+        };
+        let sensors = self
+            .capabilities()
+            .map_err(|error| fail(profiles::ConfigStep::Check, error))?
+            .sensors;
+        if !config
+            .sensors
+            .iter()
+            .all(|setup| sensors.contains(setup.id))
+        {
+            return Err(fail(profiles::ConfigStep::Check, Error::Unsupported));
+        }
+        let mut failure = None;
+        for (index, setup) in config.sensors.iter().enumerate() {
+            if let Err(error) = self.configure_sensor(setup.id, setup.config()) {
+                failure = Some((index, fail(profiles::ConfigStep::Sensor(index), error)));
+                break;
+            }
+        }
+        let (applied, mut error) = match failure {
+            Some(failure) => failure,
+            None => match self.set_ap_suspended(config.ap_suspended) {
+                Ok(()) => return Ok(()),
+                Err(error) => (
+                    config.sensors.len(),
+                    fail(profiles::ConfigStep::ApSuspended, error.into()),
+                ),
+            },
+        };
+        #[cfg(feature = "log")]
+        log::warn!(step:? = error.step; "Applying the configuration failed, rolling back");
+        for setup in &config.sensors[..applied] {
+            let disabled =
+                self.configure_sensor(setup.id, parameters::sensors::SensorConfig::new());
+            if let Err(rollback) = disabled {
+                error.rollback_error.get_or_insert(rollback);
+            }
+        }
+        Err(error)
+    }
+
+    /// Reads the information of a virtual sensor (wakeup or non-wakeup).
+    ///
+    /// The [`sensor_type`](parameters::sensors::SensorInfo::sensor_type) of the result is always `id`,
+    /// even if the firmware reported the Sensor Type without (or with) the wakeup bit.
+    /// Sensors which are not present are returned as reported (i.e. with Sensor Type 0).
+    ///
+    /// Fails with [`Error::Unsupported`] if `id` is no virtual sensor and with [`Error::InvalidState`]
+    /// if the reported Sensor Type belongs to another sensor.
+    pub fn sensor_info(
+        &mut self,
+        id: SensorId,
+    ) -> Result<parameters::sensors::SensorInfo, Error<IF::Error>> {
+        if !matches!(id as u8, 1..=63) {
+            return Err(Error::Unsupported);
+        }
+        let mut buf = [0; 16];
+        self.read_param_raw(parameters::ParameterPage::Sensors, id as u8, &mut buf)?;
+        let info = parameters::sensors::SensorInfo::from_bytes(buf);
+        if info.sensor_type_raw() == 0 {
+            return Ok(info);
+        }
+        if !info.matches(id) {
+            return Err(Error::InvalidState);
+        }
+        Ok(info.with_sensor_type(id))
+    }
+
+    /// Reads the FIFO reservations of the given sensors.
+    ///
+    /// Sensors which are not provided by the firmware are skipped.
+    #[cfg(feature = "alloc")]
+    pub fn fifo_budget(
+        &mut self,
+        ids: &[SensorId],
+    ) -> Result<budget::FifoBudget, Error<IF::Error>> {
+        let mut budget = budget::FifoBudget::default();
+        for &id in ids {
+            let info = self.sensor_info(id)?;
+            if info.sensor_type_raw() != 0 {
+                budget
+                    .reservations
+                    .push(budget::FifoReservation::from_info(id, &info));
+            }
+        }
+        Ok(budget)
+    }
+
+    /// Enables a sensor such that its events wake the host (application processor).
+    ///
+    /// Selects the wakeup variant of `id`, configures it with `config` and makes sure the wakeup FIFO may assert the host interrupt.
+    /// Use [`set_ap_suspended`](Self::set_ap_suspended) to restrict the host interrupt to wakeup sensors while the host sleeps.
+    ///
+    /// Returns the id of the enabled wakeup sensor or [`Error::Unsupported`] if there is no wakeup variant of `id`.
+    pub fn enable_wakeup_sensor(
+        &mut self,
+        id: SensorId,
+        config: parameters::sensors::SensorConfig,
+    ) -> Result<SensorId, Error<IF::Error>> {
+        let id = id.wakeup_variant().ok_or(Error::Unsupported)?;
+        self.configure_sensor(id, config)?;
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_wakeup_fifo_host_interrupt_disable(false)
+        })?;
+        Ok(id)
+    }
+
+    /// Disables a sensor and waits until its samples queued in the FIFO have been read.
+    ///
+    /// Zeros the configuration of `id`, requests a flush of its FIFO and drains the FIFO until the
+    /// [`FlushComplete`](packet::MetaEvent::FlushComplete) meta event of `id` arrives.
+    /// All events read in the meantime (including the flush meta event) are passed to `f`,
+    /// so no other sensor loses data. `buf` is used like in [`drain_fifo`](Self::drain_fifo).
+    ///
+    /// Fails with [`Error::InvalidState`] if the flush does not complete.
+    pub fn disable_sensor_and_flush(
+        &mut self,
+        id: SensorId,
+        buf: &mut [u8],
+        mut f: impl FnMut(packet::Event),
+    ) -> Result<(), Error<IF::Error>> {
+        self.configure_sensor(id, parameters::sensors::SensorConfig::new())?;
+        self.write_reg(registers::FifoFlush::Sensor(id))?;
+        let mut flushed = false;
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            self.drain_fifo_throttled(buf, Throttle::unlimited(), |event| {
+                if let packet::SensorData::MetaEvent(packet::MetaEvent::FlushComplete(sensor)) =
+                    event.data()
+                {
+                    flushed |= *sensor == id;
+                }
+                f(event);
+            })?;
+            if flushed {
+                return Ok(());
+            }
+        }
+        Err(Error::InvalidState)
+    }
+
+    /// Writes the meta event control of the selected FIFO(s).
+    ///
+    /// The wakeup FIFO has its own copy of the meta event control, so meta events enabled only for the
+    /// non-wakeup FIFO are not delivered while the host is suspended (see [`set_ap_suspended`](Self::set_ap_suspended)).
+    pub fn enable_meta_events(
+        &mut self,
+        control: parameters::system::MetaEventControl,
+        fifo: parameters::system::MetaEventFifo,
+    ) -> Result<(), Error<IF::Error>> {
+        use parameters::system::{MetaEventControlWakeup, MetaEventFifo};
+
+        if fifo != MetaEventFifo::Wakeup {
+            self.write_param(control)?;
+        }
+        if fifo != MetaEventFifo::NonWakeup {
+            self.write_param(MetaEventControlWakeup(control))?;
+        }
+        Ok(())
+    }
+
+    /// Sets up the BHI such that the host may sleep until the device is moved.
+    ///
+    /// Performs the steps in the required order:
+    /// 1. Restricts the meta events of the wakeup FIFO to [`Error`](packet::MetaEvent::Error) and
+    ///    [`SensorError`](packet::MetaEvent::SensorError), so only faults wake the host besides `trigger`.
+    /// 2. Enables the wakeup variant of `trigger` (see [`enable_wakeup_sensor`](Self::enable_wakeup_sensor)).
+    ///    Use [`SensorKind::SignificantMotion`](parameters::sensors::SensorKind::SignificantMotion) or
+    ///    [`SensorKind::TiltDetector`](parameters::sensors::SensorKind::TiltDetector).
+    /// 3. Informs the BHI that the host is suspended, which masks the interrupt of the non-wakeup FIFO
+    ///    (see [`suspend`](Self::suspend)).
+    ///
+    /// Call [`resume`](Self::resume) after the host woke up. Returns the id of the enabled wakeup sensor.
+    pub fn configure_wake_on_motion(
+        &mut self,
+        trigger: parameters::sensors::SensorKind,
+    ) -> Result<SensorId, Error<IF::Error>> {
+        use parameters::system::{MetaEvent, MetaEventControl, MetaEventFifo};
+
+        let fault = MetaEvent::new().with_enable(true).with_int_enable(true);
+        self.enable_meta_events(
+            MetaEventControl::new()
+                .with_event4(fault)
+                .with_event11(fault),
+            MetaEventFifo::Wakeup,
+        )?;
+        let id = self.enable_wakeup_sensor(
+            trigger.id(false),
+            parameters::sensors::SensorConfig::new().with_sample_rate(1),
+        )?;
+        self.suspend()?;
+        Ok(id)
+    }
+
+    /// Runs the self-test of the given (physical) sensors and collects the results.
+    ///
+    /// The BHI performs the self-test when leaving the algorithm standby, so this requests the self-test,
+    /// cycles [`algorithm_standby_request`](registers::HostInterfaceControl::algorithm_standby_request)
+    /// and drains the FIFO until all `sensors` reported a [`SelfTestResult`](packet::MetaEvent::SelfTestResult).
+    /// The meta event is enabled if necessary. All events are passed to `f`, `buf` is used like in
+    /// [`drain_fifo`](Self::drain_fifo).
+    ///
+    /// Sensors which did not report in time are listed in [`missing`](diagnostics::SelfTestReport::missing).
+    #[cfg(feature = "alloc")]
+    pub fn self_test(
+        &mut self,
+        sensors: &[SensorId],
+        buf: &mut [u8],
+        mut f: impl FnMut(packet::Event),
+    ) -> Result<diagnostics::SelfTestReport, Error<IF::Error>> {
+        let control: parameters::system::MetaEventControl = self.read_param()?;
+        if !control.event15().enable() {
+            self.write_param(control.with_event15(control.event15().with_enable(true)))?;
+        }
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_algorithm_standby_request(true)
+                .with_request_sensor_self_test(true)
+        })?;
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_algorithm_standby_request(false)
+        })?;
+
+        let mut report = diagnostics::SelfTestReport::default();
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            self.drain_fifo(buf, usize::MAX, |event| {
+                if let Some(result) = diagnostics::SelfTestReport::result_event(&event) {
+                    report.results.push(result);
+                }
+                f(event);
+            })?;
+            if sensors.iter().all(|&id| report.get(id).is_some()) {
+                break;
+            }
+        }
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_request_sensor_self_test(false)
+        })?;
+        report.missing = sensors
+            .iter()
+            .copied()
+            .filter(|&id| report.get(id).is_none())
+            .collect();
+        Ok(report)
+    }
+
+    /// Selects the coordinate system of the orientation and vector outputs.
+    ///
+    /// The [`ned_coordinates`](registers::HostInterfaceControl::ned_coordinates) flag reverts to ENU on every reset.
+    /// The driver therefore remembers the choice and applies it again when the CPU is started
+    /// (see [`start_cpu`](Self::start_cpu)) and whenever it writes the host interface control register
+    /// (e.g. [`set_ap_suspended`](Self::set_ap_suspended) and [`resume`](Self::resume)).
+    /// After a reset the driver did not observe (e.g. a power cycle) call
+    /// [`restore_coordinate_system`](Self::restore_coordinate_system).
+    pub fn set_coordinate_system(
+        &mut self,
+        coordinates: registers::CoordinateSystem,
+    ) -> Result<(), IF::Error> {
+        self.coordinate_system = Some(coordinates);
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_coordinate_system(coordinates)
+        })
+    }
+
+    /// The coordinate system selected by [`set_coordinate_system`](Self::set_coordinate_system), if any.
+    pub fn coordinate_system(&self) -> Option<registers::CoordinateSystem> {
+        self.coordinate_system
+    }
+
+    /// Writes the coordinate system selected by [`set_coordinate_system`](Self::set_coordinate_system) again.
+    ///
+    /// Does nothing if no coordinate system was selected.
+    pub fn restore_coordinate_system(&mut self) -> Result<(), IF::Error> {
+        match self.coordinate_system {
+            Some(coordinates) => self.set_coordinate_system(coordinates),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the selected coordinate system to a value written to the host interface control register.
+    fn with_coordinate_system(
+        &self,
+        reg: registers::HostInterfaceControl,
+    ) -> registers::HostInterfaceControl {
+        match self.coordinate_system {
+            Some(coordinates) => reg.with_coordinate_system(coordinates),
+            None => reg,
+        }
+    }
+
+    /// Informs the BHI whether the host (application processor) is suspended.
+    ///
+    /// While suspended only wakeup sensors (see [`enable_wakeup_sensor`](Self::enable_wakeup_sensor)) may assert the
+    /// host interrupt: [`ap_suspended`](registers::HostInterfaceControl::ap_suspended) is set and the interrupt of the
+    /// non-wakeup FIFO is masked. Non-wakeup events are still collected in their FIFO.
+    /// When not suspended both FIFOs may assert the host interrupt.
+    pub fn set_ap_suspended(&mut self, suspended: bool) -> Result<(), IF::Error> {
+        let reg: registers::HostInterfaceControl = self.read_reg()?;
+        self.write_reg(
+            self.with_coordinate_system(reg)
+                .with_ap_suspended(suspended)
+                .with_non_wakeup_fifo_host_interrupt_disable(suspended)
+                .with_wakeup_fifo_host_interrupt_disable(false),
+        )
+    }
+
+    /// Prepares the BHI for the suspend of the host.
+    ///
+    /// Only wakeup sensors may assert the host interrupt until [`resume`](Self::resume) is called
+    /// (see [`set_ap_suspended`](Self::set_ap_suspended)). Call this right before the host enters its sleep state.
+    pub fn suspend(&mut self) -> Result<(), IF::Error> {
+        self.set_ap_suspended(true)
+    }
+
+    /// Informs the BHI that the host is awake again and reads everything collected while it was suspended.
+    ///
+    /// Unmasks the interrupt of the non-wakeup FIFO, requests an up to date transfer count and drains both FIFOs,
+    /// passing all events to `f` like [`drain_fifo`](Self::drain_fifo).
+    pub fn resume(
+        &mut self,
+        buf: &mut [u8],
+        f: impl FnMut(packet::Event),
+    ) -> Result<DrainProgress, Error<IF::Error>> {
+        let reg: registers::HostInterfaceControl = self.read_reg()?;
         self.write_reg(
-            registers::ChipControl::new()
-                .with_cpu_run_request(false)
-                .with_host_upload_enable(true),
+            self.with_coordinate_system(reg)
+                .with_ap_suspended(false)
+                .with_non_wakeup_fifo_host_interrupt_disable(false)
+                .with_wakeup_fifo_host_interrupt_disable(false)
+                .with_update_transfer_count(true),
         )?;
+        self.drain_fifo_throttled(buf, Throttle::unlimited(), f)
+    }
 
-        // Reset upload address
-        self.write_reg(registers::UploadAddress(0))?;
+    /// Halts the CPU of the BHI.
+    ///
+    /// Waits until [`ChipStatus::firmware_idle`](registers::ChipStatus::firmware_idle) confirms that the firmware stopped.
+    pub fn halt_cpu(&mut self) -> Result<(), Error<IF::Error>> {
+        self.update_reg(|reg: registers::ChipControl| reg.with_cpu_run_request(false))?;
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            let status: registers::ChipStatus = self.read_reg()?;
+            if status.firmware_idle() {
+                return Ok(());
+            }
+        }
+        Err(Error::InvalidState)
+    }
 
-        // Finally burst the firmware
-        for chunk in firmware.chunks_exact(16) {
-            self.interface.write(0x96, chunk)?;
+    /// Starts the CPU of the BHI.
+    ///
+    /// Fails with [`Error::InvalidState`] if the upload mode is still enabled (see [`end_upload`](Self::end_upload)).
+    /// Afterwards the coordinate system selected by [`set_coordinate_system`](Self::set_coordinate_system) is restored.
+    pub fn start_cpu(&mut self) -> Result<(), Error<IF::Error>> {
+        let control: registers::ChipControl = self.read_reg()?;
+        if control.host_upload_enable() {
+            return Err(Error::InvalidState);
         }
+        self.write_reg(control.with_cpu_run_request(true))?;
+        self.confirm_chip_control(true, false)?;
+        Ok(self.restore_coordinate_system()?)
+    }
+
+    /// Enables the upload mode for a firmware patch.
+    ///
+    /// The patch is streamed with the returned [`Uploader`](firmware::Uploader), which disables the upload mode
+    /// again when it is finished or dropped.
+    /// Fails with [`Error::InvalidState`] if the CPU is running (see [`halt_cpu`](Self::halt_cpu)).
+    pub fn begin_upload(&mut self) -> Result<firmware::Uploader<'_, IF>, Error<IF::Error>> {
+        let control: registers::ChipControl = self.read_reg()?;
+        if control.cpu_run_request() {
+            return Err(Error::InvalidState);
+        }
+        self.write_reg(control.with_host_upload_enable(true))?;
+        self.confirm_chip_control(false, true)?;
+        Ok(firmware::Uploader::new(self))
+    }
+
+    /// Disables the upload mode. Afterwards the CPU may be started using [`start_cpu`](Self::start_cpu).
+    pub fn end_upload(&mut self) -> Result<(), Error<IF::Error>> {
+        self.update_reg(|reg: registers::ChipControl| reg.with_host_upload_enable(false))?;
+        self.confirm_chip_control(false, false)
+    }
 
+    /// Reads back the [`ChipControl`](registers::ChipControl) register and checks that it matches the expected state.
+    fn confirm_chip_control(
+        &mut self,
+        cpu_run: bool,
+        upload: bool,
+    ) -> Result<(), Error<IF::Error>> {
+        let control: registers::ChipControl = self.read_reg()?;
+        if control.cpu_run_request() == cpu_run && control.host_upload_enable() == upload {
+            Ok(())
+        } else {
+            Err(Error::InvalidState)
+        }
+    }
+
+    /// Writes the bursts of a firmware body and the incomplete last burst, then reads the CRC.
+    fn upload_body(
+        &mut self,
+        chunks: impl Iterator<Item = [u8; 16]>,
+        remainder: impl Iterator<Item = u8>,
+    ) -> Result<u32, IF::Error> {
+        for chunk in chunks {
+            self.bus_write(0x96, &chunk)?;
+        }
+        let mut rest = [0; 16];
+        let mut len = 0;
+        for (slot, byte) in rest.iter_mut().zip(remainder) {
+            *slot = byte;
+            len += 1;
+        }
+        if len > 0 {
+            self.bus_write(0x96, &rest[..len])?;
+        }
         let registers::UploadCrc(crc) = self.read_reg()?;
         Ok(crc)
     }
 
+    /// Reads the uploaded RAM patch back and compares it with the body of `firmware`.
+    ///
+    /// This is a stronger check than comparing the [`UploadCrc`](registers::UploadCrc).
+    /// The upload data port (`0x96`) can only be read while
+    /// [`host_upload_enable`](registers::ChipControl::host_upload_enable) is set, so this has to be done after the
+    /// upload and before [`end_upload`](Self::end_upload). Otherwise [`Error::InvalidState`] is returned without
+    /// touching the port, compare the CRC instead (see [`firmware_matches`](Self::firmware_matches)).
+    ///
+    /// The patch is read in 16-byte bursts starting at upload address 0 and compared with the
+    /// byteswapped [`Firmware::body`](firmware::Firmware::body). Reading advances the upload address like writing,
+    /// so it is left at the end of the compared data.
+    /// Returns `false` on the first mismatching burst.
+    pub fn verify_uploaded_firmware<T>(
+        &mut self,
+        firmware: &firmware::Firmware<T>,
+    ) -> Result<bool, Error<IF::Error>>
+    where
+        T: AsRef<[u8]>,
+    {
+        let control: registers::ChipControl = self.read_reg()?;
+        if !control.host_upload_enable() {
+            return Err(Error::InvalidState);
+        }
+        self.write_reg(registers::UploadAddress(0))?;
+
+        let mut body = firmware.body().peekable();
+        while body.peek().is_some() {
+            let mut expected = [0; 16];
+            let mut len = 0;
+            for (slot, byte) in expected.iter_mut().zip(body.by_ref()) {
+                *slot = byte;
+                len += 1;
+            }
+            let mut actual = [0; 16];
+            self.bus_read(0x96, &mut actual[..len])?;
+            if actual[..len] != expected[..len] {
+                #[cfg(feature = "log")]
+                log::error!(len = len; "Firmware readback mismatch");
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the versions of the ROM and the loaded RAM patch.
+    pub fn firmware_identity(&mut self) -> Result<firmware::FirmwareIdentity, IF::Error> {
+        let identity = firmware::FirmwareIdentity {
+            rom_version: self.read_reg()?,
+            ram_version: self.read_reg()?,
+        };
+        #[cfg(feature = "log")]
+        log::info!(firmware:% = identity; "Firmware identified");
+        Ok(identity)
+    }
+
+    /// Checks whether the given firmware is already running on the BHI.
+    ///
+    /// Compares the [`UploadCrc`](registers::UploadCrc) of the last upload with the CRC of `firmware`.
+    /// This can be used to skip redundant uploads. Returns `false` if no RAM patch is loaded
+    /// (i.e. [`RamVersion`](registers::RamVersion) reads 0), e.g. after a reset.
+    pub fn firmware_matches<T>(
+        &mut self,
+        firmware: &firmware::Firmware<T>,
+    ) -> Result<bool, IF::Error>
+    where
+        T: AsRef<[u8]>,
+    {
+        let version: registers::RamVersion = self.read_reg()?;
+        if !version.is_loaded() {
+            return Ok(false);
+        }
+        let registers::UploadCrc(crc) = self.read_reg()?;
+        Ok(crc == firmware.crc())
+    }
+
     /// Read the data fifo.
     /// 
-    /// After reading the data you may analyze it using the methods provided in the [`packet`] module.
+    /// This is the way to access the raw FIFO data. Reads as many bytes as announced by
+    /// [`BytesRemaining`](registers::BytesRemaining) (limited by the length of `buf`) and returns the filled part of `buf`.
+    /// After reading the data you may analyze it using the methods provided in the [`packet`] module,
+    /// e.g. [`packet::decode_events`] or [`packet::EventReader`].
+    /// Use [`drain_fifo`](Self::drain_fifo) to read and decode the FIFO in chunks using a small buffer
+    /// and [`read_fifo_checked`](Self::read_fifo_checked) to additionally check the data for consistency.
     /// 
     /// NOTE: The buffer should be big enough to read the whole FIFO. Otherwise the transfer has to be aborted.
+    /// Use [`read_fifo_report`](Self::read_fifo_report) to detect this.
     pub fn read_fifo<'a>(&mut self, buf: &'a mut [u8]) -> Result<&'a [u8], IF::Error> {
+        Ok(self.read_fifo_report(buf)?.0)
+    }
+
+    /// Read the data fifo and report how much of the transfer was read.
+    ///
+    /// Works like [`read_fifo`](Self::read_fifo) but additionally returns a [`FifoReadReport`],
+    /// which allows to detect that `buf` was too small for the transfer.
+    pub fn read_fifo_report<'a>(
+        &mut self,
+        buf: &'a mut [u8],
+    ) -> Result<(&'a [u8], FifoReadReport), IF::Error> {
+        let registers::BytesRemaining(remaining) = self.read_reg()?;
+        let end = buf.len().min(remaining as usize);
+        let buf = &mut buf[..end];
+        if end > 0 {
+            self.bus_read(0x00, buf)?;
+        }
+        let bytes_remaining_after = remaining as usize - end;
+        #[cfg(feature = "log")]
+        if bytes_remaining_after > 0 {
+            log::warn!(
+                len = end,
+                bytes_not_read = bytes_remaining_after;
+                "FIFO buffer too small"
+            );
+        }
+        let report = FifoReadReport {
+            bytes_read: end,
+            bytes_remaining_after,
+            truncated: bytes_remaining_after > 0,
+        };
+        Ok((buf, report))
+    }
+
+    /// Reads the first bytes of the current transfer (up to the length of `buf`) and aborts the transfer.
+    ///
+    /// Meant for debugging and for finding out how big a buffer the real read needs.
+    /// The abort leaves the transfer state of the BHI consistent but _discards_ the pending data
+    /// (see [`HostInterfaceControl::abort_transfer`](registers::HostInterfaceControl::abort_transfer)):
+    /// the peeked events are not read again. The request is cleared once [`BytesRemaining`](registers::BytesRemaining)
+    /// reads 0, so following transfers are not aborted as well.
+    ///
+    /// Fails with [`Error::InvalidState`] if the abort is not confirmed.
+    pub fn peek_fifo<'a>(&mut self, buf: &'a mut [u8]) -> Result<FifoPeek<'a>, Error<IF::Error>> {
+        let (data, report) = self.read_fifo_report(buf)?;
+        let peek = FifoPeek {
+            data,
+            transfer_size: report.bytes_read + report.bytes_remaining_after,
+        };
+        if peek.transfer_size == 0 {
+            return Ok(peek);
+        }
+        self.update_reg(|reg: registers::HostInterfaceControl| reg.with_abort_transfer(true))?;
+        let mut aborted = false;
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            let registers::BytesRemaining(remaining) = self.read_reg()?;
+            if remaining == 0 {
+                aborted = true;
+                break;
+            }
+        }
+        self.update_reg(|reg: registers::HostInterfaceControl| reg.with_abort_transfer(false))?;
+        if !aborted {
+            return Err(Error::InvalidState);
+        }
+        Ok(peek)
+    }
+
+    /// Read the whole current transfer of the data fifo into a newly allocated buffer.
+    ///
+    /// The buffer is allocated with exactly the size announced by [`BytesRemaining`](registers::BytesRemaining).
+    #[cfg(feature = "alloc")]
+    pub fn read_fifo_vec(&mut self) -> Result<alloc::vec::Vec<u8>, IF::Error> {
+        let registers::BytesRemaining(remaining) = self.read_reg()?;
+        let mut buf = alloc::vec![0; remaining as usize];
+        if !buf.is_empty() {
+            self.bus_read(0x00, &mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    /// Read and decode the whole current transfer of the data fifo.
+    ///
+    /// See [`read_fifo_vec`](Self::read_fifo_vec) and [`packet::decode_events_vec`].
+    #[cfg(feature = "alloc")]
+    pub fn read_events_vec(&mut self) -> Result<alloc::vec::Vec<packet::Event>, Error<IF::Error>> {
+        let data = self.read_fifo_vec()?;
+        packet::decode_events_vec(&data).map_err(Error::CorruptFifo)
+    }
+
+    /// Read the data fifo and check it for consistency.
+    ///
+    /// Works like [`read_fifo`](Self::read_fifo) but additionally validates the data using
+    /// [`packet::validate_fifo`] before returning it.
+    /// If the whole FIFO was read the events also have to match the length announced by
+    /// [`BytesRemaining`](registers::BytesRemaining).
+    /// Use this on marginal bus setups to detect corrupted data instead of decoding wrong samples.
+    pub fn read_fifo_checked<'a>(
+        &mut self,
+        buf: &'a mut [u8],
+    ) -> Result<&'a [u8], Error<IF::Error>> {
         let registers::BytesRemaining(remaining) = self.read_reg()?;
         let end = buf.len().min(remaining as usize);
         let buf = &mut buf[..end];
         if end > 0 {
-            self.interface.read(0x00, buf)?;
+            self.bus_read(0x00, buf)?;
+        }
+        let valid = packet::validate_fifo(buf).map_err(Error::CorruptFifo)?;
+        if end == remaining as usize && valid != end {
+            return Err(Error::CorruptFifo(packet::CorruptFifo { offset: valid }));
         }
         Ok(buf)
     }
+
+    /// Requests an up to date [`BytesRemaining`](registers::BytesRemaining) and reads it.
+    ///
+    /// Sets [`update_transfer_count`](registers::HostInterfaceControl::update_transfer_count) and reads the register
+    /// until two successive reads agree, because the BHI updates it asynchronously. The request is cleared afterwards
+    /// (the bit does not clear itself). Like the register itself, the result is only an approximation of the amount
+    /// of data in the FIFO and does not extend a transfer which is already in progress.
+    pub fn refresh_and_read_bytes_remaining(&mut self) -> Result<u16, Error<IF::Error>> {
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_update_transfer_count(true)
+        })?;
+        let registers::BytesRemaining(mut remaining) = self.read_reg()?;
+        let mut settled = false;
+        for _ in 0..STATE_POLL_ATTEMPTS {
+            let registers::BytesRemaining(current) = self.read_reg()?;
+            if current == remaining {
+                settled = true;
+                break;
+            }
+            remaining = current;
+        }
+        self.update_reg(|reg: registers::HostInterfaceControl| {
+            reg.with_update_transfer_count(false)
+        })?;
+        if !settled {
+            return Err(Error::InvalidState);
+        }
+        Ok(remaining)
+    }
+
+    /// Selects how the drain methods (e.g. [`drain_fifo`](Self::drain_fifo) and [`events`](Self::events))
+    /// handle zero bytes in the FIFO data, [`Padding::Strict`](packet::Padding::Strict) by default.
+    ///
+    /// With [`Padding::Lenient`](packet::Padding::Lenient) stray zeros are skipped instead of failing with
+    /// [`Error::CorruptFifo`], the skipped bytes are counted in [`DrainProgress::skipped`].
+    pub fn set_fifo_padding(&mut self, padding: packet::Padding) {
+        self.fifo_padding = padding;
+    }
+
+    /// The padding mode selected by [`set_fifo_padding`](Self::set_fifo_padding).
+    pub fn fifo_padding(&self) -> packet::Padding {
+        self.fifo_padding
+    }
+
+    /// Reads and decodes the FIFO until it is empty or the budget is exhausted.
+    ///
+    /// Every decoded event is passed to `f`.
+    /// `buf` is used as intermediate storage and has to be able to hold at least [`packet::MAX_EVENT_SIZE`] bytes.
+    /// Events spanning multiple chunks read into `buf` are stitched together.
+    ///
+    /// Once a transfer announced by [`BytesRemaining`](registers::BytesRemaining) is started it is always completed,
+    /// so the FIFO is left in a consistent state. The `budget` (in bytes) is only checked before starting a new transfer,
+    /// which means that up to one transfer more than the budget may be read.
+    /// After each transfer the transfer count is updated to catch data which arrived in the meantime
+    /// (see [`HostInterfaceControl::update_transfer_count`](registers::HostInterfaceControl::update_transfer_count)).
+    ///
+    /// Returns the number of bytes read.
+    pub fn drain_fifo(
+        &mut self,
+        buf: &mut [u8],
+        budget: usize,
+        f: impl FnMut(packet::Event),
+    ) -> Result<usize, Error<IF::Error>> {
+        let throttle = Throttle::unlimited().with_max_bytes(budget);
+        Ok(self.drain_fifo_throttled(buf, throttle, f)?.bytes)
+    }
+
+    /// Like [`drain_fifo`](Self::drain_fifo) but stops once the limits of `throttle` are reached.
+    ///
+    /// The limits are only checked before starting a new transfer, so a call may process up to one transfer
+    /// more than allowed. If the call stops because of the throttle, the transfer count update stays requested,
+    /// so the next call sees all data which arrived in the meantime without waiting for a new interrupt.
+    pub fn drain_fifo_throttled(
+        &mut self,
+        buf: &mut [u8],
+        throttle: Throttle,
+        mut f: impl FnMut(packet::Event),
+    ) -> Result<DrainProgress, Error<IF::Error>> {
+        if buf.len() < packet::MAX_EVENT_SIZE {
+            return Err(Error::BufferTooSmall);
+        }
+        let mut progress = DrainProgress::default();
+        let mut update_requested = false;
+        let result = loop {
+            if throttle.exhausted(&progress) {
+                break Ok(());
+            }
+            let registers::BytesRemaining(remaining) = match self.read_reg() {
+                Ok(remaining) => remaining,
+                Err(e) => break Err(e.into()),
+            };
+            if remaining == 0 {
+                progress.complete = true;
+                break Ok(());
+            }
+            if let Err(e) = self.read_transfer(buf, remaining as usize, &mut progress, &mut f) {
+                break Err(e);
+            }
+            progress.bytes += remaining as usize;
+
+            // Request an up to date BytesRemaining in case no new interrupt was asserted
+            if let Err(e) = self.update_reg(|reg: registers::HostInterfaceControl| {
+                reg.with_update_transfer_count(true)
+            }) {
+                break Err(e.into());
+            }
+            update_requested = true;
+        };
+        // The update may also have been requested by a previous throttled call
+        if progress.complete || (update_requested && result.is_err()) {
+            let control: registers::HostInterfaceControl = self.read_reg()?;
+            if control.update_transfer_count() {
+                self.write_reg(control.with_update_transfer_count(false))?;
+            }
+        }
+        result.map(|()| progress)
+    }
+
+    /// Reads the [`IntStatus`](registers::IntStatus) and drains the FIFO as selected by `policy`.
+    ///
+    /// Call this from the handler of the host interrupt. Returns the status together with the progress,
+    /// see [`drain_fifo_throttled`](Self::drain_fifo_throttled).
+    pub fn drain_on_interrupt(
+        &mut self,
+        buf: &mut [u8],
+        policy: &DrainPolicy,
+        f: impl FnMut(packet::Event),
+    ) -> Result<(registers::IntStatus, DrainProgress), Error<IF::Error>> {
+        let status: registers::IntStatus = self.read_reg()?;
+        let throttle = policy.throttle(status.cause());
+        let progress = self.drain_fifo_throttled(buf, throttle, f)?;
+        Ok((status, progress))
+    }
+
+    /// Like [`drain_fifo_throttled`](Self::drain_fifo_throttled), but captures an
+    /// [`ErrorReport`](diagnostics::ErrorReport) for every [`Error`](packet::MetaEvent::Error) meta event.
+    ///
+    /// The FIFO is read one transfer at a time. The report is captured right after the transfer containing the
+    /// meta event has been read and is passed to `f` together with the event. All other events are passed with `None`.
+    #[cfg(feature = "alloc")]
+    pub fn drain_fifo_diagnosed(
+        &mut self,
+        buf: &mut [u8],
+        throttle: Throttle,
+        mut f: impl FnMut(packet::Event, Option<&diagnostics::ErrorReport>),
+    ) -> Result<DrainProgress, Error<IF::Error>> {
+        let mut progress = DrainProgress::default();
+        let mut events = alloc::vec::Vec::new();
+        while !throttle.exhausted(&progress) {
+            let transfer = Throttle::single_transfer();
+            let step = self.drain_fifo_throttled(buf, transfer, |event| events.push(event))?;
+            progress.events += step.events;
+            progress.bytes += step.bytes;
+            progress.skipped += step.skipped;
+            for event in events.drain(..) {
+                match diagnostics::ErrorReport::error_event(&event) {
+                    Some((error, debug_state)) => {
+                        let report = diagnostics::ErrorReport::capture(self, error, debug_state)?;
+                        f(event, Some(&report));
+                    }
+                    None => f(event, None),
+                }
+            }
+            if step.complete {
+                progress.complete = true;
+                break;
+            }
+        }
+        Ok(progress)
+    }
+
+    /// Returns an iterator over the events currently in the FIFO.
+    ///
+    /// `buf` is used as intermediate storage and has to be able to hold at least [`packet::MAX_EVENT_SIZE`] bytes.
+    /// See [`events`] for more information.
+    #[cfg(feature = "alloc")]
+    pub fn events<'a>(
+        &'a mut self,
+        buf: &'a mut [u8],
+    ) -> Result<events::Events<'a, IF>, Error<IF::Error>> {
+        if buf.len() < packet::MAX_EVENT_SIZE {
+            return Err(Error::BufferTooSmall);
+        }
+        Ok(events::Events::new(self, buf))
+    }
+
+    /// Reads a complete transfer of `remaining` bytes in chunks and decodes it.
+    /// Adds the decoded events and skipped zero bytes to `progress`.
+    fn read_transfer(
+        &mut self,
+        buf: &mut [u8],
+        mut remaining: usize,
+        progress: &mut DrainProgress,
+        f: &mut impl FnMut(packet::Event),
+    ) -> Result<(), Error<IF::Error>> {
+        let mut pending = 0;
+        let armed = self.significant_motion_armed();
+        let mut fired = false;
+        while remaining > 0 {
+            let len = (buf.len() - pending).min(remaining);
+            self.bus_read(0x00, &mut buf[pending..pending + len])?;
+            remaining -= len;
+            let filled = pending + len;
+            let decoders: &[packet::CustomDecoder<core::convert::Infallible>] = &[];
+            let padding = self.fifo_padding;
+            let skipped = &mut progress.skipped;
+            let events = &mut progress.events;
+            let consumed = packet::decode_events_padded(&buf[..filled], decoders, padding, skipped, |decoded| {
+                let packet::Decoded::Event(event) = decoded;
+                *events += 1;
+                fired |= Some(event.id()) == armed;
+                if let Some((error, debug_state)) = diagnostics::ErrorReport::error_event(&event) {
+                    self.recent_errors.push(error, debug_state);
+                }
+                f(event)
+            })
+            .map_err(Error::CorruptFifo)?;
+            buf.copy_within(consumed..filled, 0);
+            pending = filled - consumed;
+        }
+        if let (Some(id), true) = (armed, fired) {
+            self.one_shot_fired(id)?;
+        }
+        Ok(())
+    }
 }
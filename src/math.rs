@@ -0,0 +1,536 @@
+//! Vector and quaternion types used by the sensor data, independent of the FIFO format.
+
+use core::ops::{Add, Div, Index, IndexMut, Mul, Sub};
+
+use crate::float;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Vector<T, const DIM: usize = 3>(pub [T; DIM]);
+
+impl<T, const DIM: usize> Vector<T, DIM> {
+    pub const fn new(elems: [T; DIM]) -> Self {
+        Self(elems)
+    }
+
+    /// Applies `f` to every element.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Vector<U, DIM> {
+        Vector(self.0.map(f))
+    }
+
+    pub fn iter(&self) -> core::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> core::slice::IterMut<'_, T> {
+        self.0.iter_mut()
+    }
+
+    pub fn elem_add<Rhs>(self, rhs: Vector<Rhs, DIM>) -> Vector<<T as Add<Rhs>>::Output, DIM>
+    where
+        T: Add<Rhs>,
+    {
+        Vector(zip_with(self.0, rhs.0, |l, r| l + r))
+    }
+
+    pub fn elem_sub<Rhs>(self, rhs: Vector<Rhs, DIM>) -> Vector<<T as Sub<Rhs>>::Output, DIM>
+    where
+        T: Sub<Rhs>,
+    {
+        Vector(zip_with(self.0, rhs.0, |l, r| l - r))
+    }
+
+    pub fn elem_mul<Rhs>(self, rhs: Vector<Rhs, DIM>) -> Vector<<T as Mul<Rhs>>::Output, DIM>
+    where
+        T: Mul<Rhs>,
+    {
+        Vector(zip_with(self.0, rhs.0, |l, r| l * r))
+    }
+
+    pub fn elem_div<Rhs>(self, rhs: Vector<Rhs, DIM>) -> Vector<<T as Div<Rhs>>::Output, DIM>
+    where
+        T: Div<Rhs>,
+    {
+        Vector(zip_with(self.0, rhs.0, |l, r| l / r))
+    }
+
+    pub fn scale<Rhs>(self, rhs: Rhs) -> Vector<<T as Mul<Rhs>>::Output, DIM>
+    where
+        T: Mul<Rhs>,
+        Rhs: Copy,
+    {
+        Vector(self.0.map(|l| l * rhs))
+    }
+
+    pub fn from<Other>(other: Vector<Other, DIM>) -> Self
+    where
+        Other: Into<T>,
+    {
+        Self(other.0.map(|x| x.into()))
+    }
+
+    pub fn change_elem<Output>(self) -> Vector<Output, DIM>
+    where
+        Output: From<T>,
+    {
+        Vector(self.0.map(|x| x.into()))
+    }
+}
+
+impl<T, const DIM: usize> Default for Vector<T, DIM>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Self(core::array::from_fn(|_| T::default()))
+    }
+}
+
+impl<T, const DIM: usize> Index<usize> for Vector<T, DIM> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.0[index]
+    }
+}
+
+impl<T, const DIM: usize> IndexMut<usize> for Vector<T, DIM> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self.0[index]
+    }
+}
+
+impl<T, const DIM: usize> IntoIterator for Vector<T, DIM> {
+    type Item = T;
+    type IntoIter = core::array::IntoIter<T, DIM>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T, const DIM: usize> IntoIterator for &'a Vector<T, DIM> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a, T, const DIM: usize> IntoIterator for &'a mut Vector<T, DIM> {
+    type Item = &'a mut T;
+    type IntoIter = core::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+/// Combines two arrays element-wise (`array_zip` is not available on stable).
+fn zip_with<A, B, C, const N: usize>(a: [A; N], b: [B; N], mut f: impl FnMut(A, B) -> C) -> [C; N] {
+    let mut b = b.into_iter();
+    a.map(|a| f(a, b.next().unwrap()))
+}
+
+impl<T> Vector<T, 3> {
+    pub fn x(&self) -> &T {
+        &self.0[0]
+    }
+
+    pub fn y(&self) -> &T {
+        &self.0[1]
+    }
+
+    pub fn z(&self) -> &T {
+        &self.0[2]
+    }
+
+    pub fn set_x(&mut self, x: T) {
+        self.0[0] = x;
+    }
+
+    pub fn set_y(&mut self, y: T) {
+        self.0[1] = y;
+    }
+
+    pub fn set_z(&mut self, z: T) {
+        self.0[2] = z;
+    }
+}
+
+impl<const DIM: usize> Vector<f32, DIM> {
+    /// The dot product of two vectors.
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.iter().zip(other).map(|(a, b)| a * b).sum()
+    }
+
+    /// The length of the vector.
+    pub fn norm(&self) -> f32 {
+        float::sqrt(self.dot(self))
+    }
+}
+
+impl Vector<f32, 3> {
+    /// The cross product of two vectors.
+    pub fn cross(&self, other: &Self) -> Self {
+        let [x1, y1, z1] = self.0;
+        let [x2, y2, z2] = other.0;
+        Vector([y1 * z2 - z1 * y2, z1 * x2 - x1 * z2, x1 * y2 - y1 * x2])
+    }
+}
+
+/// Converts a fixed-point value with `frac_bits` fractional bits (Q format) to floating point.
+pub fn from_q(raw: i16, frac_bits: u32) -> f32 {
+    raw as f32 / (1u32 << frac_bits) as f32
+}
+
+/// Converts a floating point value to a fixed-point value with `frac_bits` fractional bits (Q format).
+///
+/// Rounds to the nearest value, ties away from zero (i.e. symmetrically for negative values),
+/// and saturates at the limits of `i16`. NaN is converted to 0.
+pub fn to_q(value: f32, frac_bits: u32) -> i16 {
+    let scaled = value * (1u32 << frac_bits) as f32;
+    let rounded = if scaled >= 0.0 {
+        scaled + 0.5
+    } else {
+        scaled - 0.5
+    };
+    (rounded as i32).clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// Conversions from and to the fixed-point formats reported by the BHI.
+///
+/// See [`from_q`] and [`to_q`] for the rounding behavior.
+impl<const DIM: usize> Vector<f32, DIM> {
+    pub fn from_q(raw: Vector<i16, DIM>, frac_bits: u32) -> Self {
+        Vector(raw.0.map(|x| from_q(x, frac_bits)))
+    }
+
+    pub fn to_q(&self, frac_bits: u32) -> Vector<i16, DIM> {
+        Vector(self.0.map(|x| to_q(x, frac_bits)))
+    }
+
+    /// Converts from Q14 (e.g. the unit quaternions of the rotation vectors).
+    pub fn from_q14(raw: Vector<i16, DIM>) -> Self {
+        Self::from_q(raw, 14)
+    }
+
+    pub fn to_q14(&self) -> Vector<i16, DIM> {
+        self.to_q(14)
+    }
+
+    /// Converts from Q8.
+    pub fn from_q8(raw: Vector<i16, DIM>) -> Self {
+        Self::from_q(raw, 8)
+    }
+
+    pub fn to_q8(&self) -> Vector<i16, DIM> {
+        self.to_q(8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Quaternion<T> {
+    v: Vector<T, 3>,
+    s: T,
+}
+
+impl<T> Quaternion<T> {
+    pub const fn new(x: T, y: T, z: T, w: T) -> Self {
+        Self {
+            v: Vector([x, y, z]),
+            s: w,
+        }
+    }
+
+    pub fn x(&self) -> &T {
+        self.v.x()
+    }
+
+    pub fn y(&self) -> &T {
+        self.v.y()
+    }
+
+    pub fn z(&self) -> &T {
+        self.v.z()
+    }
+
+    pub fn w(&self) -> &T {
+        &self.s
+    }
+
+    pub fn set_x(&mut self, x: T) {
+        self.v.set_x(x);
+    }
+
+    pub fn set_y(&mut self, y: T) {
+        self.v.set_y(y);
+    }
+
+    pub fn set_z(&mut self, z: T) {
+        self.v.set_z(z);
+    }
+
+    pub fn set_w(&mut self, w: T) {
+        self.s = w;
+    }
+
+    pub fn scale<Rhs>(self, rhs: Rhs) -> Quaternion<<T as Mul<Rhs>>::Output>
+    where
+        T: Mul<Rhs>,
+        Rhs: Copy,
+    {
+        Quaternion {
+            v: self.v.scale(rhs),
+            s: self.s * rhs,
+        }
+    }
+
+    pub fn change_elem<Output>(self) -> Quaternion<Output>
+    where
+        Output: From<T>,
+    {
+        Quaternion {
+            v: self.v.change_elem(),
+            s: self.s.into(),
+        }
+    }
+}
+
+impl Quaternion<f32> {
+    /// The quaternion of no rotation.
+    pub const fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Converts from a fixed-point format, see [`from_q`].
+    pub fn from_q(raw: Quaternion<i16>, frac_bits: u32) -> Self {
+        Self {
+            v: Vector::from_q(raw.v, frac_bits),
+            s: from_q(raw.s, frac_bits),
+        }
+    }
+
+    /// Converts to a fixed-point format, see [`to_q`].
+    pub fn to_q(&self, frac_bits: u32) -> Quaternion<i16> {
+        Quaternion {
+            v: self.v.to_q(frac_bits),
+            s: to_q(self.s, frac_bits),
+        }
+    }
+
+    /// Converts from Q14, the format of the rotation vectors.
+    pub fn from_q14(raw: Quaternion<i16>) -> Self {
+        Self::from_q(raw, 14)
+    }
+
+    pub fn to_q14(&self) -> Quaternion<i16> {
+        self.to_q(14)
+    }
+
+    pub fn to_euler(self) -> Vector<f32, 3> {
+        let sinr_cosp = 2.0 * (self.w() * self.x() + self.y() * self.z());
+        let cosr_cosp = 1.0 - 2.0 * (self.x() * self.x() + self.y() * self.y());
+        let roll = float::atan2(sinr_cosp, cosr_cosp);
+
+        let sinp = 2.0 * (self.w() * self.y() - self.z() * self.x());
+        let pitch = float::asin(sinp);
+
+        let siny_cosp = 2.0 * (self.w() * self.z() + self.x() * self.y());
+        let cosy_cosp = 1.0 - 2.0 * (self.y() * self.y() + self.z() * self.z());
+
+        let yaw = float::atan2(siny_cosp, cosy_cosp);
+        Vector([roll, pitch, yaw])
+    }
+
+    /// The dot product of two quaternions.
+    pub fn dot(&self, other: &Self) -> f32 {
+        self.x() * other.x() + self.y() * other.y() + self.z() * other.z() + self.w() * other.w()
+    }
+
+    /// The length of the quaternion.
+    pub fn norm(&self) -> f32 {
+        float::sqrt(self.dot(self))
+    }
+
+    /// Scales the quaternion to unit length.
+    pub fn normalize(self) -> Self {
+        let norm = self.norm();
+        self.scale(1.0 / norm)
+    }
+
+    /// The conjugate of the quaternion, i.e. the inverse rotation for unit quaternions.
+    pub fn conjugate(self) -> Self {
+        Quaternion {
+            v: self.v.scale(-1.0),
+            s: self.s,
+        }
+    }
+
+    /// Rotates a vector by the rotation represented by this (unit) quaternion.
+    pub fn rotate(&self, vector: Vector<f32, 3>) -> Vector<f32, 3> {
+        let [x, y, z] = vector.0;
+        let rotated = *self * Quaternion::new(x, y, z, 0.0) * self.conjugate();
+        rotated.v
+    }
+
+    /// Spherical linear interpolation between two rotations.
+    ///
+    /// `t = 0.0` returns `self`, `t = 1.0` returns `other`.
+    /// Both quaternions should be normalized. The interpolation always takes the shortest path.
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut dot = self.dot(&other);
+        let other = if dot < 0.0 {
+            dot = -dot;
+            other.scale(-1.0)
+        } else {
+            other
+        };
+
+        // Fall back to linear interpolation for (almost) identical rotations to avoid dividing by zero
+        let (a, b) = if dot > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = float::acos(dot);
+            let sin_theta = float::sin(theta);
+            (
+                float::sin((1.0 - t) * theta) / sin_theta,
+                float::sin(t * theta) / sin_theta,
+            )
+        };
+
+        Quaternion {
+            v: self.v.scale(a).elem_add(other.v.scale(b)),
+            s: self.s * a + other.s * b,
+        }
+        .normalize()
+    }
+}
+
+impl Mul for Quaternion<f32> {
+    type Output = Self;
+
+    /// The Hamilton product, i.e. the rotation `rhs` followed by `self`.
+    fn mul(self, rhs: Self) -> Self {
+        let [x1, y1, z1] = self.v.0;
+        let w1 = self.s;
+        let [x2, y2, z2] = rhs.v.0;
+        let w2 = rhs.s;
+        Quaternion::new(
+            w1 * x2 + x1 * w2 + y1 * z2 - z1 * y2,
+            w1 * y2 - x1 * z2 + y1 * w2 + z1 * x2,
+            w1 * z2 + x1 * y2 - y1 * x2 + z1 * w2,
+            w1 * w2 - x1 * x2 - y1 * y2 - z1 * z2,
+        )
+    }
+}
+
+/// Smooths successive orientation samples (e.g. from the rotation vector sensors).
+///
+/// Every new sample moves the smoothed orientation towards the sample by the given factor using [`Quaternion::slerp`].
+/// This acts as a simple low-pass filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientationSmoother {
+    factor: f32,
+    current: Option<Quaternion<f32>>,
+}
+
+impl OrientationSmoother {
+    /// Creates a new smoother.
+    ///
+    /// `factor` has to be in the range `0.0..=1.0`. Smaller values result in smoother but more delayed output,
+    /// `1.0` disables smoothing.
+    pub fn new(factor: f32) -> Self {
+        Self {
+            factor: factor.clamp(0.0, 1.0),
+            current: None,
+        }
+    }
+
+    /// Adds a new sample and returns the smoothed orientation.
+    pub fn update(&mut self, sample: Quaternion<f32>) -> Quaternion<f32> {
+        let sample = sample.normalize();
+        let next = match self.current.take() {
+            Some(current) => current.slerp(sample, self.factor),
+            None => sample,
+        };
+        self.current = Some(next);
+        next
+    }
+
+    /// Returns the current smoothed orientation, if any sample was added yet.
+    pub fn current(&self) -> Option<&Quaternion<f32>> {
+        self.current.as_ref()
+    }
+
+    /// Discards the current state. The next sample will be returned unmodified.
+    pub fn reset(&mut self) {
+        self.current = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn q_format_rounding() {
+        assert_eq!(from_q(-16384, 14), -1.0);
+        assert_eq!(to_q(-1.0, 14), -16384);
+        // Ties are rounded away from zero for both signs
+        assert_eq!(to_q(0.5 / 256.0, 8), 1);
+        assert_eq!(to_q(-0.5 / 256.0, 8), -1);
+        assert_eq!(to_q(-0.4 / 256.0, 8), 0);
+        assert_eq!(to_q(2.0, 14), i16::MAX);
+        assert_eq!(to_q(-2.5, 14), i16::MIN);
+        assert_eq!(to_q(f32::NAN, 14), 0);
+
+        let v = Vector([-3, 0, 12345]);
+        assert_eq!(Vector::from_q14(v).to_q14(), v);
+        let q = Quaternion::new(-16384, 8192, -1, 3);
+        assert_eq!(Quaternion::from_q14(q).to_q14(), q);
+    }
+
+    #[test]
+    fn vector_access() {
+        const V: Vector<i16> = Vector::new([1, -2, 3]);
+        let mut v = V;
+        v[1] = 5;
+        assert_eq!(v[1], 5);
+        for x in &mut v {
+            *x *= 2;
+        }
+        let sum: i16 = v.iter().sum();
+        assert_eq!(sum, 18);
+        assert_eq!(v.map(|x| x as f32 / 2.0), Vector([1.0, 5.0, 3.0]));
+        assert_eq!(v.into_iter().max(), Some(10));
+        let zero: Vector<f32, 4> = Vector::default();
+        assert_eq!(zero, Vector([0.0; 4]));
+
+        let q = Quaternion::new(0.5, -0.5, 0.5, 0.5);
+        assert_eq!(Quaternion::identity() * q, q);
+        assert_eq!(
+            Quaternion::identity().rotate(Vector([1.0, 2.0, 3.0])),
+            Vector([1.0, 2.0, 3.0])
+        );
+    }
+
+    #[test]
+    fn slerp_halfway() {
+        let half = core::f32::consts::FRAC_1_SQRT_2;
+        let identity = Quaternion::identity();
+        let quarter_turn = Quaternion::new(0.0, 0.0, half, half);
+        let q = identity.slerp(quarter_turn, 0.5);
+        let eighth_turn = core::f32::consts::FRAC_PI_8;
+        // micromath only approximates the trigonometric functions
+        let eps = if cfg!(all(feature = "micromath", not(feature = "libm"))) {
+            1e-3
+        } else {
+            1e-6
+        };
+        assert!((q.z() - eighth_turn.sin()).abs() < eps);
+        assert!((q.w() - eighth_turn.cos()).abs() < eps);
+    }
+}
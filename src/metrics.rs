@@ -0,0 +1,102 @@
+//! Statistics of the bus usage of the driver (with the `metrics` feature).
+//!
+//! [`Bhi160`](crate::Bhi160) counts every transaction on the bus, so the bandwidth used by the application logic
+//! (e.g. polling parameters in a loop) can be measured without a protocol analyzer.
+//! The counters wrap around, use [`BusMetrics::since`] to get the usage of a period of time.
+//!
+//! ```ignore
+//! let before = bhi.metrics();
+//! bhi.drain_fifo(&mut buf, usize::MAX, |_| {})?;
+//! let usage = bhi.metrics().since(&before);
+//! println!("{} bytes in {} reads", usage.fifo_bytes, usage.fifo_reads);
+//! ```
+
+/// The address of the FIFO (Buffer Out) registers.
+const FIFO_ADDR: u8 = 0x00;
+/// The address of the Upload Data register.
+const UPLOAD_ADDR: u8 = 0x96;
+
+/// A snapshot of the bus usage, see the [module documentation](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BusMetrics {
+    /// Read transactions of registers, except for the FIFO.
+    pub register_reads: u32,
+    /// Write transactions of registers, except for the firmware upload.
+    pub register_writes: u32,
+    /// Bytes read from registers, except for the FIFO.
+    pub register_bytes_read: u32,
+    /// Bytes written to registers, except for the firmware upload.
+    pub register_bytes_written: u32,
+    /// Read transactions of the FIFO.
+    pub fifo_reads: u32,
+    /// Bytes read from the FIFO.
+    pub fifo_bytes: u32,
+    /// Bytes of firmware uploaded.
+    pub upload_bytes: u32,
+    /// Parameters read successfully.
+    pub parameter_reads: u32,
+    /// Parameters written successfully.
+    pub parameter_writes: u32,
+    /// Parameter requests rejected by the BHI, including the ones which were repeated successfully.
+    pub parameter_rejections: u32,
+    /// Reads of [`ParameterAcknowledge`](crate::registers::ParameterAcknowledge) while waiting for a parameter.
+    pub acknowledge_polls: u32,
+}
+
+impl BusMetrics {
+    /// The usage since the snapshot `earlier` was taken.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            register_reads: self.register_reads.wrapping_sub(earlier.register_reads),
+            register_writes: self.register_writes.wrapping_sub(earlier.register_writes),
+            register_bytes_read: self
+                .register_bytes_read
+                .wrapping_sub(earlier.register_bytes_read),
+            register_bytes_written: self
+                .register_bytes_written
+                .wrapping_sub(earlier.register_bytes_written),
+            fifo_reads: self.fifo_reads.wrapping_sub(earlier.fifo_reads),
+            fifo_bytes: self.fifo_bytes.wrapping_sub(earlier.fifo_bytes),
+            upload_bytes: self.upload_bytes.wrapping_sub(earlier.upload_bytes),
+            parameter_reads: self.parameter_reads.wrapping_sub(earlier.parameter_reads),
+            parameter_writes: self.parameter_writes.wrapping_sub(earlier.parameter_writes),
+            parameter_rejections: self
+                .parameter_rejections
+                .wrapping_sub(earlier.parameter_rejections),
+            acknowledge_polls: self
+                .acknowledge_polls
+                .wrapping_sub(earlier.acknowledge_polls),
+        }
+    }
+
+    /// All bytes transferred on the bus, excluding addressing.
+    pub fn total_bytes(&self) -> u32 {
+        self.register_bytes_read
+            .wrapping_add(self.register_bytes_written)
+            .wrapping_add(self.fifo_bytes)
+            .wrapping_add(self.upload_bytes)
+    }
+
+    pub(crate) fn bump(counter: &mut u32) {
+        *counter = counter.wrapping_add(1);
+    }
+
+    pub(crate) fn record_read(&mut self, addr: u8, len: usize) {
+        if addr == FIFO_ADDR {
+            self.fifo_reads = self.fifo_reads.wrapping_add(1);
+            self.fifo_bytes = self.fifo_bytes.wrapping_add(len as u32);
+        } else {
+            self.register_reads = self.register_reads.wrapping_add(1);
+            self.register_bytes_read = self.register_bytes_read.wrapping_add(len as u32);
+        }
+    }
+
+    pub(crate) fn record_write(&mut self, addr: u8, len: usize) {
+        if addr == UPLOAD_ADDR {
+            self.upload_bytes = self.upload_bytes.wrapping_add(len as u32);
+        } else {
+            self.register_writes = self.register_writes.wrapping_add(1);
+            self.register_bytes_written = self.register_bytes_written.wrapping_add(len as u32);
+        }
+    }
+}
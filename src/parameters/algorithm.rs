@@ -0,0 +1,110 @@
+//! Access to the [Algorithm Parameter Page](ParameterPage::Algorithm).
+//!
+//! The BHI copies the algorithm data structures when the page is selected and copies them back once the host
+//! selects [`Page0`](ParameterPage::Page0) as an acknowledgment. Forgetting the acknowledgment leaves the changes
+//! without effect, so all accesses go through an [`AlgorithmPageGuard`] which acknowledges them when it is dropped.
+//!
+//! ```ignore
+//! let mut page = bhi.algorithm_page();
+//! let mut buf = [0; 8];
+//! page.read_param_raw(param, &mut buf)?;
+//! page.write_param_raw(param, &buf)?;
+//! page.commit()?;
+//! ```
+
+use crate::{interface::Interface, Bhi160, Error};
+
+use super::{Parameter, ParameterPage};
+
+/// Grants access to the parameters of the Algorithm page, see the [module documentation](self).
+///
+/// Obtained by [`Bhi160::algorithm_page`]. Use [`Self::commit`] to handle errors of the acknowledgment,
+/// otherwise it is written (ignoring errors) when the guard is dropped.
+pub struct AlgorithmPageGuard<'a, IF>
+where
+    IF: Interface,
+{
+    bhi: &'a mut Bhi160<IF>,
+    acknowledged: bool,
+}
+
+impl<'a, IF> AlgorithmPageGuard<'a, IF>
+where
+    IF: Interface,
+{
+    pub(crate) fn new(bhi: &'a mut Bhi160<IF>) -> Self {
+        Self {
+            bhi,
+            acknowledged: false,
+        }
+    }
+
+    /// Reads a parameter of the Algorithm page.
+    #[cfg(feature = "nightly")]
+    pub fn read_param<T>(&mut self) -> Result<T, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; T::SIZE]>,
+    {
+        debug_assert!(T::PAGE == ParameterPage::Algorithm);
+        self.bhi.read_param()
+    }
+
+    /// Reads a parameter of the Algorithm page.
+    #[cfg(not(feature = "nightly"))]
+    pub fn read_param<T, const N: usize>(&mut self) -> Result<T, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; N]>,
+    {
+        debug_assert!(T::PAGE == ParameterPage::Algorithm);
+        self.bhi.read_param()
+    }
+
+    /// Writes a parameter of the Algorithm page.
+    #[cfg(feature = "nightly")]
+    pub fn write_param<T>(&mut self, param: T) -> Result<(), Error<IF::Error>>
+    where
+        T: Parameter + Into<[u8; T::SIZE]>,
+    {
+        debug_assert!(T::PAGE == ParameterPage::Algorithm);
+        self.bhi.write_param(param)
+    }
+
+    /// Writes a parameter of the Algorithm page.
+    #[cfg(not(feature = "nightly"))]
+    pub fn write_param<T, const N: usize>(&mut self, param: T) -> Result<(), Error<IF::Error>>
+    where
+        T: Parameter + Into<[u8; N]>,
+    {
+        debug_assert!(T::PAGE == ParameterPage::Algorithm);
+        self.bhi.write_param(param)
+    }
+
+    /// Reads the raw bytes of a parameter of the Algorithm page, see [`Bhi160::read_param_raw`].
+    pub fn read_param_raw(&mut self, param: u8, buf: &mut [u8]) -> Result<(), Error<IF::Error>> {
+        self.bhi
+            .read_param_raw(ParameterPage::Algorithm, param, buf)
+    }
+
+    /// Writes the raw bytes of a parameter of the Algorithm page, see [`Bhi160::write_param_raw`].
+    pub fn write_param_raw(&mut self, param: u8, data: &[u8]) -> Result<(), Error<IF::Error>> {
+        self.bhi
+            .write_param_raw(ParameterPage::Algorithm, param, data)
+    }
+
+    /// Selects [`Page0`](ParameterPage::Page0) to acknowledge the accesses.
+    pub fn commit(mut self) -> Result<(), IF::Error> {
+        self.acknowledged = true;
+        self.bhi.select_page(ParameterPage::Page0, 0)
+    }
+}
+
+impl<'a, IF> Drop for AlgorithmPageGuard<'a, IF>
+where
+    IF: Interface,
+{
+    fn drop(&mut self) {
+        if !self.acknowledged {
+            let _ = self.bhi.select_page(ParameterPage::Page0, 0);
+        }
+    }
+}
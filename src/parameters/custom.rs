@@ -0,0 +1,171 @@
+//! Access to the custom parameter pages (12-14).
+//!
+//! The content of the custom pages depends on the firmware. The undocumented pages 4-11 and 15 are treated the same way.
+//! Instead of defining a type for every parameter, the parameters can be described at runtime
+//! by registering a [`ParamDesc`] with a [`CustomPage`]. All accesses are checked against the registered descriptors.
+//!
+//! ```ignore
+//! let mut page = CustomPage::<4>::new(ParameterPage::Custom12)?;
+//! page.register(ParamDesc::new(1, 4, Direction::ReadWrite))?;
+//! page.write(&mut bhi, 1, &[1, 2, 3, 4])?;
+//! let mut buf = [0; 4];
+//! page.read(&mut bhi, 1, &mut buf)?;
+//! ```
+
+use super::ParameterPage;
+use crate::{interface::Interface, Bhi160, Error};
+
+/// Whether a parameter can be read and/or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Direction {
+    pub fn readable(&self) -> bool {
+        matches!(self, Self::Read | Self::ReadWrite)
+    }
+
+    pub fn writable(&self) -> bool {
+        matches!(self, Self::Write | Self::ReadWrite)
+    }
+}
+
+/// Describes a parameter of a custom page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParamDesc {
+    /// The parameter number (1-127).
+    pub param: u8,
+    /// The size in bytes. At most 16 for readable and at most 8 for writable parameters.
+    pub size: u8,
+    pub direction: Direction,
+}
+
+impl ParamDesc {
+    pub const fn new(param: u8, size: u8, direction: Direction) -> Self {
+        Self {
+            param,
+            size,
+            direction,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        let max_size = if self.direction.writable() { 8 } else { 16 };
+        (1..=127).contains(&self.param) && (1..=max_size).contains(&self.size)
+    }
+}
+
+/// Errors that can occur when setting up a [`CustomPage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CustomPageError {
+    /// The page is one of the pages documented in the datasheet, not a custom one.
+    NotCustom,
+    /// The descriptor has an invalid parameter number or size.
+    InvalidDescriptor,
+    /// A descriptor for the parameter is already registered.
+    Duplicate,
+    /// No more descriptors can be registered.
+    Full,
+}
+
+/// A custom parameter page holding up to `N` registered parameters.
+#[derive(Debug, Clone)]
+pub struct CustomPage<const N: usize> {
+    page: ParameterPage,
+    params: [Option<ParamDesc>; N],
+}
+
+impl<const N: usize> CustomPage<N> {
+    /// Creates a page without any registered parameters.
+    pub fn new(page: ParameterPage) -> Result<Self, CustomPageError> {
+        if page.is_firmware_specific() {
+            Ok(Self {
+                page,
+                params: [None; N],
+            })
+        } else {
+            Err(CustomPageError::NotCustom)
+        }
+    }
+
+    pub fn page(&self) -> ParameterPage {
+        self.page
+    }
+
+    /// Registers a parameter.
+    pub fn register(&mut self, desc: ParamDesc) -> Result<(), CustomPageError> {
+        if !desc.is_valid() {
+            return Err(CustomPageError::InvalidDescriptor);
+        }
+        if self.get(desc.param).is_some() {
+            return Err(CustomPageError::Duplicate);
+        }
+        let slot = self
+            .params
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(CustomPageError::Full)?;
+        *slot = Some(desc);
+        Ok(())
+    }
+
+    /// Returns the descriptor of a registered parameter.
+    pub fn get(&self, param: u8) -> Option<&ParamDesc> {
+        self.params
+            .iter()
+            .flatten()
+            .find(|desc| desc.param == param)
+    }
+
+    /// Iterates over all registered parameters.
+    pub fn iter(&self) -> impl Iterator<Item = &ParamDesc> {
+        self.params.iter().flatten()
+    }
+
+    /// Reads a registered parameter into `buf` and returns the filled part of `buf`.
+    ///
+    /// Returns [`Error::Unsupported`] if the parameter is not registered as readable
+    /// and [`Error::BufferTooSmall`] if `buf` cannot hold the parameter.
+    pub fn read<'a, IF>(
+        &self,
+        bhi: &mut Bhi160<IF>,
+        param: u8,
+        buf: &'a mut [u8],
+    ) -> Result<&'a [u8], Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let desc = self
+            .get(param)
+            .filter(|desc| desc.direction.readable())
+            .ok_or(Error::Unsupported)?;
+        let buf = buf
+            .get_mut(..desc.size as usize)
+            .ok_or(Error::BufferTooSmall)?;
+        bhi.read_param_raw(self.page, param, buf)?;
+        Ok(buf)
+    }
+
+    /// Writes a registered parameter.
+    ///
+    /// Returns [`Error::Unsupported`] if the parameter is not registered as writable or `data` does not match its size.
+    pub fn write<IF>(
+        &self,
+        bhi: &mut Bhi160<IF>,
+        param: u8,
+        data: &[u8],
+    ) -> Result<(), Error<IF::Error>>
+    where
+        IF: Interface,
+    {
+        let desc = self
+            .get(param)
+            .filter(|desc| desc.direction.writable() && desc.size as usize == data.len())
+            .ok_or(Error::Unsupported)?;
+        bhi.write_param_raw(self.page, desc.param, data)?;
+        Ok(())
+    }
+}
@@ -2,11 +2,14 @@
 //! 
 //! Parameters can be read-only (e.g. sensor information) or read-write (e.g. sensor configuration).
 
-pub mod system;
+pub mod algorithm;
+pub mod custom;
 pub mod sensors;
+pub mod system;
 
 /// Parameters are grouped in pages.
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[bits = 4]
 pub enum ParameterPage {
     /// The host must write this value, after finishing an access on the
@@ -21,14 +24,42 @@ pub enum ParameterPage {
     /// algorithm data structures that may be modified using Parameter I/O to this page.
     /// 
     /// After writing to this page you should go back to [`Self::Page0`] for the changes to take effect.
+    /// [`Bhi160::algorithm_page`](crate::Bhi160::algorithm_page) takes care of this.
     Algorithm,
     /// This page contains information and configuration parameters for individual sensors.
     /// 
     /// See the [`sensors`] module for more information.
     Sensors,
-    Custom12 = 12,
-    Custom13 = 13,
-    Custom14 = 14,
+    /// Pages not documented in the datasheet, which may be used by some firmwares.
+    ///
+    /// Like the custom pages, they can be accessed with a [`CustomPage`](custom::CustomPage).
+    Page4,
+    Page5,
+    Page6,
+    Page7,
+    Page8,
+    Page9,
+    Page10,
+    Page11,
+    /// Custom pages whose content depends on the firmware.
+    ///
+    /// See the [`custom`] module for more information.
+    Custom12,
+    Custom13,
+    Custom14,
+    /// Not documented in the datasheet.
+    Page15,
+}
+
+impl ParameterPage {
+    /// Returns `true` for the pages whose content depends on the firmware, i.e. all pages except
+    /// [`Page0`](Self::Page0), [`System`](Self::System), [`Algorithm`](Self::Algorithm) and [`Sensors`](Self::Sensors).
+    pub fn is_firmware_specific(&self) -> bool {
+        !matches!(
+            self,
+            Self::Page0 | Self::System | Self::Algorithm | Self::Sensors
+        )
+    }
 }
 
 pub trait Parameter {
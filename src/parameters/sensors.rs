@@ -4,7 +4,8 @@ use modular_bitfield::{bitfield, BitfieldSpecifier};
 use super::{impl_param, Parameter};
 
 /// This represents the type of a sensor.
-#[derive(Debug, Clone, Copy, BitfieldSpecifier, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, BitfieldSpecifier, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[bits = 8]
 pub enum SensorId {
     None,
@@ -85,13 +86,404 @@ pub enum SensorId {
     MetaEventWakeup = 248,
 }
 
+impl SensorId {
+    /// Converts a raw sensor type to the corresponding [`SensorId`].
+    ///
+    /// Returns `None` if the value does not denote a known sensor type.
+    pub(crate) const fn from_u8(id: u8) -> Option<Self> {
+        use SensorId::*;
+        match id {
+            0 => Some(None),
+            1 => Some(Accelerometer),
+            2 => Some(GeomagneticField),
+            3 => Some(Orientation),
+            4 => Some(Gyroscope),
+            5 => Some(Light),
+            6 => Some(Pressure),
+            7 => Some(Temperature),
+            8 => Some(Proximity),
+            9 => Some(Gravity),
+            10 => Some(LinearAcceleration),
+            11 => Some(RotationVector),
+            12 => Some(Humidity),
+            13 => Some(AmbientTemperature),
+            14 => Some(MagneticFieldUncalibrated),
+            15 => Some(GameRotationVector),
+            16 => Some(GyroscopeUncalibrated),
+            17 => Some(SignificantMotion),
+            18 => Some(StepDetector),
+            19 => Some(StepCounter),
+            20 => Some(GeomagneticRotationVector),
+            21 => Some(HeartRate),
+            22 => Some(TiltDetector),
+            23 => Some(WakeGesture),
+            24 => Some(GlanceGesture),
+            25 => Some(PickUpGesture),
+            31 => Some(ActivityRecognition),
+            33 => Some(AccelerometerWakeup),
+            34 => Some(GeomagneticFieldWakeup),
+            35 => Some(OrientationWakeup),
+            36 => Some(GyroscopeWakeup),
+            37 => Some(LightWakeup),
+            38 => Some(PressureWakeup),
+            39 => Some(TemperatureWakeup),
+            40 => Some(ProximityWakeup),
+            41 => Some(GravityWakeup),
+            42 => Some(LinearAccelerationWakeup),
+            43 => Some(RotationVectorWakeup),
+            44 => Some(HumidityWakeup),
+            45 => Some(AmbientTemperatureWakeup),
+            46 => Some(MagneticFieldUncalibratedWakeup),
+            47 => Some(GameRotationVectorWakeup),
+            48 => Some(GyroscopeUncalibratedWakeup),
+            49 => Some(SignificantMotionWakeup),
+            50 => Some(StepDetectorWakeup),
+            51 => Some(StepCounterWakeup),
+            52 => Some(GeomagneticRotationVectorWakeup),
+            53 => Some(HeartRateWakeup),
+            54 => Some(TiltDetectorWakeup),
+            55 => Some(WakeGestureWakeup),
+            56 => Some(GlanceGestureWakeup),
+            57 => Some(PickUpGestureWakeup),
+            63 => Some(ActivityRecognitionWakeup),
+            245 => Some(Debug),
+            246 => Some(TimestampLswWakeup),
+            247 => Some(TimestampMswWakeup),
+            248 => Some(MetaEventWakeup),
+            249 => Some(RawGyro),
+            250 => Some(RawMag),
+            251 => Some(RawAccel),
+            252 => Some(TimestampLsw),
+            253 => Some(TimestampMsw),
+            254 => Some(MetaEvent),
+            _ => Option::None,
+        }
+    }
+
+    /// The human readable name of the sensor.
+    pub const fn name(self) -> &'static str {
+        use SensorId::*;
+        match self {
+            None => "None",
+            RotationVector => "Rotation Vector",
+            RotationVectorWakeup => "Rotation Vector Wakeup",
+            GameRotationVector => "Game Rotation Vector",
+            GameRotationVectorWakeup => "Game Rotation Vector Wakeup",
+            GeomagneticRotationVector => "Geomagnetic Rotation Vector",
+            GeomagneticRotationVectorWakeup => "Geomagnetic Rotation Vector Wakeup",
+            Accelerometer => "Accelerometer",
+            AccelerometerWakeup => "Accelerometer Wakeup",
+            GeomagneticField => "Geomagnetic Field",
+            GeomagneticFieldWakeup => "Geomagnetic Field Wakeup",
+            Orientation => "Orientation",
+            OrientationWakeup => "Orientation Wakeup",
+            Gyroscope => "Gyroscope",
+            GyroscopeWakeup => "Gyroscope Wakeup",
+            Gravity => "Gravity",
+            GravityWakeup => "Gravity Wakeup",
+            LinearAcceleration => "Linear Acceleration",
+            LinearAccelerationWakeup => "Linear Acceleration Wakeup",
+            Light => "Light",
+            LightWakeup => "Light Wakeup",
+            Proximity => "Proximity",
+            ProximityWakeup => "Proximity Wakeup",
+            Humidity => "Humidity",
+            HumidityWakeup => "Humidity Wakeup",
+            StepCounter => "Step Counter",
+            StepCounterWakeup => "Step Counter Wakeup",
+            Temperature => "Temperature",
+            TemperatureWakeup => "Temperature Wakeup",
+            AmbientTemperature => "Ambient Temperature",
+            AmbientTemperatureWakeup => "Ambient Temperature Wakeup",
+            Pressure => "Pressure",
+            PressureWakeup => "Pressure Wakeup",
+            SignificantMotion => "Significant Motion",
+            SignificantMotionWakeup => "Significant Motion Wakeup",
+            StepDetector => "Step Detector",
+            StepDetectorWakeup => "Step Detector Wakeup",
+            TiltDetector => "Tilt Detector",
+            TiltDetectorWakeup => "Tilt Detector Wakeup",
+            WakeGesture => "Wake Gesture",
+            WakeGestureWakeup => "Wake Gesture Wakeup",
+            GlanceGesture => "Glance Gesture",
+            GlanceGestureWakeup => "Glance Gesture Wakeup",
+            PickUpGesture => "Pick Up Gesture",
+            PickUpGestureWakeup => "Pick Up Gesture Wakeup",
+            MagneticFieldUncalibrated => "Magnetic Field Uncalibrated",
+            MagneticFieldUncalibratedWakeup => "Magnetic Field Uncalibrated Wakeup",
+            GyroscopeUncalibrated => "Gyroscope Uncalibrated",
+            GyroscopeUncalibratedWakeup => "Gyroscope Uncalibrated Wakeup",
+            HeartRate => "Heart Rate",
+            HeartRateWakeup => "Heart Rate Wakeup",
+            ActivityRecognition => "Activity Recognition",
+            ActivityRecognitionWakeup => "Activity Recognition Wakeup",
+            Debug => "Debug",
+            RawAccel => "Raw Accelerometer",
+            RawMag => "Raw Magnetometer",
+            RawGyro => "Raw Gyroscope",
+            TimestampLsw => "Timestamp LSW",
+            TimestampLswWakeup => "Timestamp LSW Wakeup",
+            TimestampMsw => "Timestamp MSW",
+            TimestampMswWakeup => "Timestamp MSW Wakeup",
+            MetaEvent => "Meta Event",
+            MetaEventWakeup => "Meta Event Wakeup",
+        }
+    }
+
+    /// The unit of the values reported by this sensor once they are scaled.
+    ///
+    /// Sensors that report unitless values (e.g. quaternions) or plain events return an empty string.
+    pub const fn unit(self) -> &'static str {
+        use SensorId::*;
+        match self {
+            Accelerometer
+            | AccelerometerWakeup
+            | Gravity
+            | GravityWakeup
+            | LinearAcceleration
+            | LinearAccelerationWakeup => "m/s²",
+            GeomagneticField
+            | GeomagneticFieldWakeup
+            | MagneticFieldUncalibrated
+            | MagneticFieldUncalibratedWakeup => "µT",
+            Orientation | OrientationWakeup => "°",
+            Gyroscope | GyroscopeWakeup | GyroscopeUncalibrated | GyroscopeUncalibratedWakeup => {
+                "rad/s"
+            }
+            Light | LightWakeup => "lx",
+            Proximity | ProximityWakeup => "cm",
+            Humidity | HumidityWakeup => "%RH",
+            StepCounter | StepCounterWakeup => "steps",
+            Temperature | TemperatureWakeup | AmbientTemperature | AmbientTemperatureWakeup => "°C",
+            Pressure | PressureWakeup => "Pa",
+            HeartRate | HeartRateWakeup => "bpm",
+            RawAccel | RawMag | RawGyro => "LSB",
+            TimestampLsw | TimestampLswWakeup | TimestampMsw | TimestampMswWakeup => "ticks",
+            _ => "",
+        }
+    }
+
+    /// The number of bytes an event of this sensor occupies in the FIFO (including the Sensor Type).
+    pub const fn event_size(self) -> usize {
+        use SensorId::*;
+        match self {
+            None => 1,
+            RotationVector
+            | RotationVectorWakeup
+            | GameRotationVector
+            | GameRotationVectorWakeup
+            | GeomagneticRotationVector
+            | GeomagneticRotationVectorWakeup => 11,
+            Accelerometer
+            | AccelerometerWakeup
+            | GeomagneticField
+            | GeomagneticFieldWakeup
+            | Orientation
+            | OrientationWakeup
+            | Gyroscope
+            | GyroscopeWakeup
+            | Gravity
+            | GravityWakeup
+            | LinearAcceleration
+            | LinearAccelerationWakeup => 8,
+            Pressure | PressureWakeup => 4,
+            SignificantMotion
+            | SignificantMotionWakeup
+            | StepDetector
+            | StepDetectorWakeup
+            | TiltDetector
+            | TiltDetectorWakeup
+            | WakeGesture
+            | WakeGestureWakeup
+            | GlanceGesture
+            | GlanceGestureWakeup
+            | PickUpGesture
+            | PickUpGestureWakeup
+            | HeartRate
+            | HeartRateWakeup => 2,
+            MagneticFieldUncalibrated
+            | MagneticFieldUncalibratedWakeup
+            | GyroscopeUncalibrated
+            | GyroscopeUncalibratedWakeup
+            | Debug => 14,
+            RawAccel | RawMag | RawGyro => 17,
+            MetaEvent | MetaEventWakeup => 4,
+            _ => 3,
+        }
+    }
+
+    /// Returns the virtual sensor this id belongs to.
+    ///
+    /// Returns `None` for the ids which are no virtual sensors (e.g. the timestamp and meta events).
+    pub const fn kind(self) -> Option<SensorKind> {
+        use SensorId::*;
+        match self {
+            Accelerometer | AccelerometerWakeup => Some(SensorKind::Accelerometer),
+            GeomagneticField | GeomagneticFieldWakeup => Some(SensorKind::GeomagneticField),
+            Orientation | OrientationWakeup => Some(SensorKind::Orientation),
+            Gyroscope | GyroscopeWakeup => Some(SensorKind::Gyroscope),
+            Light | LightWakeup => Some(SensorKind::Light),
+            Pressure | PressureWakeup => Some(SensorKind::Pressure),
+            Temperature | TemperatureWakeup => Some(SensorKind::Temperature),
+            Proximity | ProximityWakeup => Some(SensorKind::Proximity),
+            Gravity | GravityWakeup => Some(SensorKind::Gravity),
+            LinearAcceleration | LinearAccelerationWakeup => Some(SensorKind::LinearAcceleration),
+            RotationVector | RotationVectorWakeup => Some(SensorKind::RotationVector),
+            Humidity | HumidityWakeup => Some(SensorKind::Humidity),
+            AmbientTemperature | AmbientTemperatureWakeup => Some(SensorKind::AmbientTemperature),
+            MagneticFieldUncalibrated | MagneticFieldUncalibratedWakeup => {
+                Some(SensorKind::MagneticFieldUncalibrated)
+            }
+            GameRotationVector | GameRotationVectorWakeup => Some(SensorKind::GameRotationVector),
+            GyroscopeUncalibrated | GyroscopeUncalibratedWakeup => {
+                Some(SensorKind::GyroscopeUncalibrated)
+            }
+            SignificantMotion | SignificantMotionWakeup => Some(SensorKind::SignificantMotion),
+            StepDetector | StepDetectorWakeup => Some(SensorKind::StepDetector),
+            StepCounter | StepCounterWakeup => Some(SensorKind::StepCounter),
+            GeomagneticRotationVector | GeomagneticRotationVectorWakeup => {
+                Some(SensorKind::GeomagneticRotationVector)
+            }
+            HeartRate | HeartRateWakeup => Some(SensorKind::HeartRate),
+            TiltDetector | TiltDetectorWakeup => Some(SensorKind::TiltDetector),
+            WakeGesture | WakeGestureWakeup => Some(SensorKind::WakeGesture),
+            GlanceGesture | GlanceGestureWakeup => Some(SensorKind::GlanceGesture),
+            PickUpGesture | PickUpGestureWakeup => Some(SensorKind::PickUpGesture),
+            ActivityRecognition | ActivityRecognitionWakeup => {
+                Some(SensorKind::ActivityRecognition)
+            }
+            _ => Option::None,
+        }
+    }
+
+    /// The raw sensor type of the non-wakeup variant of this sensor.
+    ///
+    /// Useful to index tables which are shared by the wakeup and non-wakeup variant of a sensor.
+    pub const fn base_id(self) -> u8 {
+        self.non_wakeup_variant() as u8
+    }
+
+    /// Returns `true` if events of this sensor are reported to the wakeup FIFO.
+    pub const fn is_wakeup(self) -> bool {
+        matches!(self as u8, 33..=63 | 246..=248)
+    }
+
+    /// Returns the wakeup variant of this sensor.
+    ///
+    /// Wakeup sensors return themselves.
+    /// Returns `None` if there is no wakeup variant (e.g. for [`Self::Debug`] or the raw sensors).
+    pub const fn wakeup_variant(self) -> Option<Self> {
+        match self as u8 {
+            1..=31 => Self::from_u8(self as u8 + 32),
+            252..=254 => Self::from_u8(self as u8 - 6),
+            _ if self.is_wakeup() => Some(self),
+            _ => Option::None,
+        }
+    }
+
+    /// Returns the non-wakeup variant of this sensor.
+    ///
+    /// Non-wakeup sensors return themselves.
+    pub const fn non_wakeup_variant(self) -> Self {
+        match self as u8 {
+            33..=63 => match Self::from_u8(self as u8 - 32) {
+                Some(id) => id,
+                Option::None => self,
+            },
+            246..=248 => match Self::from_u8(self as u8 + 6) {
+                Some(id) => id,
+                Option::None => self,
+            },
+            _ => self,
+        }
+    }
+}
+
+/// A virtual sensor independent of the FIFO it reports to.
+///
+/// Every virtual sensor exists as a non-wakeup and a wakeup variant with separate [`SensorId`]s.
+/// Use [`Self::id`] to pick the variant, e.g. to configure a sensor depending on a runtime flag,
+/// and [`SensorId::kind`] to handle events of both variants alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum SensorKind {
+    Accelerometer = 1,
+    GeomagneticField = 2,
+    Orientation = 3,
+    Gyroscope = 4,
+    Light = 5,
+    Pressure = 6,
+    Temperature = 7,
+    Proximity = 8,
+    Gravity = 9,
+    LinearAcceleration = 10,
+    RotationVector = 11,
+    Humidity = 12,
+    AmbientTemperature = 13,
+    MagneticFieldUncalibrated = 14,
+    GameRotationVector = 15,
+    GyroscopeUncalibrated = 16,
+    SignificantMotion = 17,
+    StepDetector = 18,
+    StepCounter = 19,
+    GeomagneticRotationVector = 20,
+    HeartRate = 21,
+    TiltDetector = 22,
+    WakeGesture = 23,
+    GlanceGesture = 24,
+    PickUpGesture = 25,
+    ActivityRecognition = 31,
+}
+
+impl SensorKind {
+    /// The orientation sensors from the most to the least accurate, for use with
+    /// [`Bhi160::enable_best_of`](crate::Bhi160::enable_best_of).
+    pub const ORIENTATION_FALLBACK: [SensorKind; 3] = [
+        SensorKind::RotationVector,
+        SensorKind::GameRotationVector,
+        SensorKind::Orientation,
+    ];
+
+    /// Returns the [`SensorId`] of the wakeup or non-wakeup variant of this sensor.
+    pub const fn id(self, wakeup: bool) -> SensorId {
+        let id = if wakeup { self as u8 + 32 } else { self as u8 };
+        match SensorId::from_u8(id) {
+            Some(id) => id,
+            None => unreachable!(),
+        }
+    }
+}
+
+/// The error returned when converting a raw sensor type which does not denote a known [`SensorId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownSensorId(pub u8);
+
+impl TryFrom<u8> for SensorId {
+    type Error = UnknownSensorId;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        Self::from_u8(id).ok_or(UnknownSensorId(id))
+    }
+}
+
+impl From<SensorId> for u8 {
+    fn from(id: SensorId) -> Self {
+        id as u8
+    }
+}
+
+/// The bit of a Sensor Type which distinguishes the wakeup from the non-wakeup variant of a virtual sensor.
+const WAKEUP_BIT: u8 = 32;
+
 /// A shared structure for all Sensor Information parameters.
 #[bitfield]
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
 pub struct SensorInfo {
     /// The id of the sensor this data belongs to.
     /// This is a defensive programming measure as this id should match the requested id.
-    /// _NOTE:_ bit 4 of Sensor Type is 1 for Non-Wakeup sensors
+    /// _NOTE:_ the wakeup bit (32) of the Sensor Type is not reported consistently by all firmwares,
+    /// so decoding the information of a wakeup sensor may yield the non-wakeup id (or fail for unknown values).
+    /// Use [`Bhi160::sensor_info`](crate::Bhi160::sensor_info) which handles this.
     #[bits = 8]
     pub sensor_type: SensorId,
     /// Id of the driver.
@@ -120,12 +512,108 @@ pub struct SensorInfo {
     pub min_rate: u8,
 }
 
+impl SensorInfo {
+    /// The raw Sensor Type as reported by the BHI.
+    pub fn sensor_type_raw(&self) -> u8 {
+        self.into_bytes()[0]
+    }
+
+    /// Returns `true` if the reported Sensor Type denotes `id` or its wakeup/non-wakeup counterpart.
+    pub fn matches(&self, id: SensorId) -> bool {
+        let raw = self.sensor_type_raw();
+        raw == id as u8 || (matches!(raw, 1..=63) && raw & !WAKEUP_BIT == id as u8 & !WAKEUP_BIT)
+    }
+
+    /// The driver reported by [`Self::driver_id`], i.e. which physical part provides this sensor.
+    pub fn driver(&self) -> DriverId {
+        self.driver_id().into()
+    }
+}
+
+/// Identifies the driver (and thereby the physical part) behind a sensor.
+///
+/// See [`SensorInfo::driver_id`]. Ids which are not known to this crate are reported as [`DriverId::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriverId {
+    /// Bosch Sensortec BMI160 accelerometer
+    Bmi160Accelerometer,
+    /// Bosch Sensortec BMI160 gyroscope
+    Bmi160Gyroscope,
+    /// Bosch Sensortec BMM150 magnetometer
+    Bmm150,
+    /// Bosch Sensortec BMP280 barometer
+    Bmp280,
+    /// Asahi Kasei AK09911 magnetometer
+    Ak09911,
+    /// Asahi Kasei AK09912 magnetometer
+    Ak09912,
+    /// Asahi Kasei AK09915 magnetometer
+    Ak09915,
+    /// Yamaha YAS532 magnetometer
+    Yas532,
+    /// Bosch Sensortec BSX fusion library (virtual sensors)
+    Bsx,
+    Unknown(u8),
+}
+
+impl From<u8> for DriverId {
+    fn from(id: u8) -> Self {
+        match id {
+            1 => Self::Bmi160Accelerometer,
+            2 => Self::Bmi160Gyroscope,
+            3 => Self::Bmm150,
+            4 => Self::Bmp280,
+            5 => Self::Ak09911,
+            6 => Self::Ak09912,
+            7 => Self::Ak09915,
+            8 => Self::Yas532,
+            0x80 => Self::Bsx,
+            x => Self::Unknown(x),
+        }
+    }
+}
+
+impl DriverId {
+    /// The name of the part (or `"Unknown"`).
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bmi160Accelerometer => "BMI160 Accelerometer",
+            Self::Bmi160Gyroscope => "BMI160 Gyroscope",
+            Self::Bmm150 => "BMM150",
+            Self::Bmp280 => "BMP280",
+            Self::Ak09911 => "AK09911",
+            Self::Ak09912 => "AK09912",
+            Self::Ak09915 => "AK09915",
+            Self::Yas532 => "YAS532",
+            Self::Bsx => "BSX",
+            Self::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl From<DriverId> for u8 {
+    fn from(id: DriverId) -> Self {
+        match id {
+            DriverId::Bmi160Accelerometer => 1,
+            DriverId::Bmi160Gyroscope => 2,
+            DriverId::Bmm150 => 3,
+            DriverId::Bmp280 => 4,
+            DriverId::Ak09911 => 5,
+            DriverId::Ak09912 => 6,
+            DriverId::Ak09915 => 7,
+            DriverId::Yas532 => 8,
+            DriverId::Bsx => 0x80,
+            DriverId::Unknown(x) => x,
+        }
+    }
+}
+
 /// A shared structure for all Sensor Configuration parameters.
 /// 
 /// Writing a Sensor Configuration parameter requests a change to the sensor's state (e.g. activating, setting dynamic range).
 /// Reading back this parameter returns the actual state.
 #[bitfield]
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
 pub struct SensorConfig {
     /// The actual sample_rate in Hz.
     /// Writing a non-zero value activates the sensor.
@@ -154,12 +642,12 @@ pub struct SensorConfig {
 macro_rules! impl_sensor {
     ($info:ident, $config:ident, $id:expr) => {
         #[bitfield]
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub struct $info(pub SensorInfo);
         impl_param!($info, ParameterPage::Sensors, $id, 16, ReadOnly);
 
         #[bitfield]
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
         pub struct $config(pub SensorConfig);
         impl_param!($config, ParameterPage::Sensors, $id + 64, 8, ReadWrite);
     };
@@ -1,3 +1,5 @@
+use core::fmt;
+
 use modular_bitfield::{bitfield, BitfieldSpecifier};
 
 use super::ParameterPage;
@@ -6,14 +8,18 @@ use super::{impl_param, Parameter};
 
 
 #[bitfield(bits = 2)]
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
 pub struct MetaEvent {
     pub int_enable: bool,
     pub enable: bool,
 }
 
+/// Enables meta events and their interrupts in the non-wakeup FIFO.
+///
+/// `eventN` controls the meta event with the type `N` (see [`packet::MetaEvent`](crate::packet::MetaEvent)).
+/// The [`Debug`](fmt::Debug) output only lists the events which are enabled or may assert an interrupt.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct MetaEventControl {
     pub event1: MetaEvent,
     pub event2: MetaEvent,
@@ -52,8 +58,83 @@ pub struct MetaEventControl {
 
 impl_param!(MetaEventControl, ParameterPage::System, 1, 8, ReadWrite);
 
+impl MetaEventControl {
+    /// Returns the control of the meta event with the type `n` (1-32).
+    pub fn event(&self, n: u8) -> Option<MetaEvent> {
+        if !(1..=32).contains(&n) {
+            return None;
+        }
+        let bits = u64::from_le_bytes(self.into_bytes()) >> (2 * (n - 1));
+        Some(MetaEvent::from_bytes([bits as u8 & 0b11]))
+    }
+}
+
+impl fmt::Debug for MetaEventControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for n in 1..=32 {
+            let event = self.event(n).unwrap();
+            if event.enable() || event.int_enable() {
+                map.entry(&n, &event);
+            }
+        }
+        map.finish()
+    }
+}
+
+/// The meta event control of the wakeup FIFO.
+///
+/// Same layout as [`MetaEventControl`], which only applies to the non-wakeup FIFO.
+/// Meta events which should be delivered while the host is suspended have to be enabled here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MetaEventControlWakeup(pub MetaEventControl);
 
-#[derive(Debug, Clone, BitfieldSpecifier)]
+impl From<[u8; 8]> for MetaEventControlWakeup {
+    fn from(bytes: [u8; 8]) -> Self {
+        Self(MetaEventControl::from_bytes(bytes))
+    }
+}
+
+impl From<MetaEventControlWakeup> for [u8; 8] {
+    fn from(param: MetaEventControlWakeup) -> Self {
+        param.0.into_bytes()
+    }
+}
+
+impl Parameter for MetaEventControlWakeup {
+    const PAGE: ParameterPage = ParameterPage::System;
+    const PARAM: u8 = 29;
+    const SIZE: usize = 8;
+}
+
+/// Selects the FIFO(s) a meta event control applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetaEventFifo {
+    /// [`MetaEventControl`]
+    NonWakeup,
+    /// [`MetaEventControlWakeup`]
+    Wakeup,
+    /// Both FIFOs.
+    Both,
+}
+
+/// The watermarks and sizes of the FIFOs in bytes.
+///
+/// Only the watermarks can be changed. A watermark of 0 disables the watermark interrupt.
+#[bitfield]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FifoControl {
+    pub wakeup_watermark: u16,
+    pub wakeup_size: u16,
+    pub non_wakeup_watermark: u16,
+    pub non_wakeup_size: u16,
+}
+
+impl_param!(FifoControl, ParameterPage::System, 2, 8, ReadWrite);
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SensorPowerMode {
     SensorNotPresent,
     PowerDown,
@@ -64,8 +145,13 @@ pub enum SensorPowerMode {
     LowPowerActive,
     Active,
 }
+
+/// The status of a physical sensor.
+///
+/// Reported by [`PhysicalSensorStatus`] and by the [`SensorError`](crate::packet::MetaEvent::SensorError) meta event.
 #[bitfield]
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SensorStatus {
     pub data_available: bool,
     pub i2c_nack: bool,
@@ -76,7 +162,7 @@ pub struct SensorStatus {
 }
 
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PhysicalSensorStatus {
     pub accel_sample_rate: u16,
     pub accel_dynamic_range: u16,
@@ -0,0 +1,24 @@
+//! The most commonly used types, for glob imports.
+//!
+//! ```ignore
+//! use bhi160::prelude::*;
+//!
+//! let mut bhi = Bhi160::new(I2c::new(i2c, 0x28));
+//! bhi.configure_sensor(SensorId::Accelerometer, SensorConfig::new().with_sample_rate(100))?;
+//! ```
+//!
+//! The meta event control (`parameters::system::MetaEvent`) and the physical sensor status
+//! (`parameters::system::SensorStatus`) share their names with types exported here and have to be imported explicitly.
+
+pub use crate::{
+    fifo::{Event, MetaEvent, SensorData, SensorStatus},
+    firmware::Firmware,
+    interface::{I2c, Interface},
+    math::{Quaternion, Vector},
+    parameters::{
+        sensors::{SensorConfig, SensorId, SensorInfo, SensorKind},
+        system::{MetaEventControl, MetaEventFifo},
+    },
+    registers::{CoordinateSystem, HostInterfaceControl, IntStatus},
+    Bhi160, DrainPolicy, DrainProgress, Error, Throttle,
+};
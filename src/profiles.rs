@@ -0,0 +1,107 @@
+//! Ready-made sensor setups for common applications.
+//!
+//! The presets are a starting point for new projects, they can be copied and adapted as needed.
+//!
+//! ```ignore
+//! bhi.apply_config(&profiles::GAMING)?;
+//! ```
+//!
+//! A configuration is applied as a whole: if a step fails, the sensors configured so far are disabled again
+//! and the failed step is reported by a [`ConfigError`].
+
+use crate::{
+    parameters::sensors::{SensorConfig, SensorId},
+    Error,
+};
+
+/// The configuration of a single sensor in a [`Bhi160Config`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SensorSetup {
+    pub id: SensorId,
+    /// The sample rate in Hz, see [`SensorConfig::sample_rate`].
+    pub sample_rate: u16,
+    /// The maximum report latency in ms, see [`SensorConfig::max_report_latency`].
+    pub max_report_latency: u16,
+}
+
+impl SensorSetup {
+    pub const fn new(id: SensorId, sample_rate: u16, max_report_latency: u16) -> Self {
+        Self {
+            id,
+            sample_rate,
+            max_report_latency,
+        }
+    }
+
+    pub fn config(&self) -> SensorConfig {
+        SensorConfig::new()
+            .with_sample_rate(self.sample_rate)
+            .with_max_report_latency(self.max_report_latency)
+    }
+}
+
+/// A set of sensors and the host suspend state, applied with [`Bhi160::apply_config`](crate::Bhi160::apply_config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bhi160Config<'a> {
+    pub sensors: &'a [SensorSetup],
+    /// See [`Bhi160::set_ap_suspended`](crate::Bhi160::set_ap_suspended).
+    pub ap_suspended: bool,
+}
+
+/// The step of [`Bhi160::apply_config`](crate::Bhi160::apply_config) which failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigStep {
+    /// Checking the capabilities of the firmware, nothing was configured yet.
+    Check,
+    /// Configuring the sensor at this index of [`Bhi160Config::sensors`].
+    Sensor(usize),
+    /// Setting the host suspend state.
+    ApSuspended,
+}
+
+/// A failed [`Bhi160::apply_config`](crate::Bhi160::apply_config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConfigError<E> {
+    pub step: ConfigStep,
+    pub error: Error<E>,
+    /// The first error while disabling the sensors configured before `step`, if any.
+    ///
+    /// If set, some of these sensors may still be enabled.
+    pub rollback_error: Option<Error<E>>,
+}
+
+/// Low latency orientation without the magnetometer, which is prone to disturbances in a game controller.
+pub const GAMING: Bhi160Config<'static> = Bhi160Config {
+    sensors: &[SensorSetup::new(SensorId::GameRotationVector, 200, 0)],
+    ap_suspended: false,
+};
+
+/// Absolute heading and the altitude (from the pressure).
+pub const NAVIGATION: Bhi160Config<'static> = Bhi160Config {
+    sensors: &[
+        SensorSetup::new(SensorId::GeomagneticRotationVector, 50, 0),
+        SensorSetup::new(SensorId::Pressure, 10, 0),
+    ],
+    ap_suspended: false,
+};
+
+/// Step counting in the background, the host is only woken by the wake gesture.
+///
+/// The step count is batched for up to a minute and can be read when the host is awake.
+pub const WEARABLE: Bhi160Config<'static> = Bhi160Config {
+    sensors: &[
+        SensorSetup::new(SensorId::StepCounter, 1, 60_000),
+        SensorSetup::new(SensorId::WakeGestureWakeup, 1, 0),
+    ],
+    ap_suspended: true,
+};
+
+/// The raw sensor data, batched for 5 s to reduce the number of host wakeups.
+pub const LOGGER: Bhi160Config<'static> = Bhi160Config {
+    sensors: &[
+        SensorSetup::new(SensorId::Accelerometer, 50, 5_000),
+        SensorSetup::new(SensorId::GyroscopeUncalibrated, 50, 5_000),
+        SensorSetup::new(SensorId::MagneticFieldUncalibrated, 25, 5_000),
+    ],
+    ap_suspended: false,
+};
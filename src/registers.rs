@@ -1,12 +1,23 @@
+use core::fmt;
+
 use modular_bitfield::{bitfield, specifiers::*, BitfieldSpecifier};
 
-use crate::parameters::{sensors::SensorId, ParameterPage};
+use crate::{
+    firmware::KnownFirmware,
+    parameters::{sensors::SensorId, ParameterPage},
+};
 
 pub trait Register {
     const ADDR: u8;
     const SIZE: usize;
 }
 
+/// Marker trait for registers that can be read from the BHI.
+pub trait ReadableRegister: Register {}
+
+/// Marker trait for registers that can be written to the BHI.
+pub trait WritableRegister: Register {}
+
 macro_rules! impl_reg {
     ($name:ident, $addr:expr, $size:expr, ReadOnly) => {
         impl From<[u8; $size]> for $name {
@@ -19,6 +30,8 @@ macro_rules! impl_reg {
             const ADDR: u8 = $addr;
             const SIZE: usize = $size;
         }
+
+        impl ReadableRegister for $name {}
     };
     ($name:ident, $addr:expr, $size:expr, ReadWrite) => {
         impl From<$name> for [u8; $size] {
@@ -27,26 +40,42 @@ macro_rules! impl_reg {
             }
         }
 
+        impl WritableRegister for $name {}
+
         impl_reg!($name, $addr, $size, ReadOnly);
     };
 }
 
 /// A pseudo register used to read the data fifo.
-#[derive(Debug, Clone)]
+///
+/// _Deprecated:_ copying the FIFO into a fixed size array is of little use.
+/// Use [`Bhi160::read_fifo`](crate::Bhi160::read_fifo) to read the FIFO into your own buffer
+/// or [`Bhi160::drain_fifo`](crate::Bhi160::drain_fifo) to read and decode it in one go.
+#[deprecated(note = "use `Bhi160::read_fifo` or `Bhi160::drain_fifo` instead")]
+#[derive(Clone)]
 pub struct BufferOut {
-    data: [u8; 0x32],
+    data: [u8; crate::consts::FIFO_WINDOW_SIZE],
     len: usize,
 }
 
+#[allow(deprecated)]
 impl BufferOut {
     pub fn data(&self) -> &[u8] {
         &self.data[..self.len]
     }
 }
 
+#[allow(deprecated)]
+impl fmt::Debug for BufferOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BufferOut").field(&self.data()).finish()
+    }
+}
+
+#[allow(deprecated)]
 impl<'a> From<&'a [u8]> for BufferOut {
     fn from(src: &'a [u8]) -> Self {
-        let mut data = [0; 0x32];
+        let mut data = [0; crate::consts::FIFO_WINDOW_SIZE];
         let len = src.len();
         data.split_at_mut(len).0.copy_from_slice(src);
         Self { data, len }
@@ -56,7 +85,7 @@ impl<'a> From<&'a [u8]> for BufferOut {
 /// Writing to this register will flush the fifo.
 /// 
 /// Either the complete fifo or an individual sensor may be flushed.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FifoFlush {
     /// Do not flush the fifo.
     Nop,
@@ -81,9 +110,15 @@ impl Register for FifoFlush {
     const SIZE: usize = 1;
 }
 
+impl WritableRegister for FifoFlush {}
+
 /// This register is used to control the state of the internal CPU of the BHI.
+///
+/// Prefer the checked transitions [`Bhi160::halt_cpu`](crate::Bhi160::halt_cpu), [`Bhi160::start_cpu`](crate::Bhi160::start_cpu),
+/// [`Bhi160::begin_upload`](crate::Bhi160::begin_upload) and [`Bhi160::end_upload`](crate::Bhi160::end_upload)
+/// over writing this register directly.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ChipControl {
     /// Set this value to `true` to start the CPU and `false` to halt it.
     pub cpu_run_request: bool,
@@ -98,7 +133,8 @@ impl_reg!(ChipControl, 0x34, 1, ReadWrite);
 /// Algorithm
 /// 
 /// Used by Android.
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[bits = 3]
 pub enum AlgorithmId {
     /// Bosch Sensortec BSX Fusion Library
@@ -109,7 +145,7 @@ pub enum AlgorithmId {
 /// Host Interface Id
 /// 
 /// Used by Android.
-#[derive(Debug, Clone, BitfieldSpecifier)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
 #[bits = 3]
 pub enum HostIfId {
     AndroidK,
@@ -118,7 +154,7 @@ pub enum HostIfId {
 
 /// This register contains status information of the BHI.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct HostStatus {
     /// Set after power-on reset or reset invoked by means of the Reset Request register.
     pub reset: bool,
@@ -145,7 +181,7 @@ impl_reg!(HostStatus, 0x35, 1, ReadOnly);
 /// respective FIFOs expired. The Wakeup and Non-Wakeup Immediate bits are set if a sensor event has
 /// occurred which was configured with no latency.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct IntStatus {
     pub host_interrupt: bool,
     pub wakeup_watermark: bool,
@@ -159,9 +195,39 @@ pub struct IntStatus {
 
 impl_reg!(IntStatus, 0x36, 1, ReadOnly);
 
+/// Why the BHI asserted the host interrupt, see [`IntStatus::cause`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InterruptCause {
+    /// The maximum report latency of a sensor expired.
+    Latency,
+    /// An event of a sensor without latency occurred.
+    Immediate,
+    /// The watermark of a FIFO was reached.
+    Watermark,
+}
+
+impl IntStatus {
+    /// Classifies the interrupt, regardless of the FIFO.
+    ///
+    /// If multiple bits are set the most urgent cause wins: an expired latency requires all data to be read,
+    /// so it takes precedence over immediate events, which take precedence over a reached watermark.
+    /// Returns `None` if no cause is set.
+    pub fn cause(&self) -> Option<InterruptCause> {
+        if self.wakeup_latency() || self.non_wakeup_latency() {
+            Some(InterruptCause::Latency)
+        } else if self.wakeup_immediate() || self.non_wakeup_immediate() {
+            Some(InterruptCause::Immediate)
+        } else if self.wakeup_watermark() || self.non_wakeup_watermark() {
+            Some(InterruptCause::Watermark)
+        } else {
+            None
+        }
+    }
+}
+
 /// This register reflects fundamental behavior of the chip during boot up.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ChipStatus {
     pub eeprom_detected: bool,
     pub ee_upload_done: bool,
@@ -193,7 +259,7 @@ impl_reg!(ChipStatus, 0x37, 1, ReadOnly);
 /// If new data arrive in the FIFOs, while the host is reading the FIFO the BHI, will update the
 /// Bytes_Remaining registers and reassert the host interrupt (depending on the configured settings for
 /// creating a host interrupt). This could occur immediately after the acknowledge, or later in time. 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct BytesRemaining(pub u16);
 
 impl From<[u8; 2]> for BytesRemaining {
@@ -207,13 +273,15 @@ impl Register for BytesRemaining {
     const SIZE: usize = 2;
 }
 
+impl ReadableRegister for BytesRemaining {}
+
 /// This register is used to acknowledge a parameter read/write request, to the host.
 /// 
 /// After the host writes to the [`ParameterPageSelect`] and the [`ParameterRequest`] register
 /// it should poll the [`ParameterAcknowledge`] register, until it matches the [`ParameterRequest`] register.
 /// 
 /// The error value means that the requested parameter page or parameter number is unsupported.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ParameterAcknowledge {
     RequestId(u8),
     Error,
@@ -234,9 +302,11 @@ impl Register for ParameterAcknowledge {
     const SIZE: usize = 1;
 }
 
+impl ReadableRegister for ParameterAcknowledge {}
+
 /// This register is used to select the parameter page and requested read/write size.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ParameterPageSelect {
     /// The page the data should be read from/written to.
     pub parameter_page: ParameterPage,
@@ -253,7 +323,7 @@ impl_reg!(ParameterPageSelect, 0x54, 1, ReadWrite);
 /// these two bits correctly every time it writes this register. However, due to possible race conditions, it
 /// should not clear any of these bits immediately after setting.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct HostInterfaceControl {
     /// Requests the algorithm to prepare itself to pause (if required by the implemented algorithm),
     /// then shuts down all sensors in order to save power. When this bit is deasserted, any sensors
@@ -290,7 +360,31 @@ pub struct HostInterfaceControl {
 
 impl_reg!(HostInterfaceControl, 0x55, 1, ReadWrite);
 
-#[derive(Debug, Clone, BitfieldSpecifier)]
+/// The coordinate system of the orientation and vector outputs, see [`HostInterfaceControl::ned_coordinates`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CoordinateSystem {
+    /// East North Up, the Android convention and the default after a reset.
+    #[default]
+    Enu,
+    /// North East Down.
+    Ned,
+}
+
+impl HostInterfaceControl {
+    pub fn coordinate_system(&self) -> CoordinateSystem {
+        if self.ned_coordinates() {
+            CoordinateSystem::Ned
+        } else {
+            CoordinateSystem::Enu
+        }
+    }
+
+    pub fn with_coordinate_system(self, coordinates: CoordinateSystem) -> Self {
+        self.with_ned_coordinates(coordinates == CoordinateSystem::Ned)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BitfieldSpecifier)]
 pub enum Request {
     Read,
     Write
@@ -298,7 +392,7 @@ pub enum Request {
 
 /// This register is used to read or write parameter from or to the BHI.
 #[bitfield]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ParameterRequest {
     /// The parameter within the page to be read from/written to.
     pub parameter: B7,
@@ -314,13 +408,35 @@ impl From<ParameterRequest> for u8 {
 
 impl_reg!(ParameterRequest, 0x64, 1, ReadWrite);
 
+/// This register pair contains a shadow copy of the Host IRQ Timestamp system parameter.
+///
+/// It holds the value of the BHI's 32 bit timestamp counter at the moment the host interrupt was last asserted.
+/// Reading it does not require a parameter transfer, so it can be used to measure the latency between the
+/// interrupt and the host reading the FIFO.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HostIrqTimestamp(pub u32);
+
+impl From<[u8; 4]> for HostIrqTimestamp {
+    fn from(bytes: [u8; 4]) -> Self {
+        Self(u32::from_le_bytes(bytes))
+    }
+}
+
+impl Register for HostIrqTimestamp {
+    const ADDR: u8 = 0x6C;
+    const SIZE: usize = 4;
+}
+
+impl ReadableRegister for HostIrqTimestamp {}
+
 /// This register contains the software version number corresponding to the code placed in
 /// ROM and in the RAM firmware patch, if any. If none is present, this will read back 0.
 /// 
 /// Known values:
 /// * `0x2112`: FUSER1_C2, BHI160
 /// * `0x2DAD`: FUSER1_C3, BHI160B
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RomVersion(pub u16);
 
 impl From<[u8; 2]> for RomVersion {
@@ -334,10 +450,27 @@ impl Register for RomVersion {
     const SIZE: usize = 2;
 }
 
+impl ReadableRegister for RomVersion {}
+
 /// This register contains the software version number corresponding to the RAM firmware patch,
 /// if any. If none is present, this will read back 0.
-#[derive(Debug, Clone)]
-pub struct RamVersion(u16);
+/// 
+/// See [`KnownFirmware`] for the versions of the firmware releases published by Bosch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RamVersion(pub u16);
+
+impl RamVersion {
+    /// Returns `true` if a RAM patch is loaded.
+    pub fn is_loaded(&self) -> bool {
+        self.0 != 0
+    }
+
+    /// Maps the version to a known firmware release.
+    pub fn known_firmware(&self) -> Option<KnownFirmware> {
+        KnownFirmware::from_ram_version(self.0)
+    }
+}
 
 impl From<[u8; 2]> for RamVersion {
     fn from(bytes: [u8; 2]) -> Self {
@@ -350,12 +483,68 @@ impl Register for RamVersion {
     const SIZE: usize = 2;
 }
 
+impl ReadableRegister for RamVersion {}
+
+/// This register contains the error code of the last fatal firmware error.
+///
+/// The same value is reported by the [`Error`](crate::packet::MetaEvent::Error) meta event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ErrorRegister(pub u8);
+
+impl From<[u8; 1]> for ErrorRegister {
+    fn from(bytes: [u8; 1]) -> Self {
+        Self(bytes[0])
+    }
+}
+
+impl Register for ErrorRegister {
+    const ADDR: u8 = 0x50;
+    const SIZE: usize = 1;
+}
+
+impl ReadableRegister for ErrorRegister {}
+
+/// This register contains a debug value of the firmware, whose meaning depends on [`DebugState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DebugValue(pub u8);
+
+impl From<[u8; 1]> for DebugValue {
+    fn from(bytes: [u8; 1]) -> Self {
+        Self(bytes[0])
+    }
+}
+
+impl Register for DebugValue {
+    const ADDR: u8 = 0x52;
+    const SIZE: usize = 1;
+}
+
+impl ReadableRegister for DebugValue {}
+
+/// This register contains the debug state of the firmware.
+///
+/// The same value is reported by the [`Error`](crate::packet::MetaEvent::Error) meta event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DebugState(pub u8);
+
+impl From<[u8; 1]> for DebugState {
+    fn from(bytes: [u8; 1]) -> Self {
+        Self(bytes[0])
+    }
+}
+
+impl Register for DebugState {
+    const ADDR: u8 = 0x53;
+    const SIZE: usize = 1;
+}
+
+impl ReadableRegister for DebugState {}
 
 /// This register contains the product id.
 /// 
 /// Known values:
 /// * `0x83`: BHI160(B)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ProductId(pub u8);
 
 impl From<[u8; 1]> for ProductId {
@@ -369,14 +558,25 @@ impl Register for ProductId {
     const SIZE: usize = 1;
 }
 
+impl ReadableRegister for ProductId {}
+
 /// This register contains the revision id.
 /// 
 /// Known values:
 /// * `0x01`: BHI160
 /// * `0x03`: BHI160B
-#[derive(Debug, Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RevisionId(pub u8);
 
+impl fmt::Debug for RevisionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RevisionId")
+            .field("id", &format_args!("{:#04x}", self.0))
+            .field("variant", &self.variant())
+            .finish()
+    }
+}
+
 impl From<[u8; 1]> for RevisionId {
     fn from(bytes: [u8; 1]) -> Self {
         Self(bytes[0])
@@ -388,11 +588,34 @@ impl Register for RevisionId {
     const SIZE: usize = 1;
 }
 
+impl ReadableRegister for RevisionId {}
+
+impl RevisionId {
+    /// Returns the chip variant denoted by this revision, if it is known.
+    pub fn variant(&self) -> Option<ChipVariant> {
+        match self.0 {
+            0x01 => Some(ChipVariant::Bhi160),
+            0x03 => Some(ChipVariant::Bhi160B),
+            _ => None,
+        }
+    }
+}
+
+/// The variants of the chip supported by this crate.
+///
+/// Some features are only available on the BHI160B, see [`crate::bhi160b`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChipVariant {
+    Bhi160,
+    Bhi160B,
+}
+
 /// This register lets the host specify the starting address for a RAM patch.
 /// 
 /// By default it is 0. After a RAM upload, it will not be 0,
 /// so a subsequent RAM upload procedure will need to start by writing this to 0. 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UploadAddress(pub u16);
 
 impl From<UploadAddress> for [u8; 2] {
@@ -412,6 +635,10 @@ impl Register for UploadAddress {
     const SIZE: usize = 2;
 }
 
+impl ReadableRegister for UploadAddress {}
+
+impl WritableRegister for UploadAddress {}
+
 /// This register contains the calculated CRC of the firmware uploaded to the BHI.
 /// 
 /// After the host has transferred all data from the RAM patch file via the Upload Data register into the
@@ -419,9 +646,15 @@ impl Register for UploadAddress {
 /// calculated CRC (see [`Firmware::crc()`](crate::firmware::Firmware::crc())) to determine whether the upload was successful.
 /// If the upload was successful, the host should disable upload mode and start firmware execution by
 /// setting the corresponding bits in the [`ChipControl`] register.
-#[derive(Debug, Clone)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct UploadCrc(pub u32);
 
+impl fmt::Debug for UploadCrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UploadCrc({:#010x})", self.0)
+    }
+}
+
 impl From<[u8; 4]> for UploadCrc {
     fn from(bytes: [u8; 4]) -> Self {
         Self(u32::from_le_bytes(bytes))
@@ -433,8 +666,10 @@ impl Register for UploadCrc {
     const SIZE: usize = 4;
 }
 
+impl ReadableRegister for UploadCrc {}
+
 /// This register can be written to, in order to reset the BHI.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ResetRequest;
 
 impl From<ResetRequest> for [u8; 1] {
@@ -446,4 +681,131 @@ impl From<ResetRequest> for [u8; 1] {
 impl Register for ResetRequest {
     const ADDR: u8 = 0x9B;
     const SIZE: usize = 1;
+}
+
+impl WritableRegister for ResetRequest {}
+
+/// The access direction of a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Access {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+/// Describes a register (or a buffer accessed like one) of the BHI.
+///
+/// See [`REGISTER_MAP`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RegisterDesc {
+    pub addr: u8,
+    /// The size in bytes, for a [`port`](Self::port) the largest burst accessing it.
+    pub size: usize,
+    pub name: &'static str,
+    pub access: Access,
+    /// `true` if the register is a data port: the address does not advance during a burst,
+    /// so the register occupies only `addr`.
+    pub port: bool,
+}
+
+impl RegisterDesc {
+    /// Returns `true` if `addr` lies within this register.
+    pub const fn contains(&self, addr: u8) -> bool {
+        if self.port {
+            return addr == self.addr;
+        }
+        addr >= self.addr && (addr as usize) < self.addr as usize + self.size
+    }
+}
+
+fn assert_readable<T: ReadableRegister>() {}
+fn assert_writable<T: WritableRegister>() {}
+
+macro_rules! register_map {
+    ($(($name:ident, $access:ident)),* $(,)?; $($raw:expr),* $(,)?) => {
+        $(register_map!(@check $name, $access);)*
+
+        /// All registers of the BHI, e.g. to label raw addresses in debugging tools.
+        ///
+        /// Besides the typed registers of this module this includes the buffers which are accessed directly by the driver
+        /// (data FIFO, parameter buffers and upload data).
+        pub const REGISTER_MAP: &[RegisterDesc] = &[
+            $(RegisterDesc {
+                addr: $name::ADDR,
+                size: $name::SIZE,
+                name: stringify!($name),
+                access: Access::$access,
+                port: false,
+            },)*
+            $($raw,)*
+        ];
+    };
+    (@check $name:ident, ReadOnly) => {
+        const _: fn() = assert_readable::<$name>;
+    };
+    (@check $name:ident, WriteOnly) => {
+        const _: fn() = assert_writable::<$name>;
+    };
+    (@check $name:ident, ReadWrite) => {
+        const _: fn() = assert_readable::<$name>;
+        const _: fn() = assert_writable::<$name>;
+    };
+}
+
+register_map!(
+    (FifoFlush, WriteOnly),
+    (ChipControl, ReadWrite),
+    (HostStatus, ReadOnly),
+    (IntStatus, ReadOnly),
+    (ChipStatus, ReadOnly),
+    (BytesRemaining, ReadOnly),
+    (ParameterAcknowledge, ReadOnly),
+    (ErrorRegister, ReadOnly),
+    (DebugValue, ReadOnly),
+    (DebugState, ReadOnly),
+    (ParameterPageSelect, ReadWrite),
+    (HostInterfaceControl, ReadWrite),
+    (ParameterRequest, ReadWrite),
+    (HostIrqTimestamp, ReadOnly),
+    (RomVersion, ReadOnly),
+    (RamVersion, ReadOnly),
+    (ProductId, ReadOnly),
+    (RevisionId, ReadOnly),
+    (UploadAddress, ReadWrite),
+    (UploadCrc, ReadOnly),
+    (ResetRequest, WriteOnly);
+    RegisterDesc {
+        addr: 0x00,
+        size: 0x32,
+        name: "BufferOut",
+        access: Access::ReadOnly,
+        port: false,
+    },
+    RegisterDesc {
+        addr: 0x3B,
+        size: 16,
+        name: "ParameterReadBuffer",
+        access: Access::ReadOnly,
+        port: false,
+    },
+    RegisterDesc {
+        addr: 0x5C,
+        size: 8,
+        name: "ParameterWriteBuffer",
+        access: Access::WriteOnly,
+        port: false,
+    },
+    // Written in 16-byte bursts during the upload, readable while the upload mode is enabled
+    RegisterDesc {
+        addr: 0x96,
+        size: 16,
+        name: "UploadData",
+        access: Access::ReadWrite,
+        port: true,
+    },
+);
+
+/// Finds the register containing `addr` in the [`REGISTER_MAP`].
+pub fn describe(addr: u8) -> Option<&'static RegisterDesc> {
+    REGISTER_MAP.iter().find(|desc| desc.contains(addr))
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+//! Replay of recorded FIFO captures without hardware in the loop.
+//!
+//! A capture is a sequence of FIFO reads together with the host time at which they happened.
+//! The text format extends the dump format of the [`fixture`](crate::fixture) module: a line starting with `@`
+//! begins a new read at the given host time in microseconds, all following bytes belong to this read.
+//! ```text
+//! @0
+//! 01 FE FF 05 00 69 08 02
+//! @20000
+//! 01 FD FF 05 00 6A 08 02
+//! ```
+//! [`Replay`] decodes the reads like [`Bhi160::drain_fifo`](crate::Bhi160::drain_fifo) (events spanning two reads are
+//! stitched together), tracks the timestamps and passes the events to a callback, either as fast as possible
+//! or at the original pacing.
+
+use std::{thread, time::Duration};
+
+use crate::{
+    clock::{Clock, StdClock},
+    fifo::{decode_events, CorruptFifo, Event},
+    fixture::{parse_dump, FixtureError},
+    timestamp::TimestampTracker,
+};
+
+/// A single FIFO read.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Capture {
+    /// The host time of the read, relative to the start of the recording.
+    pub host_time: Duration,
+    /// The raw FIFO bytes.
+    pub data: Vec<u8>,
+}
+
+/// Parses the textual representation of a recording.
+///
+/// Bytes before the first `@` line belong to a read at host time 0.
+pub fn parse_captures(text: &str) -> Result<Vec<Capture>, FixtureError> {
+    let mut captures = Vec::new();
+    let mut host_time = Duration::ZERO;
+    let mut block = String::new();
+    for (line_nr, line) in text.lines().enumerate() {
+        let Some(time) = line.trim_start().strip_prefix('@') else {
+            block.push_str(line);
+            block.push('\n');
+            continue;
+        };
+        let time = time.split('#').next().unwrap_or_default().trim();
+        let micros = time.parse().map_err(|_| FixtureError::InvalidByte {
+            line: line_nr + 1,
+            token: time.into(),
+        })?;
+        push_capture(&mut captures, host_time, &block)?;
+        host_time = Duration::from_micros(micros);
+        block.clear();
+    }
+    push_capture(&mut captures, host_time, &block)?;
+    Ok(captures)
+}
+
+fn push_capture(
+    captures: &mut Vec<Capture>,
+    host_time: Duration,
+    block: &str,
+) -> Result<(), FixtureError> {
+    let data = parse_dump(block)?;
+    if !data.is_empty() {
+        captures.push(Capture { host_time, data });
+    }
+    Ok(())
+}
+
+/// How fast a [`Replay`] delivers the events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pacing {
+    /// Deliver all events immediately.
+    AsFastAsPossible,
+    /// Wait until the original host time of each read has passed since the start of the replay.
+    Original,
+}
+
+/// An event delivered by a [`Replay`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayedEvent {
+    /// The host time of the read which completed the event.
+    pub host_time: Duration,
+    /// The timestamp of the event in ticks, see [`TimestampTracker`].
+    pub ticks: u32,
+    pub event: Event,
+}
+
+/// Replays captures through the decoder and the [`TimestampTracker`].
+///
+/// The [original pacing](Pacing::Original) follows the host time of `C`.
+#[derive(Debug, Clone)]
+pub struct Replay<C = StdClock>
+where
+    C: Clock,
+{
+    captures: Vec<Capture>,
+    pacing: Pacing,
+    timestamps: TimestampTracker,
+    clock: C,
+}
+
+impl Replay {
+    pub fn new(captures: Vec<Capture>, pacing: Pacing) -> Self {
+        Self::with_clock(captures, pacing, StdClock::new())
+    }
+}
+
+impl<C> Replay<C>
+where
+    C: Clock,
+{
+    pub fn with_clock(captures: Vec<Capture>, pacing: Pacing, clock: C) -> Self {
+        Self {
+            captures,
+            pacing,
+            timestamps: TimestampTracker::new(),
+            clock,
+        }
+    }
+
+    /// The timestamps after the events replayed so far.
+    pub fn timestamps(&self) -> &TimestampTracker {
+        &self.timestamps
+    }
+
+    /// Replays all captures and passes every event to `f`.
+    ///
+    /// Returns the number of events. Fails if a read contains corrupt data,
+    /// the offset of [`CorruptFifo`] is relative to the failing read (including stitched bytes).
+    pub fn run(&mut self, mut f: impl FnMut(&ReplayedEvent)) -> Result<usize, CorruptFifo> {
+        let start = self.clock.now();
+        let mut pending = Vec::new();
+        let mut events = 0;
+        for capture in &self.captures {
+            if self.pacing == Pacing::Original {
+                if let Some(wait) = capture.host_time.checked_sub(self.clock.elapsed(start)) {
+                    thread::sleep(wait);
+                }
+            }
+            pending.extend_from_slice(&capture.data);
+            let timestamps = &mut self.timestamps;
+            let consumed = decode_events(&pending, |event| {
+                events += 1;
+                f(&ReplayedEvent {
+                    host_time: capture.host_time,
+                    ticks: timestamps.update(&event),
+                    event,
+                });
+            })?;
+            pending.drain(..consumed);
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,207 @@
+//! A fixed-size queue for handing events from an interrupt handler to the main loop.
+//!
+//! [`EventQueue`] is a lock-free single-producer single-consumer ring buffer. After [`split`](EventQueue::split)ing it,
+//! the [`Producer`] can be moved into the context reading the FIFO (e.g. an interrupt handler) and the [`Consumer`]
+//! into the context processing the events. Neither side blocks, so both are safe to use from interrupts.
+//!
+//! ```ignore
+//! let mut queue = EventQueue::<64>::new();
+//! let (mut producer, mut consumer) = queue.split();
+//! // Producer side, e.g. on the host interrupt
+//! bhi.drain_fifo(&mut buf, usize::MAX, |event| {
+//!     if producer.enqueue(event).is_err() {
+//!         dropped += 1;
+//!     }
+//! })?;
+//! // Consumer side
+//! while let Some(event) = consumer.dequeue() {
+//!     handle(event);
+//! }
+//! ```
+
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::fifo::{Event, EventRef};
+
+/// A single-producer single-consumer queue holding up to `N` events, see the [module documentation](self).
+pub struct EventQueue<const N: usize> {
+    buf: [UnsafeCell<MaybeUninit<Event>>; N],
+    /// The number of events dequeued so far modulo `2 * N`. Only written by the consumer.
+    head: AtomicUsize,
+    /// The number of events enqueued so far modulo `2 * N`. Only written by the producer.
+    tail: AtomicUsize,
+}
+
+// The producer only writes slots the consumer does not read and vice versa, synchronized through `head` and `tail`.
+unsafe impl<const N: usize> Sync for EventQueue<N> {}
+
+impl<const N: usize> Default for EventQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EventQueue<N> {
+    pub const fn new() -> Self {
+        assert!(N > 0, "the queue needs at least one slot");
+        Self {
+            buf: [const { UnsafeCell::new(MaybeUninit::uninit()) }; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// The number of events currently stored.
+    pub fn len(&self) -> usize {
+        distance(
+            self.head.load(Ordering::Acquire),
+            self.tail.load(Ordering::Acquire),
+            N,
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits the queue into its producer and consumer end.
+    pub fn split(&mut self) -> (Producer<'_, N>, Consumer<'_, N>) {
+        (Producer { queue: self }, Consumer { queue: self })
+    }
+
+    fn slot(&self, counter: usize) -> *mut MaybeUninit<Event> {
+        self.buf[counter % N].get()
+    }
+}
+
+// The counters run modulo `2 * N` (instead of wrapping at `usize::MAX`), so a full and an empty queue can be told
+// apart and the slot index stays continuous for any `N`.
+fn advance(counter: usize, n: usize) -> usize {
+    (counter + 1) % (2 * n)
+}
+
+fn distance(head: usize, tail: usize, n: usize) -> usize {
+    (tail + 2 * n - head) % (2 * n)
+}
+
+/// The end of an [`EventQueue`] adding events.
+pub struct Producer<'a, const N: usize> {
+    queue: &'a EventQueue<N>,
+}
+
+unsafe impl<'a, const N: usize> Send for Producer<'a, N> {}
+
+impl<'a, const N: usize> Producer<'a, N> {
+    /// Adds an event. Returns the event if the queue is full.
+    pub fn enqueue(&mut self, event: Event) -> Result<(), Event> {
+        let tail = self.queue.tail.load(Ordering::Relaxed);
+        if distance(self.queue.head.load(Ordering::Acquire), tail, N) == N {
+            return Err(event);
+        }
+        // The slot is not visible to the consumer until `tail` is advanced
+        unsafe { (*self.queue.slot(tail)).write(event) };
+        self.queue.tail.store(advance(tail, N), Ordering::Release);
+        Ok(())
+    }
+
+    /// Decodes and adds a borrowed event, so the FIFO buffer can be reused right away.
+    pub fn enqueue_ref(&mut self, event: &EventRef<'_>) -> Result<(), Event> {
+        self.enqueue(event.to_event())
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.queue.len() == N
+    }
+}
+
+/// The end of an [`EventQueue`] removing events.
+pub struct Consumer<'a, const N: usize> {
+    queue: &'a EventQueue<N>,
+}
+
+unsafe impl<'a, const N: usize> Send for Consumer<'a, N> {}
+
+impl<'a, const N: usize> Consumer<'a, N> {
+    /// Removes the oldest event.
+    pub fn dequeue(&mut self) -> Option<Event> {
+        let head = self.queue.head.load(Ordering::Relaxed);
+        if self.queue.tail.load(Ordering::Acquire) == head {
+            return None;
+        }
+        // The producer initialized the slot before advancing `tail` and does not touch it until `head` is advanced
+        let event = unsafe { (*self.queue.slot(head)).assume_init_read() };
+        self.queue.head.store(advance(head, N), Ordering::Release);
+        Some(event)
+    }
+
+    /// The number of events waiting.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+impl<'a, const N: usize> Iterator for Consumer<'a, N> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.dequeue()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fifo::SensorData, parameters::sensors::SensorId};
+
+    fn step(n: i32) -> Event {
+        Event::new(SensorId::StepCounter, SensorData::Scalar(n))
+    }
+
+    #[test]
+    fn wrap_around() {
+        let mut queue = EventQueue::<3>::new();
+        let (mut producer, mut consumer) = queue.split();
+        for n in 0..3 {
+            producer.enqueue(step(n)).unwrap();
+        }
+        assert!(producer.is_full());
+        assert_eq!(producer.enqueue(step(3)), Err(step(3)));
+        assert_eq!(consumer.dequeue(), Some(step(0)));
+        producer.enqueue(step(3)).unwrap();
+        assert_eq!(consumer.collect::<Vec<_>>(), [step(1), step(2), step(3)]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn threads() {
+        let mut queue = EventQueue::<4>::new();
+        let (mut producer, consumer) = queue.split();
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for n in 0..1000 {
+                    while producer.enqueue(step(n)).is_err() {}
+                }
+            });
+            let mut consumer = consumer;
+            let mut next = 0;
+            while next < 1000 {
+                if let Some(event) = consumer.dequeue() {
+                    assert_eq!(event, step(next));
+                    next += 1;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,377 @@
+//! Conversion of the raw sensor data into physical units.
+//!
+//! The BHI reports fixed-point values whose scale depends on the sensor and its configured dynamic range.
+//! A [`Scaler`] converts decoded [`Event`]s into [`Reading`]s using floating-point SI units
+//! and optionally rotates all vector and quaternion outputs into a different coordinate frame (see [`FrameTransform`]).
+//!
+//! The dynamic ranges of the physical sensors can be read back from the BHI, see [`Bhi160::scaler`](crate::Bhi160::scaler).
+//! [`heading`] computes the compass heading from the scaled magnetometer and gravity vectors.
+
+use crate::{
+    fifo::{Event, SensorData, SensorStatus},
+    float,
+    math::{Quaternion, Vector},
+    parameters::{sensors::SensorId, system::PhysicalSensorStatus},
+};
+
+/// Standard gravity in m/s².
+const STANDARD_GRAVITY: f32 = 9.80665;
+
+/// A sensor reading converted into physical units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reading {
+    /// A vector in the unit given by [`SensorId::unit`].
+    Vector(Vector<f32>, SensorStatus),
+    /// An uncalibrated vector and the estimated bias in the unit given by [`SensorId::unit`].
+    VectorBias(Vector<f32>, Vector<f32>, SensorStatus),
+    /// A unit quaternion and the estimated accuracy in radians.
+    Quaternion(Quaternion<f32>, f32),
+    /// A scalar in the unit given by [`SensorId::unit`], e.g. the illuminance in lx.
+    Scalar(f32),
+}
+
+/// A rotation from the sensor frame into a user defined frame (e.g. the body frame of a robot).
+///
+/// Vectors are rotated into the target frame.
+/// Orientation quaternions (sensor frame to world frame) are converted to describe the orientation of the target frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTransform {
+    rotation: Quaternion<f32>,
+}
+
+impl FrameTransform {
+    /// Creates a transform from a quaternion rotating vectors from the sensor frame into the target frame.
+    pub fn from_quaternion(rotation: Quaternion<f32>) -> Self {
+        Self {
+            rotation: rotation.normalize(),
+        }
+    }
+
+    /// Creates a transform from a rotation matrix (row-major) mapping vectors from the sensor frame into the target frame.
+    ///
+    /// The matrix has to be orthonormal.
+    pub fn from_matrix(m: [[f32; 3]; 3]) -> Self {
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let rotation = if trace > 0.0 {
+            let s = float::sqrt(trace + 1.0) * 2.0;
+            Quaternion::new(
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                0.25 * s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = float::sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]) * 2.0;
+            Quaternion::new(
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = float::sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]) * 2.0;
+            Quaternion::new(
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = float::sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]) * 2.0;
+            Quaternion::new(
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        };
+        Self::from_quaternion(rotation)
+    }
+
+    /// Rotates a vector from the sensor frame into the target frame.
+    pub fn apply_vector(&self, vector: Vector<f32>) -> Vector<f32> {
+        self.rotation.rotate(vector)
+    }
+
+    /// Converts an orientation of the sensor frame into the orientation of the target frame.
+    pub fn apply_quaternion(&self, orientation: Quaternion<f32>) -> Quaternion<f32> {
+        orientation * self.rotation.conjugate()
+    }
+}
+
+/// Converts raw sensor data into physical units.
+///
+/// The scale of accelerometer, gyroscope and magnetometer based sensors depends on their dynamic range.
+/// If you change the dynamic range (see [`SensorConfig::dynamic_range`](crate::parameters::sensors::SensorConfig::dynamic_range))
+/// you have to update the scaler accordingly, e.g. with [`set_dynamic_ranges`](Self::set_dynamic_ranges) after a
+/// [`DynamicRangeChanged`](crate::fifo::MetaEvent::DynamicRangeChanged) meta event.
+///
+/// Light, proximity and humidity are only reported by firmwares for external environmental sensors.
+/// Their scale depends on the attached sensor, the defaults assume full-scale ranges of 10000 lx, 100 cm and 100 %RH.
+/// Unlike the other readings these are unsigned, i.e. the full 16 bit range maps to `0..range`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scaler {
+    accel_range: f32,
+    gyro_range: f32,
+    mag_range: f32,
+    light_range: f32,
+    proximity_range: f32,
+    humidity_range: f32,
+    transform: Option<FrameTransform>,
+}
+
+impl Default for Scaler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scaler {
+    /// Creates a scaler for the default dynamic ranges.
+    pub fn new() -> Self {
+        Self {
+            accel_range: 16.0,
+            gyro_range: 2000.0,
+            mag_range: 1000.0,
+            light_range: 10000.0,
+            proximity_range: 100.0,
+            humidity_range: 100.0,
+            transform: None,
+        }
+    }
+
+    /// Sets the dynamic range of the accelerometer in g.
+    pub fn with_accel_range(mut self, range: u16) -> Self {
+        self.accel_range = range as f32;
+        self
+    }
+
+    /// Sets the dynamic range of the gyroscope in °/s.
+    pub fn with_gyro_range(mut self, range: u16) -> Self {
+        self.gyro_range = range as f32;
+        self
+    }
+
+    /// Sets the dynamic range of the magnetometer in µT.
+    pub fn with_mag_range(mut self, range: u16) -> Self {
+        self.mag_range = range as f32;
+        self
+    }
+
+    /// Takes the dynamic ranges of the accelerometer, gyroscope and magnetometer from `status`.
+    ///
+    /// Ranges reported as 0 (i.e. the sensor is not present) are left unchanged.
+    pub fn with_dynamic_ranges(mut self, status: &PhysicalSensorStatus) -> Self {
+        self.set_dynamic_ranges(status);
+        self
+    }
+
+    /// Like [`with_dynamic_ranges`](Self::with_dynamic_ranges), but updates the scaler in place.
+    pub fn set_dynamic_ranges(&mut self, status: &PhysicalSensorStatus) {
+        let ranges = [
+            (&mut self.accel_range, status.accel_dynamic_range()),
+            (&mut self.gyro_range, status.gyro_dynamic_range()),
+            (&mut self.mag_range, status.mag_dynamic_range()),
+        ];
+        for (range, reported) in ranges {
+            if reported != 0 {
+                *range = reported as f32;
+            }
+        }
+    }
+
+    /// Sets the full-scale range of the light sensor in lx.
+    pub fn with_light_range(mut self, range: u16) -> Self {
+        self.light_range = range as f32;
+        self
+    }
+
+    /// Sets the full-scale range of the proximity sensor in cm.
+    pub fn with_proximity_range(mut self, range: u16) -> Self {
+        self.proximity_range = range as f32;
+        self
+    }
+
+    /// Sets the full-scale range of the humidity sensor in %RH.
+    pub fn with_humidity_range(mut self, range: u16) -> Self {
+        self.humidity_range = range as f32;
+        self
+    }
+
+    /// Applies the given transform to all vector and quaternion outputs.
+    pub fn with_transform(mut self, transform: FrameTransform) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Changes the transform applied to all vector and quaternion outputs.
+    /// `None` keeps the outputs in the sensor frame.
+    pub fn set_transform(&mut self, transform: Option<FrameTransform>) {
+        self.transform = transform;
+    }
+
+    pub fn transform(&self) -> Option<&FrameTransform> {
+        self.transform.as_ref()
+    }
+
+    /// The value of one LSB for the given sensor in the unit given by [`SensorId::unit`].
+    ///
+    /// Returns `None` for sensors which are not scaled by this scaler.
+    pub fn scale_of(&self, id: SensorId) -> Option<f32> {
+        let range = match id.non_wakeup_variant() {
+            SensorId::Light => return Some(self.light_range / 65536.0),
+            SensorId::Proximity => return Some(self.proximity_range / 65536.0),
+            SensorId::Humidity => return Some(self.humidity_range / 65536.0),
+            SensorId::Accelerometer | SensorId::Gravity | SensorId::LinearAcceleration => {
+                self.accel_range * STANDARD_GRAVITY
+            }
+            SensorId::Gyroscope | SensorId::GyroscopeUncalibrated => self.gyro_range.to_radians(),
+            SensorId::GeomagneticField | SensorId::MagneticFieldUncalibrated => self.mag_range,
+            SensorId::Orientation => 360.0,
+            SensorId::RotationVector
+            | SensorId::GameRotationVector
+            | SensorId::GeomagneticRotationVector => return Some(1.0 / 16384.0),
+            _ => return None,
+        };
+        Some(range / 32768.0)
+    }
+
+    /// Converts the data of an event into physical units.
+    ///
+    /// Returns `None` if the event does not carry a vector, quaternion or environmental reading which can be scaled.
+    /// The Euler angles reported by [`SensorId::Orientation`] are scaled but not transformed.
+    pub fn scale(&self, event: &Event) -> Option<Reading> {
+        let scale = self.scale_of(event.id())?;
+        let rotate = event.id().non_wakeup_variant() != SensorId::Orientation;
+        let vector = |v: &Vector<i16>| {
+            let v = v.change_elem::<f32>().scale(scale);
+            match &self.transform {
+                Some(transform) if rotate => transform.apply_vector(v),
+                _ => v,
+            }
+        };
+        match event.data() {
+            SensorData::VectorStatus(v, status) => Some(Reading::Vector(vector(v), *status)),
+            SensorData::VectorBiasStatus(v, bias, status) => {
+                Some(Reading::VectorBias(vector(v), vector(bias), *status))
+            }
+            SensorData::QuaternionAccuracy(q, accuracy) => {
+                let q = q.change_elem::<f32>().scale(scale);
+                let q = match &self.transform {
+                    Some(transform) => transform.apply_quaternion(q),
+                    None => q,
+                };
+                Some(Reading::Quaternion(q, *accuracy as f32 * scale))
+            }
+            // Only the environmental readings are scaled scalars, they are decoded as signed but are unsigned
+            SensorData::Scalar(value) => Some(Reading::Scalar(*value as u16 as f32 * scale)),
+            _ => None,
+        }
+    }
+}
+
+/// The compass heading in radians, clockwise from magnetic north to the y axis of the sensor (`0..2π`).
+///
+/// `mag` is a [`GeomagneticField`](SensorId::GeomagneticField) and `gravity` a [`Gravity`](SensorId::Gravity) reading
+/// in the same frame, e.g. both scaled by the same [`Scaler`]. The heading is tilt compensated: the magnetic field is
+/// projected onto the horizontal plane defined by `gravity`. Returns `None` if the horizontal component vanishes,
+/// e.g. when the field is parallel to gravity or one of the vectors is zero.
+pub fn heading(mag: Vector<f32>, gravity: Vector<f32>) -> Option<f32> {
+    let east = mag.cross(&gravity);
+    let north = gravity.cross(&east);
+    let (east_norm, north_norm) = (east.norm(), north.norm());
+    if east_norm < f32::EPSILON || north_norm < f32::EPSILON {
+        return None;
+    }
+    let heading = float::atan2(east.y() / east_norm, north.y() / north_norm);
+    Some(if heading < 0.0 {
+        heading + 2.0 * core::f32::consts::PI
+    } else {
+        heading
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn environmental_scalars() {
+        let scaler = Scaler::new().with_proximity_range(50);
+        let light = Event::new(SensorId::LightWakeup, SensorData::Scalar(16384));
+        assert_eq!(scaler.scale(&light), Some(Reading::Scalar(2500.0)));
+        // The upper half of the range is decoded as negative values
+        let proximity = Event::new(SensorId::Proximity, SensorData::Scalar(-32768));
+        assert_eq!(scaler.scale(&proximity), Some(Reading::Scalar(25.0)));
+        let humidity = Event::new(SensorId::Humidity, SensorData::Scalar(-1));
+        assert_eq!(
+            scaler.scale(&humidity),
+            Some(Reading::Scalar(100.0 * 65535.0 / 65536.0))
+        );
+        let steps = Event::new(SensorId::StepCounter, SensorData::Scalar(10));
+        assert_eq!(scaler.scale(&steps), None);
+    }
+
+    #[test]
+    fn mag_dynamic_range() {
+        let mag = Event::new(
+            SensorId::GeomagneticField,
+            SensorData::VectorStatus(Vector([16384, 0, -3277]), SensorStatus::High),
+        );
+        let default = Scaler::new();
+        assert_eq!(
+            default.scale_of(SensorId::GeomagneticFieldWakeup),
+            Some(1000.0 / 32768.0)
+        );
+        let Some(Reading::Vector(v, _)) = default.scale(&mag) else {
+            panic!("unexpected reading");
+        };
+        assert_eq!(*v.x(), 500.0);
+
+        let status = PhysicalSensorStatus::new()
+            .with_accel_dynamic_range(8)
+            .with_mag_dynamic_range(2500);
+        let scaler = default.with_dynamic_ranges(&status);
+        let Some(Reading::Vector(v, _)) = scaler.scale(&mag) else {
+            panic!("unexpected reading");
+        };
+        assert_eq!(*v.x(), 1250.0);
+        assert_eq!(
+            scaler.scale_of(SensorId::Accelerometer),
+            Some(8.0 * STANDARD_GRAVITY / 32768.0)
+        );
+        // Not reported, the default is kept
+        assert_eq!(
+            scaler.scale_of(SensorId::Gyroscope),
+            default.scale_of(SensorId::Gyroscope)
+        );
+    }
+
+    #[test]
+    fn tilt_compensated_heading() {
+        use core::f32::consts::{FRAC_PI_2, PI};
+
+        let eps = if cfg!(all(feature = "micromath", not(feature = "libm"))) {
+            1e-3
+        } else {
+            1e-5
+        };
+        let close = |heading: Option<f32>, expected: f32| (heading.unwrap() - expected).abs() < eps;
+        let flat = Vector([0.0, 0.0, STANDARD_GRAVITY]);
+        // Lying flat, the field points north and downwards
+        assert!(close(heading(Vector([0.0, 20.0, -40.0]), flat), 0.0));
+        assert!(close(heading(Vector([-20.0, 0.0, -40.0]), flat), FRAC_PI_2));
+        assert!(close(heading(Vector([0.0, -20.0, -40.0]), flat), PI));
+        assert!(close(
+            heading(Vector([20.0, 0.0, -40.0]), flat),
+            3.0 * FRAC_PI_2
+        ));
+
+        // Rolled by 90° around the y axis: the z axis of the sensor is horizontal
+        let rolled = Vector([STANDARD_GRAVITY, 0.0, 0.0]);
+        assert!(close(heading(Vector([-40.0, 20.0, 0.0]), rolled), 0.0));
+
+        assert_eq!(heading(Vector([0.0, 0.0, -40.0]), flat), None);
+        assert_eq!(heading(Vector([0.0, 20.0, -40.0]), Vector([0.0; 3])), None);
+    }
+}
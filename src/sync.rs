@@ -0,0 +1,135 @@
+//! Sharing the driver between threads on hosts with `std`.
+//!
+//! [`SyncBhi160`] allows draining the FIFO in one thread while other threads change the configuration.
+//! It uses two locks:
+//! * the _bus lock_ protects the [`Bhi160`] and is held for a single operation, e.g. a parameter access
+//!   or one FIFO transfer,
+//! * the _FIFO lock_ is held for a whole drain, so only one thread reads the FIFO at a time.
+//!
+//! The FIFO lock is always acquired before the bus lock. A drain releases the bus lock between transfers,
+//! so parameter accesses of other threads are delayed by at most one transfer.
+//!
+//! ```ignore
+//! let bhi = SyncBhi160::new(bhi);
+//! std::thread::scope(|scope| {
+//!     scope.spawn(|| loop {
+//!         bhi.drain_fifo(&mut [0; 256], |event| println!("{event:?}")).unwrap();
+//!     });
+//!     bhi.configure_sensor(SensorId::Accelerometer, SensorConfig::new().with_sample_rate(50))?;
+//! });
+//! ```
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    fifo::Event,
+    interface::Interface,
+    parameters::{
+        sensors::{SensorConfig, SensorId},
+        Parameter,
+    },
+    Bhi160, DrainProgress, Error, Throttle,
+};
+
+/// A [`Bhi160`] which can be shared between threads, see the [module documentation](self).
+pub struct SyncBhi160<IF>
+where
+    IF: Interface,
+{
+    bus: Mutex<Bhi160<IF>>,
+    fifo: Mutex<()>,
+}
+
+impl<IF> SyncBhi160<IF>
+where
+    IF: Interface,
+{
+    pub fn new(bhi: Bhi160<IF>) -> Self {
+        Self {
+            bus: Mutex::new(bhi),
+            fifo: Mutex::new(()),
+        }
+    }
+
+    pub fn into_inner(self) -> Bhi160<IF> {
+        self.bus
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Acquires the bus lock for operations not provided by this wrapper.
+    ///
+    /// Must not be called while holding the guard or from the callback of [`drain_fifo`](Self::drain_fifo),
+    /// which would deadlock.
+    pub fn lock(&self) -> MutexGuard<'_, Bhi160<IF>> {
+        // A panic while holding the lock leaves no state of the driver half-updated that a retry could not fix
+        self.bus.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// See [`Bhi160::read_param`].
+    #[cfg(feature = "nightly")]
+    pub fn read_param<T>(&self) -> Result<T, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; T::SIZE]>,
+    {
+        self.lock().read_param()
+    }
+
+    /// See [`Bhi160::read_param`].
+    #[cfg(not(feature = "nightly"))]
+    pub fn read_param<T, const N: usize>(&self) -> Result<T, Error<IF::Error>>
+    where
+        T: Parameter + From<[u8; N]>,
+    {
+        self.lock().read_param()
+    }
+
+    /// See [`Bhi160::write_param`].
+    #[cfg(feature = "nightly")]
+    pub fn write_param<T>(&self, param: T) -> Result<(), Error<IF::Error>>
+    where
+        T: Parameter + Into<[u8; T::SIZE]>,
+    {
+        self.lock().write_param(param)
+    }
+
+    /// See [`Bhi160::write_param`].
+    #[cfg(not(feature = "nightly"))]
+    pub fn write_param<T, const N: usize>(&self, param: T) -> Result<(), Error<IF::Error>>
+    where
+        T: Parameter + Into<[u8; N]>,
+    {
+        self.lock().write_param(param)
+    }
+
+    /// See [`Bhi160::configure_sensor`].
+    pub fn configure_sensor(
+        &self,
+        id: SensorId,
+        config: SensorConfig,
+    ) -> Result<(), Error<IF::Error>> {
+        self.lock().configure_sensor(id, config)
+    }
+
+    /// Drains the FIFO like [`Bhi160::drain_fifo`], releasing the bus lock after every transfer.
+    ///
+    /// `f` is called with the bus lock held and must not access the `SyncBhi160`.
+    pub fn drain_fifo(
+        &self,
+        buf: &mut [u8],
+        mut f: impl FnMut(Event),
+    ) -> Result<DrainProgress, Error<IF::Error>> {
+        let _fifo = self.fifo.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut total = DrainProgress::default();
+        while !total.complete {
+            let progress =
+                self.lock()
+                    .drain_fifo_throttled(buf, Throttle::single_transfer(), &mut f)?;
+            total.events += progress.events;
+            total.bytes += progress.bytes;
+            total.skipped += progress.skipped;
+            total.complete = progress.complete;
+        }
+        Ok(total)
+    }
+}
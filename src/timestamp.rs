@@ -0,0 +1,102 @@
+//! Reconstruction of event timestamps.
+//!
+//! The BHI does not attach a timestamp to every event. Instead it inserts
+//! [`TimestampLsw`](SensorId::TimestampLsw) and [`TimestampMsw`](SensorId::TimestampMsw) events into the FIFO
+//! whenever the time changed. All following events share this timestamp.
+//! The wakeup and non-wakeup FIFO carry separate timestamps.
+//!
+//! Timestamps are counted in ticks of [`TICK_FREQUENCY_HZ`], which is the same on the BHI160 and the BHI160B.
+//! Use [`ticks_to_micros`] and [`micros_to_ticks`] to convert them, e.g. for the timestamps of
+//! [`VectorTimestamp`](SensorData::VectorTimestamp) events or for latency measurements.
+
+use crate::{
+    consts::TICK_FREQUENCY_HZ,
+    fifo::{Event, SensorData},
+    parameters::sensors::SensorId,
+};
+
+/// Converts a number of ticks of the timestamp counter into microseconds, rounding down.
+pub const fn ticks_to_micros(ticks: u32) -> u64 {
+    ticks as u64 * 1_000_000 / TICK_FREQUENCY_HZ as u64
+}
+
+/// Converts microseconds into ticks of the timestamp counter, rounding to the nearest tick.
+///
+/// Converting the result of [`ticks_to_micros`] back yields the original number of ticks.
+/// The result wraps around like the 32 bit counter of the BHI (after about 37 hours).
+pub const fn micros_to_ticks(micros: u64) -> u32 {
+    ((micros as u128 * TICK_FREQUENCY_HZ as u128 + 500_000) / 1_000_000) as u32
+}
+
+/// Tracks the timestamp events of both FIFOs and assigns a timestamp (in ticks) to every event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TimestampTracker {
+    non_wakeup: u32,
+    wakeup: u32,
+}
+
+impl TimestampTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes an event and returns its timestamp.
+    ///
+    /// All events have to be passed in FIFO order, including the timestamp events.
+    pub fn update(&mut self, event: &Event) -> u32 {
+        let ticks = if event.id().is_wakeup() {
+            &mut self.wakeup
+        } else {
+            &mut self.non_wakeup
+        };
+        if let SensorData::Scalar(value) = event.data() {
+            let value = *value as u32 & 0xFFFF;
+            match event.id() {
+                SensorId::TimestampLsw | SensorId::TimestampLswWakeup => {
+                    *ticks = (*ticks & 0xFFFF_0000) | value;
+                }
+                SensorId::TimestampMsw | SensorId::TimestampMswWakeup => {
+                    *ticks = (value << 16) | (*ticks & 0xFFFF);
+                }
+                _ => {}
+            }
+        }
+        *ticks
+    }
+
+    /// The current timestamp of the non-wakeup FIFO.
+    pub fn non_wakeup(&self) -> u32 {
+        self.non_wakeup
+    }
+
+    /// The current timestamp of the wakeup FIFO.
+    pub fn wakeup(&self) -> u32 {
+        self.wakeup
+    }
+
+    /// The current timestamp of the non-wakeup FIFO in microseconds.
+    pub fn non_wakeup_micros(&self) -> u64 {
+        ticks_to_micros(self.non_wakeup)
+    }
+
+    /// The current timestamp of the wakeup FIFO in microseconds.
+    pub fn wakeup_micros(&self) -> u64 {
+        ticks_to_micros(self.wakeup)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tick_conversion() {
+        assert_eq!(ticks_to_micros(32), 1000);
+        assert_eq!(ticks_to_micros(u32::MAX), 134_217_727_968);
+        assert_eq!(micros_to_ticks(1000), 32);
+        assert_eq!(micros_to_ticks(15), 0);
+        assert_eq!(micros_to_ticks(31), 1);
+        assert_eq!(micros_to_ticks(ticks_to_micros(u32::MAX)), u32::MAX);
+        assert_eq!(micros_to_ticks(134_217_728_000), 0);
+    }
+}
@@ -0,0 +1,107 @@
+//! Dimensioned quantities for the scaled readings, based on [`uom`].
+//!
+//! The [`Scaler`](crate::scaled::Scaler) reports plain `f32` values in the unit given by [`SensorId::unit`].
+//! Converting them into `uom` quantities lets the compiler check the dimensions of all further calculations.
+//!
+//! ```ignore
+//! if let Some(Quantity::Acceleration(accel)) = Quantity::from_reading(event.id(), &scaler.scale(&event)?) {
+//!     let speed = *accel.x() * dt;
+//! }
+//! ```
+
+use uom::si::{
+    acceleration::meter_per_second_squared,
+    angular_velocity::radian_per_second,
+    f32::{Acceleration, AngularVelocity, MagneticFluxDensity, Pressure, ThermodynamicTemperature},
+    magnetic_flux_density::microtesla,
+    pressure::pascal,
+    thermodynamic_temperature::degree_celsius,
+};
+
+use crate::{math::Vector, parameters::sensors::SensorId, scaled::Reading};
+
+/// A vector reading as a quantity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quantity {
+    Acceleration(Vector<Acceleration>),
+    AngularVelocity(Vector<AngularVelocity>),
+    MagneticFluxDensity(Vector<MagneticFluxDensity>),
+}
+
+impl Quantity {
+    /// Converts a vector reading of sensor `id` (e.g. a [`Reading::Vector`] of the accelerometer).
+    ///
+    /// The bias of uncalibrated sensors is dropped, use [`Quantity::from_vector`] to convert it.
+    /// Returns `None` for sensors without a physical vector quantity (e.g. the orientation).
+    pub fn from_reading(id: SensorId, reading: &Reading) -> Option<Self> {
+        match reading {
+            Reading::Vector(vector, _) | Reading::VectorBias(vector, _, _) => {
+                Self::from_vector(id, *vector)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts a vector in the unit of `id` (see [`SensorId::unit`]).
+    pub fn from_vector(id: SensorId, vector: Vector<f32>) -> Option<Self> {
+        match id.non_wakeup_variant() {
+            SensorId::Accelerometer | SensorId::Gravity | SensorId::LinearAcceleration => {
+                Some(Self::Acceleration(Vector(
+                    vector.0.map(Acceleration::new::<meter_per_second_squared>),
+                )))
+            }
+            SensorId::Gyroscope | SensorId::GyroscopeUncalibrated => Some(Self::AngularVelocity(
+                Vector(vector.0.map(AngularVelocity::new::<radian_per_second>)),
+            )),
+            SensorId::GeomagneticField | SensorId::MagneticFieldUncalibrated => {
+                Some(Self::MagneticFluxDensity(Vector(
+                    vector.0.map(MagneticFluxDensity::new::<microtesla>),
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Converts a pressure in Pa (the unit of [`SensorId::Pressure`]).
+pub fn pressure(pascals: f32) -> Pressure {
+    Pressure::new::<pascal>(pascals)
+}
+
+/// Converts a temperature in °C (the unit of [`SensorId::Temperature`] and [`SensorId::AmbientTemperature`]).
+pub fn temperature(celsius: f32) -> ThermodynamicTemperature {
+    ThermodynamicTemperature::new::<degree_celsius>(celsius)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fifo::SensorStatus;
+
+    #[test]
+    fn convert_readings() {
+        let reading = Reading::Vector(Vector([0.0, 0.0, 9.81]), SensorStatus::High);
+        let Some(Quantity::Acceleration(accel)) =
+            Quantity::from_reading(SensorId::AccelerometerWakeup, &reading)
+        else {
+            panic!("not an acceleration");
+        };
+        assert_eq!(accel.z().get::<meter_per_second_squared>(), 9.81);
+
+        let Some(Quantity::MagneticFluxDensity(mag)) =
+            Quantity::from_vector(SensorId::GeomagneticField, Vector([50.0, 0.0, 0.0]))
+        else {
+            panic!("not a magnetic flux density");
+        };
+        assert!((mag.x().get::<uom::si::magnetic_flux_density::tesla>() - 50e-6).abs() < 1e-9);
+        assert_eq!(
+            Quantity::from_reading(SensorId::Orientation, &reading),
+            None
+        );
+        assert_eq!(pressure(101_325.0).get::<pascal>(), 101_325.0);
+        assert!(
+            (temperature(25.0).get::<uom::si::thermodynamic_temperature::kelvin>() - 298.15).abs()
+                < 1e-3
+        );
+    }
+}
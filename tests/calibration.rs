@@ -0,0 +1,131 @@
+mod mock;
+
+use bhi160::{
+    calibration::{
+        Layout, Persistence, Profile, ProfileLayout, Snapshot, Store, MAX_PROFILE_SIZE,
+        SNAPSHOT_SIZE,
+    },
+    packet::{Event, SensorData, SensorStatus, Vector},
+    parameters::sensors::SensorId,
+    Bhi160,
+};
+use mock::MockBhi;
+
+#[derive(Default)]
+struct MemoryStore(Vec<u8>);
+
+impl Store for MemoryStore {
+    type Error = ();
+
+    fn save(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.0 = data.to_vec();
+        Ok(())
+    }
+
+    fn load(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        buf[..self.0.len()].copy_from_slice(&self.0);
+        Ok(self.0.len())
+    }
+}
+
+/// Profiles spanning several parameters, the last ones not filling their parameter.
+const LAYOUT: Layout = Layout {
+    accelerometer: ProfileLayout {
+        param: 10,
+        size: 20,
+    },
+    magnetometer: ProfileLayout {
+        param: 13,
+        size: MAX_PROFILE_SIZE,
+    },
+    gyroscope: ProfileLayout {
+        param: 21,
+        size: 12,
+    },
+};
+
+/// Fills the parameters of the profile `n` in the mock, byte `i` of the profile is `n * 100 + i`.
+fn insert_profile(device: &mut MockBhi, layout: ProfileLayout, n: u8) {
+    let bytes: Vec<u8> = (0..layout.size as u8).map(|i| n * 100 + i).collect();
+    for (chunk, param) in bytes.chunks(8).zip(layout.param..) {
+        device.params.insert((2, param), chunk.to_vec());
+    }
+}
+
+fn accel(status: SensorStatus) -> Event {
+    Event::new(
+        SensorId::Accelerometer,
+        SensorData::VectorStatus(Vector([0, 0, 4096]), status),
+    )
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut device = MockBhi::new();
+    insert_profile(&mut device, LAYOUT.accelerometer, 0);
+    insert_profile(&mut device, LAYOUT.magnetometer, 1);
+    insert_profile(&mut device, LAYOUT.gyroscope, 2);
+    let mut bhi = Bhi160::new(device);
+    let mut persistence = Persistence::new(MemoryStore::default(), LAYOUT);
+    assert_eq!(persistence.restore(&mut bhi), Ok(false));
+
+    persistence.observe(&accel(SensorStatus::Medium));
+    assert!(!persistence.pending());
+    persistence.observe(&accel(SensorStatus::High));
+    assert_eq!(persistence.poll(&mut bhi), Ok(true));
+    persistence.observe(&accel(SensorStatus::High));
+    assert_eq!(persistence.poll(&mut bhi), Ok(false));
+
+    let mut bhi = Bhi160::new(MockBhi::new());
+    assert_eq!(persistence.restore(&mut bhi), Ok(true));
+    let mut expected = MockBhi::new();
+    insert_profile(&mut expected, LAYOUT.accelerometer, 0);
+    insert_profile(&mut expected, LAYOUT.magnetometer, 1);
+    insert_profile(&mut expected, LAYOUT.gyroscope, 2);
+    let writes = &bhi.interface().param_writes;
+    assert_eq!(writes.len(), 3 + 8 + 2);
+    for write in writes {
+        let data = &expected.params[&(write.page, write.param)];
+        // A size of 0 selects the whole window
+        assert_eq!(write.size as usize, data.len() % 8);
+        assert_eq!(&write.window[..data.len()], &data[..]);
+    }
+    assert_eq!(bhi.interface().regs[0x54], 0);
+}
+
+#[test]
+fn full_size_round_trip() {
+    let accelerometer: Vec<u8> = (0..20).collect();
+    let magnetometer: Vec<u8> = (0..MAX_PROFILE_SIZE as u8).rev().collect();
+    let snapshot = Snapshot {
+        accelerometer: Profile::new(&accelerometer).unwrap(),
+        magnetometer: Profile::new(&magnetometer).unwrap(),
+        gyroscope: Profile::new(&[0xAB; 12]).unwrap(),
+    };
+    let mut buf = [0; SNAPSHOT_SIZE];
+    let len = snapshot.to_bytes(&mut buf);
+    assert_eq!(len, 1 + 3 + 20 + MAX_PROFILE_SIZE + 12);
+    assert_eq!(Snapshot::from_bytes(&buf[..len], &LAYOUT), Some(snapshot));
+    assert_eq!(Snapshot::from_bytes(&buf[..len - 1], &LAYOUT), None);
+
+    // The firmware changed the size of a profile
+    let mut layout = LAYOUT;
+    layout.gyroscope.size = 16;
+    assert_eq!(Snapshot::from_bytes(&buf[..len], &layout), None);
+
+    let mut bhi = Bhi160::new(MockBhi::new());
+    snapshot.write(&mut bhi, &LAYOUT).unwrap();
+    let writes = std::mem::take(&mut bhi.interface_mut().param_writes);
+    for write in writes {
+        let size = match write.size {
+            0 => 8,
+            size => size as usize,
+        };
+        let value = write.window[..size].to_vec();
+        bhi.interface_mut()
+            .params
+            .insert((write.page, write.param), value);
+    }
+    assert_eq!(Snapshot::read(&mut bhi, &LAYOUT), Ok(snapshot));
+    assert!(Profile::new(&[0; MAX_PROFILE_SIZE + 1]).is_none());
+}
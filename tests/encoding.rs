@@ -0,0 +1,312 @@
+//! Golden wire encodings of the registers and parameters.
+//!
+//! These guard the bit layout of the bitfields against refactorings.
+
+#![allow(incomplete_features)]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
+
+mod mock;
+
+use bhi160::{
+    bhi160b::{PhysicalSensorInfo, PhysicalSensorsPresent},
+    parameters::{
+        sensors::{SensorConfig, SensorId, SensorKind, UnknownSensorId},
+        system::{FifoControl, MetaEvent, MetaEventControl},
+        ParameterPage,
+    },
+    registers::*,
+    Bhi160,
+};
+use mock::MockBhi;
+
+/// A small deterministic generator for the round-trip checks.
+fn random_bytes<const N: usize>(seed: &mut u64) -> [u8; N] {
+    [0; N].map(|_| {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed as u8
+    })
+}
+
+/// Checks that decoding and encoding all values of a single byte register yields the same byte.
+fn round_trip_byte<T>()
+where
+    T: From<[u8; 1]> + Into<[u8; 1]>,
+{
+    for byte in 0..=255 {
+        assert_eq!(
+            T::from([byte]).into(),
+            [byte],
+            "{}",
+            core::any::type_name::<T>()
+        );
+    }
+}
+
+/// Checks that decoding and encoding random values yields the same bytes.
+fn round_trip<T, const N: usize>()
+where
+    T: From<[u8; N]> + Into<[u8; N]>,
+{
+    let mut seed = 0x2545_F491_4F6C_DD1D;
+    for _ in 0..1000 {
+        let bytes = random_bytes::<N>(&mut seed);
+        assert_eq!(
+            T::from(bytes).into(),
+            bytes,
+            "{}",
+            core::any::type_name::<T>()
+        );
+    }
+}
+
+#[test]
+fn register_round_trips() {
+    round_trip_byte::<ChipControl>();
+    round_trip_byte::<ParameterPageSelect>();
+    round_trip_byte::<HostInterfaceControl>();
+    round_trip_byte::<ParameterRequest>();
+    round_trip::<UploadAddress, 2>();
+    round_trip::<MetaEventControl, 8>();
+    round_trip::<FifoControl, 8>();
+}
+
+#[test]
+fn register_map() {
+    let upload = describe(0x96).unwrap();
+    assert_eq!(upload.name, "UploadData");
+    assert_eq!((upload.size, upload.access), (16, Access::ReadWrite));
+    // The data port does not cover the following registers
+    assert_eq!(describe(0x97).unwrap().name, "UploadCrc");
+    assert_eq!(describe(0x9C), None);
+    assert_eq!(describe(0x3C).unwrap().name, "ParameterReadBuffer");
+}
+
+#[test]
+fn all_parameter_pages_decode() {
+    for byte in 0..=255 {
+        let select = ParameterPageSelect::from_bytes([byte]);
+        let page = select.parameter_page_or_err().unwrap();
+        assert_eq!(page as u8, byte & 0x0F);
+    }
+    assert!(!ParameterPage::Sensors.is_firmware_specific());
+    assert!(ParameterPage::Page4.is_firmware_specific());
+    assert!(ParameterPage::Custom12.is_firmware_specific());
+}
+
+#[test]
+fn register_encodings() {
+    assert_eq!(<[u8; 1]>::from(FifoFlush::Nop), [0x00]);
+    assert_eq!(<[u8; 1]>::from(FifoFlush::FlushAll), [0xFF]);
+    assert_eq!(
+        <[u8; 1]>::from(FifoFlush::Sensor(SensorId::GyroscopeWakeup)),
+        [36]
+    );
+
+    assert_eq!(
+        ChipControl::new().with_cpu_run_request(true).into_bytes(),
+        [0x01]
+    );
+    assert_eq!(
+        ChipControl::new()
+            .with_host_upload_enable(true)
+            .into_bytes(),
+        [0x02]
+    );
+
+    let select = ParameterPageSelect::new()
+        .with_parameter_page(ParameterPage::System)
+        .with_parameter_size(8);
+    assert_eq!(select.into_bytes(), [0x81]);
+    let select = ParameterPageSelect::new()
+        .with_parameter_page(ParameterPage::Custom14)
+        .with_parameter_size(0);
+    assert_eq!(select.into_bytes(), [0x0E]);
+
+    let request = ParameterRequest::new()
+        .with_parameter(65)
+        .with_request(Request::Write);
+    assert_eq!(u8::from(request), 0xC1);
+    let request = ParameterRequest::new()
+        .with_parameter(31)
+        .with_request(Request::Read);
+    assert_eq!(u8::from(request), 0x1F);
+
+    let control = HostInterfaceControl::new();
+    assert_eq!(
+        control.with_algorithm_standby_request(true).into_bytes(),
+        [0x01]
+    );
+    assert_eq!(control.with_abort_transfer(true).into_bytes(), [0x02]);
+    assert_eq!(
+        control.with_update_transfer_count(true).into_bytes(),
+        [0x04]
+    );
+    assert_eq!(
+        control
+            .with_wakeup_fifo_host_interrupt_disable(true)
+            .into_bytes(),
+        [0x08]
+    );
+    assert_eq!(control.with_ned_coordinates(true).into_bytes(), [0x10]);
+    assert_eq!(control.with_ap_suspended(true).into_bytes(), [0x20]);
+    assert_eq!(
+        control.with_request_sensor_self_test(true).into_bytes(),
+        [0x40]
+    );
+    assert_eq!(
+        control
+            .with_non_wakeup_fifo_host_interrupt_disable(true)
+            .into_bytes(),
+        [0x80]
+    );
+
+    assert_eq!(<[u8; 2]>::from(UploadAddress(0x1234)), [0x12, 0x34]);
+    assert_eq!(<[u8; 1]>::from(ResetRequest), [0x01]);
+}
+
+#[test]
+fn register_decodings() {
+    let status = HostStatus::from([0b0000_0101]);
+    assert!(status.reset());
+    assert!(!status.algorithm_standby());
+    assert_eq!(status.host_if_id(), HostIfId::AndroidL);
+    assert_eq!(status.algorithm_id(), AlgorithmId::BSX);
+
+    let status = IntStatus::from([0x11]);
+    assert!(status.host_interrupt());
+    assert!(status.non_wakeup_watermark());
+    assert!(!status.wakeup_watermark());
+
+    assert!(ChipStatus::from([0x08]).firmware_idle());
+    assert_eq!(BytesRemaining::from([0x34, 0x12]), BytesRemaining(0x1234));
+    assert_eq!(
+        ParameterAcknowledge::from([0x80]),
+        ParameterAcknowledge::Error
+    );
+    assert_eq!(
+        ParameterAcknowledge::from([0x81]),
+        ParameterAcknowledge::RequestId(0x81)
+    );
+    assert_eq!(
+        HostIrqTimestamp::from([0x78, 0x56, 0x34, 0x12]),
+        HostIrqTimestamp(0x1234_5678)
+    );
+    assert_eq!(RomVersion::from([0xAD, 0x2D]), RomVersion(0x2DAD));
+    assert_eq!(RamVersion::from([0xB0, 0x2D]), RamVersion(11696));
+    assert_eq!(
+        RevisionId::from([0x03]).variant(),
+        Some(ChipVariant::Bhi160B)
+    );
+    assert_eq!(UploadCrc::from([4, 3, 2, 1]), UploadCrc(0x0102_0304));
+}
+
+#[test]
+fn parameter_encodings() {
+    let control = MetaEventControl::new()
+        .with_event1(MetaEvent::new().with_enable(true))
+        .with_event2(MetaEvent::new().with_int_enable(true))
+        .with_event32(MetaEvent::new().with_enable(true).with_int_enable(true));
+    assert_eq!(<[u8; 8]>::from(control), [0x06, 0, 0, 0, 0, 0, 0, 0xC0]);
+
+    let fifo = FifoControl::from([0x00, 0x01, 0x00, 0x08, 0x40, 0x00, 0x00, 0x10]);
+    assert_eq!(fifo.wakeup_watermark(), 0x100);
+    assert_eq!(fifo.wakeup_size(), 0x800);
+    assert_eq!(fifo.non_wakeup_watermark(), 0x40);
+    assert_eq!(fifo.non_wakeup_size(), 0x1000);
+
+    let config = SensorConfig::new()
+        .with_sample_rate(200)
+        .with_max_report_latency(0x0102)
+        .with_dynamic_range(8);
+    assert_eq!(config.into_bytes(), [200, 0, 0x02, 0x01, 0, 0, 8, 0]);
+
+    let mut bytes = [0; 16];
+    bytes[..6].copy_from_slice(&[6, 0x21, 3, 7, 0x2C, 0x01]);
+    let info = PhysicalSensorInfo::from_bytes(bytes);
+    assert_eq!(info.sensor_type(), SensorId::Pressure as u8);
+    assert_eq!((info.driver_id(), info.driver_version()), (0x21, 3));
+    assert_eq!((info.current(), info.dynamic_range()), (7, 300));
+
+    let present = PhysicalSensorsPresent::from([0x16, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(present.is_present(1));
+    assert!(present.is_present(4));
+    assert!(!present.is_present(3));
+}
+
+#[test]
+fn sensor_id_conversions() {
+    for raw in 0..=255 {
+        match SensorId::try_from(raw) {
+            Ok(id) => assert_eq!(u8::from(id), raw),
+            Err(e) => assert_eq!(e, UnknownSensorId(raw)),
+        }
+    }
+    assert_eq!(SensorId::try_from(32), Err(UnknownSensorId(32)));
+    assert_eq!(SensorId::GyroscopeWakeup.base_id(), 4);
+    assert_eq!(SensorId::TimestampLswWakeup.base_id(), 252);
+}
+
+#[test]
+fn readable_debug() {
+    let control = MetaEventControl::new()
+        .with_event1(MetaEvent::new().with_enable(true))
+        .with_event12(MetaEvent::new().with_int_enable(true));
+    assert_eq!(
+        format!("{control:?}"),
+        "{1: MetaEvent { int_enable: false, enable: true }, 12: MetaEvent { int_enable: true, enable: false }}"
+    );
+    assert_eq!(
+        format!("{:?}", RevisionId(3)),
+        "RevisionId { id: 0x03, variant: Some(Bhi160B) }"
+    );
+    assert_eq!(format!("{:?}", UploadCrc(0xBEEF)), "UploadCrc(0x0000beef)");
+    assert_eq!(
+        format!(
+            "{:?}",
+            PhysicalSensorsPresent::from([0x16, 0, 0, 0, 0, 0, 0, 0])
+        ),
+        "{1, 2, 4}"
+    );
+}
+
+#[test]
+fn sensor_kinds() {
+    for raw in 1..64 {
+        let Ok(id) = SensorId::try_from(raw) else {
+            continue;
+        };
+        let kind = id.kind().unwrap();
+        assert_eq!(kind.id(id.is_wakeup()), id);
+        assert_eq!(kind as u8, id.base_id());
+    }
+    assert_eq!(
+        SensorKind::Orientation.id(true),
+        SensorId::OrientationWakeup
+    );
+    assert_eq!(SensorId::MetaEventWakeup.kind(), None);
+}
+
+/// A register pointing at the read-only [`HostStatus`].
+struct BrokenRegister;
+
+impl Register for BrokenRegister {
+    const ADDR: u8 = 0x35;
+    const SIZE: usize = 1;
+}
+
+impl WritableRegister for BrokenRegister {}
+
+impl From<BrokenRegister> for [u8; 1] {
+    fn from(_: BrokenRegister) -> Self {
+        [0]
+    }
+}
+
+#[test]
+#[should_panic(expected = "read-only register HostStatus")]
+fn reject_write_to_read_only_register() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    bhi.write_reg(BrokenRegister).unwrap();
+}
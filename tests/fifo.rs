@@ -0,0 +1,394 @@
+mod mock;
+
+use bhi160::{
+    diagnostics::SupportBundle,
+    packet::{MetaEvent, Padding, SensorData},
+    parameters::sensors::SensorId,
+    registers::{CoordinateSystem, HostInterfaceControl},
+    Bhi160, DrainPolicy, Error, Throttle,
+};
+use mock::MockBhi;
+
+/// An accelerometer event with high accuracy.
+const ACCEL: [u8; 8] = [1, 0, 0, 0, 0, 0, 0x10, 3];
+
+fn device(events: usize) -> MockBhi {
+    let mut device = MockBhi::new();
+    device.fifo = ACCEL.repeat(events);
+    device.max_transfer = 2 * ACCEL.len();
+    device
+}
+
+#[test]
+fn drain_throttled() {
+    let mut bhi = Bhi160::new(device(5));
+    let mut buf = [0; 32];
+    let mut events = 0;
+
+    let progress = bhi
+        .drain_fifo_throttled(&mut buf, Throttle::unlimited().with_max_events(2), |_| {
+            events += 1
+        })
+        .unwrap();
+    assert_eq!((progress.events, progress.bytes), (2, 16));
+    assert!(!progress.complete);
+    // The transfer count update stays requested for the next call
+    assert_eq!(bhi.interface().regs[0x55] & 0x04, 0x04);
+
+    let progress = bhi
+        .drain_fifo_throttled(&mut buf, Throttle::unlimited(), |_| events += 1)
+        .unwrap();
+    assert_eq!((progress.events, progress.bytes), (3, 24));
+    assert!(progress.complete);
+    assert_eq!(bhi.interface().regs[0x55] & 0x04, 0);
+    assert_eq!(events, 5);
+}
+
+#[test]
+fn lenient_padding() {
+    let mut padded = device(2);
+    padded.fifo.insert(ACCEL.len(), 0);
+    padded.fifo.extend([0, 0]);
+    padded.max_transfer = usize::MAX;
+    let mut bhi = Bhi160::new(padded);
+    let mut buf = [0; 32];
+    assert!(matches!(
+        bhi.drain_fifo(&mut buf, usize::MAX, |_| {}),
+        Err(Error::CorruptFifo(_))
+    ));
+
+    let mut padded = device(2);
+    padded.fifo.insert(ACCEL.len(), 0);
+    padded.fifo.extend([0, 0]);
+    padded.max_transfer = usize::MAX;
+    let mut bhi = Bhi160::new(padded);
+    bhi.set_fifo_padding(Padding::Lenient);
+    let mut events = 0;
+    let progress = bhi
+        .drain_fifo_throttled(&mut buf, Throttle::unlimited(), |_| events += 1)
+        .unwrap();
+    assert_eq!((events, progress.events, progress.skipped), (2, 2, 3));
+    assert!(progress.complete);
+}
+
+#[test]
+fn refresh_bytes_remaining() {
+    let mut bhi = Bhi160::new(device(3));
+    bhi.interface_mut().max_transfer = usize::MAX;
+    assert_eq!(bhi.refresh_and_read_bytes_remaining(), Ok(24));
+    let writes: Vec<_> = bhi
+        .interface()
+        .writes
+        .iter()
+        .filter(|(addr, _)| *addr == 0x55)
+        .map(|(_, data)| data[0])
+        .collect();
+    assert_eq!(writes, [0x04, 0x00]);
+}
+
+#[test]
+fn significant_motion_one_shot() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let mut buf = [0; 32];
+    assert_eq!(
+        bhi.arm_significant_motion(true, false),
+        Ok(SensorId::SignificantMotionWakeup)
+    );
+    assert_eq!(bhi.interface().param_writes.len(), 1);
+    bhi.interface_mut().fifo = vec![49, 1, 0];
+    bhi.drain_fifo(&mut buf, usize::MAX, |_| {}).unwrap();
+    assert_eq!(bhi.significant_motion_armed(), None);
+    assert_eq!(bhi.interface().param_writes.len(), 1);
+
+    bhi.arm_significant_motion(false, true).unwrap();
+    bhi.interface_mut().fifo = vec![17, 1, 0];
+    bhi.drain_fifo(&mut buf, usize::MAX, |_| {}).unwrap();
+    assert_eq!(
+        bhi.significant_motion_armed(),
+        Some(SensorId::SignificantMotion)
+    );
+    let rearm = bhi.interface().param_writes.last().unwrap();
+    assert_eq!((rearm.param, rearm.window[0]), (17 + 64, 1));
+    assert_eq!(bhi.interface().param_writes.len(), 3);
+}
+
+#[test]
+fn peek_and_abort() {
+    let mut bhi = Bhi160::new(device(3));
+    bhi.interface_mut().max_transfer = usize::MAX;
+    let mut buf = [0; 12];
+    let peek = bhi.peek_fifo(&mut buf).unwrap();
+    assert_eq!((peek.data.len(), peek.transfer_size), (12, 24));
+    assert_eq!(peek.events().filter_map(Result::ok).count(), 1);
+    assert_eq!(bhi.interface().regs[0x55] & 0x02, 0);
+    assert!(bhi.interface().fifo.is_empty());
+
+    let peek = bhi.peek_fifo(&mut buf).unwrap();
+    assert_eq!(peek.transfer_size, 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn iterate_events() {
+    let mut bhi = Bhi160::new(device(5));
+    let mut buf = [0; 32];
+    let events = bhi.events(&mut buf).unwrap();
+    assert_eq!(events.map(Result::unwrap).count(), 5);
+    assert_eq!(bhi.interface().regs[0x55] & 0x04, 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn iterate_events_lenient() {
+    let mut device = device(3);
+    device.fifo.insert(2 * ACCEL.len(), 0);
+    let mut bhi = Bhi160::new(device);
+    bhi.set_fifo_padding(Padding::Lenient);
+    let mut buf = [0; 32];
+    let mut events = bhi.events(&mut buf).unwrap();
+    assert_eq!(events.by_ref().map(Result::unwrap).count(), 3);
+    assert_eq!(events.skipped(), 1);
+}
+
+#[test]
+fn report_truncated_read() {
+    let mut bhi = Bhi160::new(device(5));
+    let mut buf = [0; 12];
+    let (data, report) = bhi.read_fifo_report(&mut buf).unwrap();
+    assert_eq!(data, &ACCEL.repeat(2)[..12]);
+    assert_eq!(report.bytes_read, 12);
+    assert_eq!(report.bytes_remaining_after, 4);
+    assert!(report.truncated);
+}
+
+#[test]
+fn disable_and_flush() {
+    let mut device = device(2);
+    // FlushComplete of the accelerometer followed by a sample of another sensor
+    device.fifo.extend([254, 1, 1, 0]);
+    device.fifo.extend(ACCEL);
+    let mut bhi = Bhi160::new(device);
+    let mut buf = [0; 32];
+    let mut events = Vec::new();
+    bhi.disable_sensor_and_flush(SensorId::Accelerometer, &mut buf, |event| {
+        events.push(event)
+    })
+    .unwrap();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[2].data(),
+        &SensorData::MetaEvent(MetaEvent::FlushComplete(SensorId::Accelerometer))
+    );
+    let writes = &bhi.interface().writes;
+    assert!(writes.contains(&(0x32, vec![1])));
+    assert_eq!(bhi.interface().param_writes[0].window, [0; 8]);
+
+    // The flush of another sensor never completes
+    assert_eq!(
+        bhi.disable_sensor_and_flush(SensorId::Gyroscope, &mut buf, |_| {}),
+        Err(Error::InvalidState)
+    );
+}
+
+#[test]
+fn suspend_and_resume() {
+    let mut bhi = Bhi160::new(device(3));
+    bhi.suspend().unwrap();
+    assert_eq!(bhi.interface().regs[0x55], 0xA0);
+
+    let mut events = 0;
+    let progress = bhi.resume(&mut [0; 32], |_| events += 1).unwrap();
+    assert_eq!(events, 3);
+    assert!(progress.complete);
+    assert_eq!(bhi.interface().regs[0x55], 0);
+}
+
+#[test]
+fn coordinate_system_after_reset() {
+    let mut bhi = Bhi160::new(device(0));
+    bhi.set_coordinate_system(CoordinateSystem::Ned).unwrap();
+    assert_eq!(bhi.interface().regs[0x55], 0x10);
+
+    bhi.suspend().unwrap();
+    assert_eq!(bhi.interface().regs[0x55], 0xB0);
+
+    // A reset clears the flag, starting the firmware restores it
+    bhi.interface_mut().regs[0x55] = 0;
+    bhi.start_cpu().unwrap();
+    assert_eq!(bhi.interface().regs[0x55], 0x10);
+    assert_eq!(bhi.coordinate_system(), Some(CoordinateSystem::Ned));
+}
+
+#[test]
+fn drain_by_interrupt_cause() {
+    let mut bhi = Bhi160::new(device(5));
+    let mut buf = [0; 32];
+    let policy = DrainPolicy::default();
+
+    // Non-wakeup watermark: a single transfer
+    bhi.interface_mut().regs[0x36] = 0x11;
+    let (_, progress) = bhi.drain_on_interrupt(&mut buf, &policy, |_| {}).unwrap();
+    assert_eq!(progress.events, 2);
+    assert!(!progress.complete);
+
+    // Non-wakeup latency: everything
+    bhi.interface_mut().regs[0x36] = 0x21;
+    let (_, progress) = bhi.drain_on_interrupt(&mut buf, &policy, |_| {}).unwrap();
+    assert_eq!(progress.events, 3);
+    assert!(progress.complete);
+
+    // Latency with the single transfer policy
+    let mut bhi = Bhi160::new(device(5));
+    bhi.interface_mut().regs[0x36] = 0x21;
+    let policy = DrainPolicy::single_transfer();
+    let (_, progress) = bhi.drain_on_interrupt(&mut buf, &policy, |_| {}).unwrap();
+    assert_eq!((progress.events, progress.bytes), (2, 16));
+    assert!(!progress.complete);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn capture_error_report() {
+    let mut device = device(1);
+    // Error meta event with error code 0x21 and debug state 3
+    device.fifo.extend([254, 4, 0x21, 3]);
+    device.regs[0x50] = 0x21;
+    device.regs[0x53] = 3;
+    device.params.insert((1, 31), vec![0; 15]);
+    let mut bhi = Bhi160::new(device);
+    let mut reports = Vec::new();
+    let progress = bhi
+        .drain_fifo_diagnosed(&mut [0; 32], Throttle::unlimited(), |_, report| {
+            reports.push(report.copied())
+        })
+        .unwrap();
+    assert!(progress.complete);
+    assert_eq!(progress.events, 2);
+    assert_eq!(reports[0], None);
+    let report = reports[1].unwrap();
+    assert_eq!((report.event_error, report.event_debug_state), (0x21, 3));
+    assert_eq!(report.error.0, 0x21);
+    assert_eq!(report.debug_state.0, 3);
+}
+
+#[test]
+fn support_bundle() {
+    let mut device = device(1);
+    // Five error meta events, only the last four are kept
+    for error in 0x20..0x25 {
+        device.fifo.extend([254, 4, error, 3]);
+    }
+    device.regs[0x50] = 0x24;
+    device.params.insert((3, 1), vec![1, 1]);
+    let mut bhi = Bhi160::new(device);
+    bhi.drain_fifo(&mut [0; 32], usize::MAX, |_| {}).unwrap();
+
+    let bundle = bhi.support_bundle().unwrap();
+    assert_eq!(bundle.format_version, SupportBundle::FORMAT_VERSION);
+    assert!(bundle.capabilities.has_sensor(SensorId::Accelerometer));
+    assert_eq!(bundle.registers.error, 0x24);
+    assert_eq!(bundle.recent_errors.total(), 5);
+    assert_eq!(
+        bundle.recent_errors.iter().collect::<Vec<_>>(),
+        [(0x21, 3), (0x22, 3), (0x23, 3), (0x24, 3)]
+    );
+
+    #[cfg(feature = "serde")]
+    {
+        let bytes = postcard::to_allocvec(&bundle).unwrap();
+        assert_eq!(
+            postcard::from_bytes::<SupportBundle>(&bytes).unwrap(),
+            bundle
+        );
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn read_events_vec() {
+    let mut bhi = Bhi160::new(device(5));
+    assert_eq!(bhi.read_events_vec().unwrap().len(), 2);
+    assert_eq!(bhi.read_fifo_vec().unwrap(), ACCEL.repeat(2));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn self_test_report() {
+    let mut device = device(1);
+    // Accelerometer passed, gyroscope failed on the z axis
+    device.fifo.extend([254, 15, 1, 0, 254, 15, 4, 0b100]);
+    let mut bhi = Bhi160::new(device);
+    let report = bhi
+        .self_test(
+            &[
+                SensorId::Accelerometer,
+                SensorId::Gyroscope,
+                SensorId::GeomagneticField,
+            ],
+            &mut [0; 32],
+            |_| {},
+        )
+        .unwrap();
+
+    assert!(report.get(SensorId::Accelerometer).unwrap().passed());
+    let gyro = report.get(SensorId::Gyroscope).unwrap();
+    assert!(gyro.z_failed() && !gyro.x_failed());
+    assert_eq!(report.missing, [SensorId::GeomagneticField]);
+    assert!(!report.passed());
+    assert_eq!(report.failed().count(), 1);
+
+    // The meta event was enabled, the self-test requested with the standby and cleared again
+    assert_eq!(bhi.interface().param_writes[0].window[3], 0x20);
+    assert!(bhi.interface().writes.contains(&(0x55, vec![0x41])));
+    assert_eq!(bhi.interface().regs[0x55], 0);
+}
+
+#[test]
+fn interrupts_masked() {
+    let mut bhi = Bhi160::new(device(1));
+    bhi.interface_mut().regs[0x55] = HostInterfaceControl::new()
+        .with_wakeup_fifo_host_interrupt_disable(true)
+        .into_bytes()[0];
+    let mut events = 0;
+    bhi.with_interrupts_masked(|bhi| {
+        let control = HostInterfaceControl::from_bytes([bhi.interface().regs[0x55]]);
+        assert!(control.wakeup_fifo_host_interrupt_disable());
+        assert!(control.non_wakeup_fifo_host_interrupt_disable());
+        bhi.update_reg(|reg: HostInterfaceControl| reg.with_ap_suspended(true))
+            .unwrap();
+        bhi.drain_fifo(&mut [0; 32], usize::MAX, |_| events += 1)
+            .unwrap();
+    })
+    .unwrap();
+    assert_eq!(events, 1);
+    // Only the interrupt disable bits are restored
+    let control = HostInterfaceControl::from_bytes([bhi.interface().regs[0x55]]);
+    assert!(control.wakeup_fifo_host_interrupt_disable());
+    assert!(!control.non_wakeup_fifo_host_interrupt_disable());
+    assert!(control.ap_suspended());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sync_drain_and_configure() {
+    use bhi160::{parameters::sensors::SensorConfig, sync::SyncBhi160};
+
+    let bhi = SyncBhi160::new(Bhi160::new(device(50)));
+    let mut events = 0;
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for rate in 1..=20 {
+                bhi.configure_sensor(
+                    SensorId::Gyroscope,
+                    SensorConfig::new().with_sample_rate(rate),
+                )
+                .unwrap();
+            }
+        });
+        let progress = bhi.drain_fifo(&mut [0; 32], |_| events += 1).unwrap();
+        assert!(progress.complete);
+        assert_eq!(progress.bytes, 50 * ACCEL.len());
+    });
+    assert_eq!(events, 50);
+    assert_eq!(bhi.into_inner().interface().param_writes.len(), 20);
+}
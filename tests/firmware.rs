@@ -0,0 +1,154 @@
+#![allow(incomplete_features)]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
+
+mod mock;
+
+use bhi160::{
+    firmware::{Firmware, Segment},
+    Bhi160, Error,
+};
+use mock::{BusError, MockBhi};
+
+#[cfg(all(feature = "heatshrink", feature = "alloc"))]
+use bhi160::firmware::CompressedFirmware;
+
+fn firmware() -> Firmware<Vec<u8>> {
+    let mut blob = vec![0x2A, 0x65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0];
+    blob.extend(0..32);
+    Firmware::new(blob).unwrap()
+}
+
+#[test]
+fn verify_readback() {
+    let firmware = firmware();
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let mut upload = bhi.begin_upload().unwrap();
+    let body: Vec<_> = firmware.body().collect();
+    for chunk in body.chunks(16) {
+        upload.write_chunk(chunk).unwrap();
+    }
+    assert_eq!(upload.verify(&firmware), Ok(true));
+    upload.finish(0).unwrap();
+
+    bhi.interface_mut().ram[17] ^= 0xFF;
+    let mut upload = bhi.begin_upload().unwrap();
+    assert_eq!(upload.verify(&firmware), Ok(false));
+}
+
+#[test]
+fn upload_segments() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let segments = [
+        Segment::new(0x0000, vec![1; 20]),
+        Segment::new(0x0100, vec![2; 8]),
+    ];
+    let mut upload = bhi.begin_upload().unwrap();
+    for segment in &segments {
+        upload.upload_segment(segment).unwrap();
+    }
+    upload.finish(0).unwrap();
+    let ram = &bhi.interface().ram;
+    assert_eq!(ram.len(), 0x108);
+    assert_eq!(&ram[..20], &[1; 20]);
+    assert!(ram[20..0x100].iter().all(|&byte| byte == 0));
+    assert_eq!(&ram[0x100..], &[2; 8]);
+    assert_eq!(bhi.interface().regs[0x34] & 0x03, 0x00);
+}
+
+#[test]
+fn upload_in_chunks() {
+    let mut blob = vec![0x2A, 0x65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 36, 0, 0, 0];
+    blob.extend(0..36);
+    let firmware = Firmware::new(blob).unwrap();
+    let chunks: Vec<_> = firmware.body_chunks().collect();
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0][..8], [3, 2, 1, 0, 7, 6, 5, 4]);
+    assert_eq!(
+        firmware.body_remainder().collect::<Vec<_>>(),
+        [35, 34, 33, 32]
+    );
+
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let mut upload = bhi.begin_upload().unwrap();
+    upload.upload_firmware(&firmware).unwrap();
+    assert_eq!(upload.verify(&firmware), Ok(true));
+    upload.finish(0).unwrap();
+    assert_eq!(bhi.interface().ram, firmware.body().collect::<Vec<_>>());
+}
+
+#[cfg(all(feature = "heatshrink", feature = "alloc"))]
+#[test]
+fn upload_compressed() {
+    let mut blob = vec![
+        0x2A, 0x65, 0, 0, 0x78, 0x56, 0x34, 0x12, 0, 0, 0, 0, 0, 1, 0, 0,
+    ];
+    blob.extend((0..256u16).map(|i| (i % 20) as u8));
+    let firmware = Firmware::new(blob).unwrap();
+    let compressed = CompressedFirmware::compress(&firmware);
+    assert!(compressed.as_bytes().len() < 100);
+    let compressed = CompressedFirmware::new(compressed.as_bytes()).unwrap();
+    assert_eq!(compressed.info(), firmware.info());
+    assert!(compressed.body().eq(firmware.body()));
+
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let mut upload = bhi.begin_upload().unwrap();
+    upload.upload_compressed_firmware(&compressed).unwrap();
+    upload.finish(0).unwrap();
+    assert_eq!(bhi.interface().ram, firmware.body().collect::<Vec<_>>());
+
+    // The body is truncated
+    let bytes = compressed.as_bytes();
+    assert!(CompressedFirmware::new(&bytes[..bytes.len() - 4]).is_none());
+}
+
+#[test]
+fn upload_guard() {
+    let firmware = firmware();
+    let mut bhi = Bhi160::new(MockBhi::new());
+    bhi.interface_mut().regs[0x97..0x9B].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+
+    let mut upload = bhi.begin_upload().unwrap();
+    assert_eq!(upload.upload_firmware(&firmware), Ok(0x1234_5678));
+    assert_eq!(upload.verify(&firmware), Ok(true));
+    assert_eq!(
+        upload.finish(firmware.crc()),
+        Err(Error::CrcMismatch {
+            expected: 0,
+            actual: 0x1234_5678
+        })
+    );
+    // The upload mode is left even if the CRC does not match
+    assert_eq!(bhi.interface().regs[0x34] & 0x03, 0x00);
+    assert_eq!(bhi.interface().ram, firmware.body().collect::<Vec<_>>());
+
+    let mut upload = bhi.begin_upload().unwrap();
+    upload.upload_segment(&Segment::new(0x10, [1; 4])).unwrap();
+    assert_eq!(upload.finish(0x1234_5678), Ok(()));
+    assert_eq!(bhi.interface().regs[0x34] & 0x03, 0x00);
+
+    // Dropping the guard leaves the upload mode
+    drop(bhi.begin_upload().unwrap());
+    assert_eq!(bhi.interface().regs[0x34] & 0x03, 0x00);
+
+    bhi.interface_mut().regs[0x34] = 0x01;
+    assert!(matches!(bhi.begin_upload(), Err(Error::InvalidState)));
+}
+
+#[test]
+fn upload_guard_crc_read_fails() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    bhi.interface_mut().failing_reads.insert(0x97);
+    let upload = bhi.begin_upload().unwrap();
+    assert_eq!(upload.finish(0), Err(Error::Interface(BusError)));
+    // The upload mode is left nevertheless
+    assert_eq!(bhi.interface().regs[0x34] & 0x03, 0x00);
+}
+
+#[test]
+fn verify_requires_upload_mode() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    assert_eq!(
+        bhi.verify_uploaded_firmware(&firmware()),
+        Err(Error::InvalidState)
+    );
+}
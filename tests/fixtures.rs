@@ -0,0 +1,29 @@
+#![cfg(feature = "std")]
+
+use bhi160::{
+    fixture::assert_dump,
+    packet::{Event, MetaEvent, SensorData, SensorStatus, Vector},
+    parameters::sensors::SensorId,
+};
+
+#[test]
+fn accel_meta() {
+    assert_dump(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/accel_meta.hex"),
+        &[
+            Event::new(
+                SensorId::MetaEvent,
+                SensorData::MetaEvent(MetaEvent::SampleRateChanged(SensorId::Accelerometer)),
+            ),
+            Event::new(SensorId::TimestampLsw, SensorData::Scalar(0x1234)),
+            Event::new(
+                SensorId::Accelerometer,
+                SensorData::VectorStatus(Vector([-2, 5, 2153]), SensorStatus::Medium),
+            ),
+            Event::new(
+                SensorId::Accelerometer,
+                SensorData::VectorStatus(Vector([3, -1, 2150]), SensorStatus::High),
+            ),
+        ],
+    );
+}
@@ -0,0 +1,209 @@
+//! Smoke tests against a real BHI160(B) connected to a Linux I2C bus.
+//!
+//! The tests are only built with the `hil-tests` feature and ignored by default. They are configured by
+//! environment variables:
+//! * `BHI160_I2C`: the I2C device, e.g. `/dev/i2c-1`
+//! * `BHI160_I2C_ADDR`: the address of the BHI (default `0x28`)
+//! * `BHI160_FIRMWARE`: the path of the firmware file matching the chip
+//!
+//! ```text
+//! BHI160_I2C=/dev/i2c-1 BHI160_FIRMWARE=Bosch_PCB_7183_di03_BMI160-7183_di03.2.1.11696.fw \
+//!     cargo test --features hil-tests --test hil -- --ignored --test-threads=1
+//! ```
+#![cfg(feature = "hil-tests")]
+#![allow(incomplete_features)]
+#![cfg_attr(feature = "nightly", feature(generic_const_exprs))]
+
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    os::fd::AsRawFd,
+    thread,
+    time::{Duration, Instant},
+};
+
+use bhi160::{
+    consts,
+    firmware::Firmware,
+    interface::{I2c, I2C_ADDR1},
+    packet::MetaEvent,
+    parameters::sensors::{SensorConfig, SensorId},
+    registers::{ProductId, ResetRequest},
+    Bhi160,
+};
+use embedded_hal::i2c::{ErrorKind, ErrorType, Operation};
+
+/// `I2C_RDWR` of the Linux i2c-dev interface.
+const I2C_RDWR: u64 = 0x0707;
+const I2C_M_RD: u16 = 0x0001;
+
+#[repr(C)]
+struct I2cMsg {
+    addr: u16,
+    flags: u16,
+    len: u16,
+    buf: *mut u8,
+}
+
+#[repr(C)]
+struct I2cRdwrData {
+    msgs: *mut I2cMsg,
+    nmsgs: u32,
+}
+
+extern "C" {
+    fn ioctl(fd: i32, request: u64, ...) -> i32;
+}
+
+/// A minimal `embedded-hal` implementation for Linux i2c-dev devices.
+struct LinuxI2c(File);
+
+#[derive(Debug)]
+struct LinuxI2cError(#[allow(dead_code)] std::io::Error);
+
+impl embedded_hal::i2c::Error for LinuxI2cError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+impl ErrorType for LinuxI2c {
+    type Error = LinuxI2cError;
+}
+
+impl embedded_hal::i2c::I2c for LinuxI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // Adjacent writes are merged, i2c-dev would otherwise issue a repeated start between them
+        let mut write_buf = Vec::new();
+        let mut msgs = Vec::new();
+        for operation in operations.iter_mut() {
+            match operation {
+                Operation::Write(data) => write_buf.extend_from_slice(data),
+                Operation::Read(buf) => {
+                    if !write_buf.is_empty() {
+                        msgs.push((0, std::mem::take(&mut write_buf)));
+                    }
+                    msgs.push((I2C_M_RD, vec![0; buf.len()]));
+                }
+            }
+        }
+        if !write_buf.is_empty() {
+            msgs.push((0, write_buf));
+        }
+        let mut raw: Vec<_> = msgs
+            .iter_mut()
+            .map(|(flags, buf)| I2cMsg {
+                addr: address as u16,
+                flags: *flags,
+                len: buf.len() as u16,
+                buf: buf.as_mut_ptr(),
+            })
+            .collect();
+        let mut data = I2cRdwrData {
+            msgs: raw.as_mut_ptr(),
+            nmsgs: raw.len() as u32,
+        };
+        if unsafe { ioctl(self.0.as_raw_fd(), I2C_RDWR, &mut data) } < 0 {
+            return Err(LinuxI2cError(std::io::Error::last_os_error()));
+        }
+        let mut reads = msgs.into_iter().filter(|(flags, _)| *flags == I2C_M_RD);
+        for operation in operations.iter_mut() {
+            if let Operation::Read(buf) = operation {
+                buf.copy_from_slice(&reads.next().unwrap().1);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn env_var(name: &str) -> String {
+    env::var(name).unwrap_or_else(|_| panic!("{name} is not set, see tests/hil.rs"))
+}
+
+fn connect() -> Bhi160<I2c<LinuxI2c>> {
+    let device = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(env_var("BHI160_I2C"))
+        .expect("cannot open the I2C device");
+    let addr = match env::var("BHI160_I2C_ADDR") {
+        Ok(addr) => u8::from_str_radix(addr.trim_start_matches("0x"), 16).expect("invalid address"),
+        Err(_) => I2C_ADDR1,
+    };
+    Bhi160::new(I2c::new(LinuxI2c(device), addr))
+}
+
+/// Resets the BHI, uploads the firmware and waits until it is initialized.
+fn boot() -> Bhi160<I2c<LinuxI2c>> {
+    let mut bhi = connect();
+    let ProductId(id) = bhi.read_reg().unwrap();
+    assert_eq!(id, 0x83, "unexpected product id");
+
+    bhi.write_reg(ResetRequest).unwrap();
+    thread::sleep(Duration::from_millis(consts::bhi160::RESET_TIME_MS as u64));
+
+    let data = std::fs::read(env_var("BHI160_FIRMWARE")).unwrap();
+    let firmware = Firmware::new(data).expect("invalid firmware file");
+    let mut upload = bhi.begin_upload().unwrap();
+    upload.upload_firmware(&firmware).unwrap();
+    upload.finish(firmware.crc()).unwrap();
+    bhi.start_cpu().unwrap();
+
+    let mut buf = [0; 64];
+    let deadline =
+        Instant::now() + 2 * Duration::from_millis(consts::bhi160::FIRMWARE_START_TIME_MS as u64);
+    let mut initialized = false;
+    while !initialized && Instant::now() < deadline {
+        bhi.drain_fifo(&mut buf, usize::MAX, |event| {
+            initialized |= matches!(event.meta_event(), Some(MetaEvent::Initialized(_)));
+        })
+        .unwrap();
+    }
+    assert!(initialized, "the firmware did not report Initialized");
+    bhi
+}
+
+#[test]
+#[ignore]
+fn upload_firmware() {
+    let mut bhi = boot();
+    let identity = bhi.firmware_identity().unwrap();
+    println!("Running {identity:?}");
+}
+
+#[test]
+#[ignore]
+fn scan_sensors() {
+    let mut bhi = boot();
+    let capabilities = *bhi.capabilities().unwrap();
+    println!("{capabilities:#?}");
+    assert!(capabilities.has_sensor(SensorId::Accelerometer));
+}
+
+#[test]
+#[ignore]
+fn stream_accel() {
+    let mut bhi = boot();
+    bhi.configure_sensor(
+        SensorId::Accelerometer,
+        SensorConfig::new().with_sample_rate(100),
+    )
+    .unwrap();
+
+    let mut buf = [0; 256];
+    let mut samples = 0;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(1) {
+        bhi.drain_fifo(&mut buf, usize::MAX, |event| {
+            samples += event.accel().is_some() as usize;
+        })
+        .unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+    // Leave some slack for the start-up of the sensor
+    assert!(samples >= 50, "only {samples} samples in 1 s");
+}
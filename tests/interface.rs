@@ -0,0 +1,66 @@
+use std::convert::Infallible;
+
+use bhi160::interface::{I2c, I2cError, Interface};
+use embedded_hal::i2c::{ErrorType, Operation};
+
+/// Records the writes of each transaction.
+#[derive(Debug, Default)]
+struct RecordingBus {
+    transactions: Vec<Vec<u8>>,
+}
+
+impl ErrorType for RecordingBus {
+    type Error = Infallible;
+}
+
+impl embedded_hal::i2c::I2c for RecordingBus {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut written = Vec::new();
+        for operation in operations.iter_mut() {
+            match operation {
+                Operation::Write(data) => written.extend_from_slice(data),
+                Operation::Read(buf) => buf.fill(0),
+            }
+        }
+        self.transactions.push(written);
+        Ok(())
+    }
+}
+
+#[test]
+fn split_upload_writes() {
+    let mut i2c = I2c::new(RecordingBus::default(), 0x28).with_max_write_len(11);
+    let data: Vec<u8> = (0..20).collect();
+    i2c.write(0x96, &data).unwrap();
+    let transactions = i2c.into_inner().transactions;
+    // The data port keeps its address and is split at word boundaries
+    assert_eq!(
+        transactions,
+        [
+            [&[0x96][..], &data[..8]].concat(),
+            [&[0x96][..], &data[8..16]].concat(),
+            [&[0x96][..], &data[16..]].concat(),
+        ]
+    );
+}
+
+#[test]
+fn reject_long_register_writes() {
+    let mut i2c = I2c::new(RecordingBus::default(), 0x28).with_max_write_len(9);
+    assert_eq!(i2c.write(0x5C, &[0; 9]), Err(I2cError::WriteTooLong));
+    i2c.write(0x5C, &[0; 8]).unwrap();
+    assert_eq!(
+        i2c.into_inner().transactions,
+        [[&[0x5C][..], &[0; 8]].concat()]
+    );
+}
+
+#[test]
+#[should_panic]
+fn max_write_len_too_short() {
+    let _ = I2c::new(RecordingBus::default(), 0x28).with_max_write_len(4);
+}
@@ -0,0 +1,189 @@
+//! A simulated BHI for testing the driver without hardware.
+
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use bhi160::interface::Interface;
+
+/// A parameter write as seen by the BHI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamWrite {
+    pub page: u8,
+    pub param: u8,
+    pub size: u8,
+    /// The content of the parameter load window (`0x5C`-`0x63`) when the write was requested.
+    pub window: [u8; 8],
+}
+
+/// The error returned for the reads configured in [`MockBhi::failing_reads`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusError;
+
+/// A register file with just enough behaviour to answer parameter requests and FIFO reads.
+#[derive(Debug, Clone)]
+pub struct MockBhi {
+    pub regs: [u8; 256],
+    /// Values returned for parameter reads, keyed by `(page, param)`.
+    pub params: HashMap<(u8, u8), Vec<u8>>,
+    pub param_writes: Vec<ParamWrite>,
+    /// The number of parameter reads requested.
+    pub param_reads: usize,
+    /// The data returned when reading the FIFO.
+    pub fifo: Vec<u8>,
+    /// The maximum number of bytes announced per transfer.
+    pub max_transfer: usize,
+    /// Every write in the order it was issued.
+    pub writes: Vec<(u8, Vec<u8>)>,
+    /// The RAM patch written through the upload data register.
+    pub ram: Vec<u8>,
+    /// The number of reads of the parameter acknowledge register which return 0 after each request.
+    pub ack_delay: usize,
+    /// Acknowledge parameter reads with an error.
+    pub reject_reads: bool,
+    /// The number of following parameter requests (reads and writes) acknowledged with an error.
+    pub rejections: usize,
+    /// Parameters keyed by `(page, param)` whose requests are always acknowledged with an error.
+    pub rejected: HashSet<(u8, u8)>,
+    /// The writes and reads issued as one transaction by `write_then_read`, as `(write_addr, read_addr)`.
+    pub combined: Vec<(u8, u8)>,
+    /// Register addresses whose reads fail with [`BusError`].
+    pub failing_reads: HashSet<u8>,
+    pending_acks: usize,
+}
+
+impl Default for MockBhi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockBhi {
+    pub fn new() -> Self {
+        Self {
+            regs: [0; 256],
+            params: HashMap::new(),
+            param_writes: Vec::new(),
+            param_reads: 0,
+            fifo: Vec::new(),
+            max_transfer: usize::MAX,
+            writes: Vec::new(),
+            ram: Vec::new(),
+            ack_delay: 0,
+            reject_reads: false,
+            rejections: 0,
+            rejected: HashSet::new(),
+            combined: Vec::new(),
+            failing_reads: HashSet::new(),
+            pending_acks: 0,
+        }
+    }
+
+    fn upload_address(&self) -> usize {
+        u16::from_be_bytes([self.regs[0x94], self.regs[0x95]]) as usize
+    }
+
+    fn set_upload_address(&mut self, addr: usize) {
+        self.regs[0x94..0x96].copy_from_slice(&(addr as u16).to_be_bytes());
+    }
+
+    fn parameter_request(&mut self, request: u8) {
+        let page = self.regs[0x54] & 0x0F;
+        let size = self.regs[0x54] >> 4;
+        let param = request & 0x7F;
+        if request & 0x80 != 0 {
+            let mut window = [0; 8];
+            window.copy_from_slice(&self.regs[0x5C..0x64]);
+            self.param_writes.push(ParamWrite {
+                page,
+                param,
+                size,
+                window,
+            });
+        } else if param != 0 {
+            self.param_reads += 1;
+            let value = self.params.get(&(page, param)).cloned().unwrap_or_default();
+            self.regs[0x3B..0x4B].fill(0);
+            self.regs[0x3B..0x3B + value.len()].copy_from_slice(&value);
+        }
+        let reject = param != 0
+            && (self.rejections > 0
+                || self.reject_reads && request & 0x80 == 0
+                || self.rejected.contains(&(page, param)));
+        self.rejections = self.rejections.saturating_sub((param != 0) as usize);
+        self.regs[0x3A] = if reject { 0x80 } else { request };
+        self.pending_acks = self.ack_delay;
+    }
+}
+
+impl Interface for MockBhi {
+    type Error = BusError;
+
+    fn read<'a>(&mut self, addr: u8, buf: &'a mut [u8]) -> Result<(), Self::Error> {
+        if self.failing_reads.contains(&addr) {
+            return Err(BusError);
+        }
+        if addr == 0x00 {
+            let len = buf.len().min(self.fifo.len());
+            buf[..len].copy_from_slice(&self.fifo[..len]);
+            buf[len..].fill(0);
+            self.fifo.drain(..len);
+            return Ok(());
+        }
+        if addr == 0x96 {
+            let start = self.upload_address();
+            for (i, byte) in buf.iter_mut().enumerate() {
+                *byte = self.ram.get(start + i).copied().unwrap_or(0);
+            }
+            self.set_upload_address(start + buf.len());
+            return Ok(());
+        }
+        if addr == 0x3A && self.pending_acks > 0 {
+            self.pending_acks -= 1;
+            buf.fill(0);
+            return Ok(());
+        }
+        if addr == 0x38 {
+            let remaining = self.fifo.len().min(self.max_transfer) as u16;
+            self.regs[0x38..0x3A].copy_from_slice(&remaining.to_le_bytes());
+        }
+        let addr = addr as usize;
+        buf.copy_from_slice(&self.regs[addr..addr + buf.len()]);
+        Ok(())
+    }
+
+    fn write(&mut self, addr: u8, buf: &[u8]) -> Result<(), Self::Error> {
+        self.writes.push((addr, buf.to_vec()));
+        if addr == 0x96 {
+            let start = self.upload_address();
+            if self.ram.len() < start + buf.len() {
+                self.ram.resize(start + buf.len(), 0);
+            }
+            self.ram[start..start + buf.len()].copy_from_slice(buf);
+            self.set_upload_address(start + buf.len());
+            return Ok(());
+        }
+        let start = addr as usize;
+        self.regs[start..start + buf.len()].copy_from_slice(buf);
+        // Abort transfer discards all pending data
+        if addr == 0x55 && buf[0] & 0x02 != 0 {
+            self.fifo.clear();
+        }
+        if addr == 0x64 {
+            self.parameter_request(buf[0]);
+        }
+        Ok(())
+    }
+
+    fn write_then_read(
+        &mut self,
+        write_addr: u8,
+        data: &[u8],
+        read_addr: u8,
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.combined.push((write_addr, read_addr));
+        self.write(write_addr, data)?;
+        self.read(read_addr, buf)
+    }
+}
@@ -0,0 +1,515 @@
+#![cfg(feature = "nightly")]
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
+
+mod mock;
+
+use std::task::Poll;
+
+use bhi160::{
+    parameters::{
+        custom::{CustomPage, CustomPageError, Direction, ParamDesc},
+        sensors::{SensorConfig, SensorId, SensorKind},
+        system::{FifoControl, MetaEvent, MetaEventControl, MetaEventFifo},
+        Parameter, ParameterPage,
+    },
+    profiles::{self, ConfigStep},
+    registers::{HostInterfaceControl, ResetRequest},
+    scaled::Scaler,
+    Bhi160, Error, ParamPoll,
+};
+use mock::MockBhi;
+
+/// A two byte parameter on the algorithm page.
+struct Short([u8; 2]);
+
+impl Parameter for Short {
+    const PAGE: ParameterPage = ParameterPage::Algorithm;
+    const PARAM: u8 = 5;
+    const SIZE: usize = 2;
+}
+
+impl From<Short> for [u8; 2] {
+    fn from(param: Short) -> Self {
+        param.0
+    }
+}
+
+#[test]
+fn write_full_window() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    bhi.write_param(MetaEventControl::from_bytes([0xFF; 8]))
+        .unwrap();
+    let write = &bhi.interface().param_writes[0];
+    assert_eq!(write.page, 1);
+    assert_eq!(write.param, 1);
+    assert_eq!(write.size, 0);
+    assert_eq!(write.window, [0xFF; 8]);
+}
+
+#[test]
+fn short_write_clears_stale_bytes() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    bhi.write_param(MetaEventControl::from_bytes([0xFF; 8]))
+        .unwrap();
+    bhi.write_param(Short([0x12, 0x34])).unwrap();
+    let write = &bhi.interface().param_writes[1];
+    assert_eq!(write.page, 2);
+    assert_eq!(write.param, 5);
+    assert_eq!(write.size, 2);
+    assert_eq!(write.window, [0x12, 0x34, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn algorithm_page_acknowledged_on_drop() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    {
+        let mut page = bhi.algorithm_page();
+        page.write_param(Short([1, 2])).unwrap();
+    }
+    assert_eq!(bhi.interface().param_writes[0].page, 2);
+    assert_eq!(bhi.interface().writes.last(), Some(&(0x54, vec![0])));
+
+    bhi.algorithm_page().write_param(Short([3, 4])).unwrap();
+    assert_eq!(bhi.interface().regs[0x54], 0);
+}
+
+#[test]
+fn read_param_verbatim() {
+    let mut device = MockBhi::new();
+    device.params.insert((1, 2), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    device.params.insert((13, 5), vec![9, 8, 7]);
+    let mut bhi = Bhi160::new(device);
+
+    let mut buf = [0; 16];
+    assert_eq!(
+        bhi.read_param_into::<FifoControl>(&mut buf),
+        Ok(&[1, 2, 3, 4, 5, 6, 7, 8][..])
+    );
+    assert_eq!(
+        bhi.read_param_into::<FifoControl>(&mut buf[..4]),
+        Err(Error::BufferTooSmall)
+    );
+    bhi.read_param_raw(ParameterPage::Custom13, 5, &mut buf[..3])
+        .unwrap();
+    assert_eq!(buf[..3], [9, 8, 7]);
+    assert_eq!(bhi.interface().regs[0x54], 0x3D);
+}
+
+#[test]
+fn poll_param_yields() {
+    let mut device = MockBhi::new();
+    device.params.insert((1, 2), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    device.ack_delay = 2;
+    let mut bhi = Bhi160::new(device);
+
+    let mut poll = ParamPoll::<FifoControl>::new();
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    assert!(poll.is_pending());
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    let Ok(Poll::Ready(fifo)) = bhi.poll_param(&mut poll) else {
+        panic!("parameter not ready");
+    };
+    assert_eq!(fifo.wakeup_size(), 0x0403);
+    assert!(!poll.is_pending());
+    // Only a single request was issued
+    assert_eq!(bhi.interface().param_reads, 1);
+
+    bhi.interface_mut().reject_reads = true;
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    assert_eq!(
+        bhi.poll_param(&mut poll),
+        Err(Error::ParameterRejected(ParameterPage::System, 2))
+    );
+    assert!(!poll.is_pending());
+    // The parameter interface was parked
+    assert_eq!(bhi.interface().regs[0x64], 0);
+}
+
+#[test]
+fn meta_events_both_fifos() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    let control = MetaEventControl::new().with_event1(MetaEvent::new().with_enable(true));
+    bhi.enable_meta_events(control, MetaEventFifo::Both)
+        .unwrap();
+    let writes = &bhi.interface().param_writes;
+    assert_eq!(writes.len(), 2);
+    assert_eq!((writes[0].page, writes[0].param), (1, 1));
+    assert_eq!((writes[1].page, writes[1].param), (1, 29));
+    assert_eq!(writes[1].window, control.into_bytes());
+
+    bhi.enable_meta_events(control, MetaEventFifo::Wakeup)
+        .unwrap();
+    let writes = &bhi.interface().param_writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[2].param, 29);
+}
+
+#[test]
+fn wake_on_motion() {
+    let mut bhi = Bhi160::new(MockBhi::new());
+    assert_eq!(
+        bhi.configure_wake_on_motion(SensorKind::SignificantMotion),
+        Ok(SensorId::SignificantMotionWakeup)
+    );
+    let writes = &bhi.interface().param_writes;
+    assert_eq!((writes[0].page, writes[0].param), (1, 29));
+    assert_eq!(writes[0].window, [0xC0, 0, 0x30, 0, 0, 0, 0, 0]);
+    assert_eq!((writes[1].page, writes[1].param), (3, 49 + 64));
+    assert_eq!(writes[1].window[..2], [1, 0]);
+    assert_eq!(bhi.interface().regs[0x55], 0xA0);
+}
+
+#[test]
+fn custom_page() {
+    let mut page = CustomPage::<2>::new(ParameterPage::Custom13).unwrap();
+    page.register(ParamDesc::new(3, 4, Direction::ReadWrite))
+        .unwrap();
+    page.register(ParamDesc::new(7, 12, Direction::Read))
+        .unwrap();
+    assert_eq!(
+        page.register(ParamDesc::new(3, 2, Direction::Read)),
+        Err(CustomPageError::Duplicate)
+    );
+    assert_eq!(
+        page.register(ParamDesc::new(9, 2, Direction::Read)),
+        Err(CustomPageError::Full)
+    );
+
+    let mut device = MockBhi::new();
+    device.params.insert((13, 7), (0..12).collect());
+    let mut bhi = Bhi160::new(device);
+
+    page.write(&mut bhi, 3, &[1, 2, 3, 4]).unwrap();
+    let write = &bhi.interface().param_writes[0];
+    assert_eq!((write.page, write.param, write.size), (13, 3, 4));
+    assert_eq!(page.write(&mut bhi, 7, &[0; 12]), Err(Error::Unsupported));
+    assert_eq!(page.write(&mut bhi, 3, &[0; 2]), Err(Error::Unsupported));
+
+    let mut buf = [0; 16];
+    let data = page.read(&mut bhi, 7, &mut buf).unwrap();
+    assert_eq!(data, (0..12).collect::<Vec<u8>>());
+    assert_eq!(
+        page.read(&mut bhi, 7, &mut [0; 4]),
+        Err(Error::BufferTooSmall)
+    );
+}
+
+#[test]
+fn rejected_requests_are_retried() {
+    let mut device = MockBhi::new();
+    device
+        .params
+        .insert((1, 2), vec![0, 0, 0x00, 0x08, 0, 0, 0x00, 0x10]);
+    device.rejections = 2;
+    let mut bhi = Bhi160::new(device);
+    let fifo: FifoControl = bhi.read_param().unwrap();
+    assert_eq!(fifo.wakeup_size(), 0x0800);
+    assert_eq!(bhi.interface().param_reads, 3);
+    // The parameter interface was parked after each rejection
+    let parks = bhi.interface().writes.iter();
+    assert_eq!(parks.filter(|write| *write == &(0x64, vec![0])).count(), 2);
+
+    bhi.interface_mut().rejections = 1;
+    bhi.write_param(FifoControl::new().with_wakeup_watermark(16))
+        .unwrap();
+    assert_eq!(bhi.interface().param_writes.len(), 2);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn bus_metrics() {
+    let mut device = MockBhi::new();
+    device
+        .params
+        .insert((1, 2), vec![0, 0, 0x00, 0x08, 0, 0, 0x00, 0x10]);
+    device.rejections = 1;
+    let mut bhi = Bhi160::new(device);
+    let _: FifoControl = bhi.read_param().unwrap();
+    let metrics = bhi.metrics();
+    assert_eq!(metrics.parameter_reads, 1);
+    assert_eq!(metrics.parameter_rejections, 1);
+    assert_eq!(metrics.acknowledge_polls, 2);
+    // At least the acknowledgments and the value
+    assert!(metrics.register_bytes_read >= 2 + 8);
+    assert_eq!(metrics.fifo_reads, 0);
+
+    let before = bhi.metrics();
+    bhi.write_param(FifoControl::new()).unwrap();
+    let usage = bhi.metrics().since(&before);
+    assert_eq!(usage.parameter_writes, 1);
+    assert_eq!(usage.parameter_reads, 0);
+    assert_eq!(
+        usage.total_bytes(),
+        usage.register_bytes_read + usage.register_bytes_written
+    );
+
+    bhi.reset_metrics();
+    assert_eq!(bhi.metrics(), Default::default());
+}
+
+#[test]
+fn request_and_acknowledge_combined() {
+    let mut device = MockBhi::new();
+    device.ack_delay = 2;
+    device.rejections = 1;
+    let mut bhi = Bhi160::new(device);
+    let _: FifoControl = bhi.read_param().unwrap();
+    // The rejected request, parking the interface and the repeated request
+    assert_eq!(bhi.interface().combined, [(0x64, 0x3A); 3]);
+    let requests = bhi.interface().writes.iter();
+    assert_eq!(requests.filter(|write| write.0 == 0x64).count(), 3);
+
+    let mut poll = ParamPoll::<FifoControl>::new();
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    assert_eq!(bhi.poll_param(&mut poll), Ok(Poll::Pending));
+    assert_eq!(bhi.interface().combined.len(), 4);
+}
+
+#[test]
+fn unacknowledged_request() {
+    let mut device = MockBhi::new();
+    device.ack_delay = usize::MAX;
+    let mut bhi = Bhi160::new(device);
+    assert_eq!(bhi.read_param::<FifoControl>(), Err(Error::InvalidState));
+}
+
+#[test]
+fn slow_acknowledge() {
+    let mut device = MockBhi::new();
+    device.ack_delay = 150;
+    let mut bhi = Bhi160::new(device);
+    assert_eq!(bhi.read_param::<FifoControl>(), Err(Error::InvalidState));
+    bhi.set_ack_poll_limit(200);
+    assert!(bhi.read_param::<FifoControl>().is_ok());
+}
+
+#[test]
+fn rejected_requests_fail_eventually() {
+    let mut device = MockBhi::new();
+    device.reject_reads = true;
+    let mut bhi = Bhi160::new(device);
+    assert_eq!(
+        bhi.read_param::<FifoControl>(),
+        Err(Error::ParameterRejected(ParameterPage::System, 2))
+    );
+    assert_eq!(bhi.interface().param_reads, 1 + bhi160::PARAMETER_RETRIES);
+
+    bhi.interface_mut().rejections = usize::MAX;
+    assert_eq!(
+        bhi.write_param(FifoControl::new()),
+        Err(Error::ParameterRejected(ParameterPage::System, 2))
+    );
+}
+
+#[test]
+fn scaler_readback() {
+    let mut device = MockBhi::new();
+    // Accelerometer: 100 Hz, 4 g; gyroscope off; magnetometer: 25 Hz, 2500 µT
+    device.params.insert(
+        (1, 31),
+        vec![100, 0, 4, 0, 0, 0, 0, 0, 0, 0, 25, 0, 0xC4, 0x09, 0],
+    );
+    let mut bhi = Bhi160::new(device);
+    let scaler = bhi.scaler().unwrap();
+    assert_eq!(
+        scaler.scale_of(SensorId::GeomagneticField),
+        Some(2500.0 / 32768.0)
+    );
+    assert_eq!(
+        scaler.scale_of(SensorId::Accelerometer),
+        Some(4.0 * 9.80665 / 32768.0)
+    );
+    assert_eq!(
+        scaler.scale_of(SensorId::Gyroscope),
+        Scaler::new().scale_of(SensorId::Gyroscope)
+    );
+}
+
+#[test]
+fn capabilities_scan() {
+    let mut device = MockBhi::new();
+    // Accelerometer and its wakeup variant with driver id 1
+    device.params.insert((3, 1), vec![1, 1]);
+    device.params.insert((3, 33), vec![33, 1]);
+    device
+        .params
+        .insert((1, 2), vec![0, 0, 0x00, 0x08, 0, 0, 0x00, 0x10]);
+    let mut bhi = Bhi160::new(device);
+
+    let capabilities = *bhi.capabilities().unwrap();
+    assert!(capabilities.has_sensor(SensorId::Accelerometer));
+    assert!(!capabilities.has_sensor(SensorId::Gyroscope));
+    assert!(capabilities.supports_wakeup());
+    assert_eq!(capabilities.wakeup_fifo_size, 0x0800);
+    assert_eq!(capabilities.non_wakeup_fifo_size, 0x1000);
+
+    // Cached until the chip is reset
+    let requests = bhi.interface().param_reads;
+    bhi.capabilities().unwrap();
+    assert_eq!(bhi.interface().param_reads, requests);
+    bhi.write_reg(ResetRequest).unwrap();
+    bhi.capabilities().unwrap();
+    assert!(bhi.interface().param_reads > requests);
+}
+
+#[test]
+fn sensor_info_table() {
+    let mut device = MockBhi::new();
+    // Accelerometer: BMI160 v2, 1-800 Hz, 10/200 events of 8 bytes
+    device.params.insert(
+        (3, 1),
+        vec![1, 1, 2, 3, 16, 0, 16, 0, 0x20, 0x03, 10, 0, 200, 0, 8, 1],
+    );
+    device
+        .params
+        .insert((1, 2), vec![0, 0, 0x00, 0x08, 0, 0, 0x00, 0x10]);
+    let mut bhi = Bhi160::new(device);
+    let mut table = String::new();
+    bhi.print_all_sensor_info(&mut table).unwrap();
+    let lines: Vec<_> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with(" ID  Sensor"));
+    assert_eq!(
+        lines[1],
+        "  1  Accelerometer                       0x01 v2   BMI160 Accelerometer     1 - 800     10/200        8"
+    );
+    assert_eq!(lines[2], "FIFO: 2048 bytes wakeup, 4096 bytes non-wakeup");
+}
+
+#[test]
+fn best_of_fallback() {
+    let mut device = MockBhi::new();
+    // Game rotation vector and orientation, but no rotation vector (e.g. a firmware without magnetometer)
+    device.params.insert((3, 15), vec![15, 1]);
+    device.params.insert((3, 3), vec![3, 1]);
+    let mut bhi = Bhi160::new(device);
+
+    let config = SensorConfig::new().with_sample_rate(50);
+    assert_eq!(
+        bhi.enable_best_of(&SensorKind::ORIENTATION_FALLBACK, config),
+        Ok(SensorKind::GameRotationVector)
+    );
+    let write = bhi.interface().param_writes.last().unwrap();
+    assert_eq!((write.page, write.param), (3, 15 + 64));
+    assert_eq!(write.window[..2], [50, 0]);
+
+    assert_eq!(
+        bhi.enable_best_of(&[SensorKind::RotationVector], config),
+        Err(Error::Unsupported)
+    );
+}
+
+#[test]
+fn apply_profile() {
+    let mut device = MockBhi::new();
+    device.params.insert((3, 19), vec![19, 1]);
+    let mut bhi = Bhi160::new(device);
+    let err = bhi.apply_config(&profiles::WEARABLE).unwrap_err();
+    assert_eq!(err.step, ConfigStep::Check);
+    assert_eq!(err.error, Error::Unsupported);
+    assert!(bhi.interface().param_writes.is_empty());
+
+    bhi.interface_mut().params.insert((3, 55), vec![55, 1]);
+    bhi.refresh_capabilities().unwrap();
+    bhi.apply_config(&profiles::WEARABLE).unwrap();
+    let writes = &bhi.interface().param_writes;
+    assert_eq!((writes[0].page, writes[0].param), (3, 19 + 64));
+    assert_eq!(writes[0].window[..4], [1, 0, 0x60, 0xEA]);
+    assert_eq!((writes[1].page, writes[1].param), (3, 55 + 64));
+    assert!(HostInterfaceControl::from_bytes([bhi.interface().regs[0x55]]).ap_suspended());
+}
+
+#[test]
+fn apply_profile_rollback() {
+    let mut device = MockBhi::new();
+    device.params.insert((3, 19), vec![19, 1]);
+    device.params.insert((3, 55), vec![55, 1]);
+    device.rejected.insert((3, 55 + 64));
+    let mut bhi = Bhi160::new(device);
+    let err = bhi.apply_config(&profiles::WEARABLE).unwrap_err();
+    assert_eq!(err.step, ConfigStep::Sensor(1));
+    assert_eq!(
+        err.error,
+        Error::ParameterRejected(ParameterPage::Sensors, 55 + 64)
+    );
+    assert_eq!(err.rollback_error, None);
+    // The step counter was enabled and disabled again, the suspend state was not touched
+    let writes = &bhi.interface().param_writes;
+    let last = writes.last().unwrap();
+    assert_eq!((last.page, last.param), (3, 19 + 64));
+    assert_eq!(last.window, [0; 8]);
+    assert_eq!(writes[0].window[..4], [1, 0, 0x60, 0xEA]);
+    assert!(!HostInterfaceControl::from_bytes([bhi.interface().regs[0x55]]).ap_suspended());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn fifo_reservations() {
+    let mut device = MockBhi::new();
+    // Gyroscope with 100 reserved events, significant motion (wakeup) sharing the FIFO
+    let mut gyro = vec![4, 1, 1, 9, 0, 0, 0, 0, 0, 0];
+    gyro.extend([100, 0, 0x10, 0x04, 8, 1]);
+    device.params.insert((3, 4), gyro);
+    device.params.insert(
+        (3, 49),
+        vec![49, 9, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x80, 0x01, 2, 1],
+    );
+    let mut bhi = Bhi160::new(device);
+
+    let budget = bhi
+        .fifo_budget(&[
+            SensorId::Gyroscope,
+            SensorId::Accelerometer,
+            SensorId::SignificantMotionWakeup,
+        ])
+        .unwrap();
+    assert_eq!(budget.reservations.len(), 2);
+    let gyro = budget.get(SensorId::Gyroscope).unwrap();
+    assert_eq!(gyro.reserved_bytes(), 800);
+    assert_eq!(gyro.max_bytes(), 0x410 * 8);
+    assert_eq!(
+        gyro.reserved_duration(200),
+        Some(std::time::Duration::from_millis(500))
+    );
+    assert_eq!(budget.reserved_bytes(false), 800);
+    assert_eq!(budget.reserved_bytes(true), 0);
+    assert_eq!(
+        budget.shared().map(|r| r.id).collect::<Vec<_>>(),
+        [SensorId::SignificantMotionWakeup]
+    );
+}
+
+#[test]
+fn wakeup_sensor_info() {
+    let mut device = MockBhi::new();
+    // The wakeup gyroscope reported with the Sensor Type of the non-wakeup variant
+    device.params.insert((3, 36), vec![4, 1, 2, 3]);
+    device.params.insert((3, 4), vec![4, 1, 2, 3]);
+    device.params.insert((3, 33), vec![33, 1]);
+    device.params.insert((3, 34), vec![1, 1]);
+    let mut bhi = Bhi160::new(device);
+
+    let info = bhi.sensor_info(SensorId::GyroscopeWakeup).unwrap();
+    assert_eq!(info.sensor_type(), SensorId::GyroscopeWakeup);
+    assert_eq!(info.sensor_type_raw(), 36);
+    assert_eq!((info.driver_id(), info.driver_version()), (1, 2));
+
+    let info = bhi.sensor_info(SensorId::Gyroscope).unwrap();
+    assert_eq!(info.sensor_type(), SensorId::Gyroscope);
+    let info = bhi.sensor_info(SensorId::AccelerometerWakeup).unwrap();
+    assert_eq!(info.sensor_type(), SensorId::AccelerometerWakeup);
+    // Not present
+    let info = bhi.sensor_info(SensorId::OrientationWakeup).unwrap();
+    assert_eq!(info.sensor_type_raw(), 0);
+
+    assert_eq!(
+        bhi.sensor_info(SensorId::GeomagneticFieldWakeup),
+        Err(Error::InvalidState)
+    );
+    assert_eq!(
+        bhi.sensor_info(SensorId::MetaEvent),
+        Err(Error::Unsupported)
+    );
+}
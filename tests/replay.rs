@@ -0,0 +1,33 @@
+#![cfg(feature = "std")]
+
+use std::time::Duration;
+
+use bhi160::{
+    packet::SensorData,
+    parameters::sensors::SensorId,
+    replay::{parse_captures, Pacing, Replay},
+};
+
+const RECORDING: &str = "
+# Timestamp and the start of an accelerometer event
+@0
+FC 34 12
+01 FE FF
+@20000
+05 00 69 08 02 # rest of the event
+";
+
+#[test]
+fn replay_stitched_reads() {
+    let captures = parse_captures(RECORDING).unwrap();
+    assert_eq!(captures.len(), 2);
+    assert_eq!(captures[1].host_time, Duration::from_millis(20));
+
+    let mut replay = Replay::new(captures, Pacing::AsFastAsPossible);
+    let mut events = Vec::new();
+    assert_eq!(replay.run(|event| events.push(*event)), Ok(2));
+    assert_eq!(events[0].event.data(), &SensorData::Scalar(0x1234));
+    assert_eq!(events[1].event.id(), SensorId::Accelerometer);
+    assert_eq!(events[1].host_time, Duration::from_millis(20));
+    assert_eq!(events[1].ticks, 0x1234);
+}
@@ -0,0 +1,147 @@
+//! Feeds random and truncated FIFO data to all decoding paths.
+//!
+//! The data read from the bus may be corrupted, so decoding must report errors instead of panicking.
+#![cfg(feature = "std")]
+
+use std::io::Cursor;
+
+use bhi160::{
+    fixture::{load_dump, parse_dump},
+    packet::{decode_events, validate_fifo, EventReader, EventRefs, MetaEvent, MAX_EVENT_SIZE},
+    parameters::sensors::SensorId,
+    replay::{parse_captures, Capture, Pacing, Replay},
+};
+
+/// A small xorshift generator, so the tests are reproducible without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn byte(&mut self) -> u8 {
+        self.next() as u8
+    }
+
+    /// Random bytes which mostly start with a valid Sensor Type, so the decoders get past the first byte.
+    fn fifo(&mut self, len: usize) -> Vec<u8> {
+        let mut data = Vec::with_capacity(len);
+        while data.len() < len {
+            let id = SensorId::try_from(self.byte()).unwrap_or(SensorId::Accelerometer);
+            data.push(if self.below(8) == 0 {
+                self.byte()
+            } else {
+                id as u8
+            });
+            for _ in 1..id.event_size() {
+                data.push(self.byte());
+            }
+        }
+        data.truncate(len);
+        data
+    }
+}
+
+/// Runs all decoders on `data`, they may fail but must not panic.
+fn decode_all(data: &[u8]) {
+    if let Ok(len) = validate_fifo(data) {
+        assert!(len <= data.len());
+    }
+    if let Ok(consumed) = decode_events(data, |_| {}) {
+        assert!(consumed <= data.len());
+    }
+    let mut refs = EventRefs::new(data);
+    for event in refs.by_ref() {
+        let Ok(event) = event else { break };
+        assert!(event.bytes().len() <= MAX_EVENT_SIZE);
+        let _ = (
+            event.payload(),
+            event.vector(),
+            event.raw_vector(),
+            event.status(),
+            event.to_event(),
+        );
+    }
+    assert!(refs.consumed() <= data.len());
+    let mut reader = EventReader::new(Cursor::new(data));
+    for _ in reader.by_ref() {}
+    assert_eq!(reader.position() + reader.remaining().len(), data.len());
+}
+
+#[test]
+fn random_fifo_data() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..20_000 {
+        let len = rng.below(4 * MAX_EVENT_SIZE);
+        decode_all(&rng.fifo(len));
+    }
+}
+
+#[test]
+fn random_meta_events() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..10_000 {
+        MetaEvent::from_bytes([rng.byte(), rng.byte(), rng.byte()]);
+    }
+}
+
+#[test]
+fn truncated_and_corrupted_dump() {
+    let dump = load_dump(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/accel_meta.hex"
+    ))
+    .unwrap();
+    for len in 0..=dump.len() {
+        decode_all(&dump[..len]);
+        decode_all(&dump[len..]);
+    }
+    let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+    for _ in 0..5_000 {
+        let mut data = dump.clone();
+        let i = rng.below(data.len());
+        data[i] = rng.byte();
+        decode_all(&data);
+    }
+}
+
+#[test]
+fn random_replay_splits() {
+    let mut rng = Rng(0x94D0_49BB_1331_11EB);
+    for _ in 0..1_000 {
+        let len = rng.below(8 * MAX_EVENT_SIZE);
+        let data = rng.fifo(len);
+        let mut captures = Vec::new();
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let (read, tail) = rest.split_at(1 + rng.below(rest.len()));
+            captures.push(Capture {
+                host_time: Default::default(),
+                data: read.to_vec(),
+            });
+            rest = tail;
+        }
+        let _ = Replay::new(captures, Pacing::AsFastAsPossible).run(|_| {});
+    }
+}
+
+#[test]
+fn random_dump_text() {
+    const ALPHABET: &[u8] = b"0123456789abcdefxX@# \n\t-+";
+    let mut rng = Rng(0xBF58_476D_1CE4_E5B9);
+    for _ in 0..5_000 {
+        let text: String = (0..rng.below(64))
+            .map(|_| ALPHABET[rng.below(ALPHABET.len())] as char)
+            .collect();
+        let _ = parse_dump(&text);
+        let _ = parse_captures(&text);
+    }
+}
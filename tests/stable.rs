@@ -0,0 +1,39 @@
+//! The API without the `nightly` feature, run with `cargo +stable test --test stable`.
+#![cfg(not(feature = "nightly"))]
+
+mod mock;
+
+use bhi160::{
+    parameters::{sensors::SensorConfig, system::FifoControl, ParameterPage},
+    registers::{ChipStatus, HostInterfaceControl},
+    Bhi160,
+};
+use mock::MockBhi;
+
+#[test]
+fn inferred_sizes() {
+    let mut device = MockBhi::new();
+    device.regs[0x37] = ChipStatus::new().with_firmware_idle(true).into_bytes()[0];
+    device.params.insert((1, 2), vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut bhi = Bhi160::new(device);
+
+    let status: ChipStatus = bhi.read_reg().unwrap();
+    assert!(status.firmware_idle());
+    bhi.update_reg(|reg: HostInterfaceControl| reg.with_ap_suspended(true))
+        .unwrap();
+    assert_eq!(bhi.interface().regs[0x55], 0x20);
+
+    let fifo: FifoControl = bhi.read_param().unwrap();
+    assert_eq!(fifo.wakeup_size(), 0x0403);
+    let mut buf = [0; 8];
+    bhi.read_param_raw(ParameterPage::System, 2, &mut buf)
+        .unwrap();
+    assert_eq!(buf, [1, 2, 3, 4, 5, 6, 7, 8]);
+    bhi.write_param_raw(
+        ParameterPage::Sensors,
+        65,
+        &SensorConfig::new().with_sample_rate(50).into_bytes(),
+    )
+    .unwrap();
+    assert_eq!(bhi.interface().param_writes[0].window[..2], [50, 0]);
+}